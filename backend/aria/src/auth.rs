@@ -1,3 +1,5 @@
+use aria_core::AriaCore;
+use aria_models::local as lm;
 use chrono::{Duration, Utc};
 use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation, errors::ErrorKind};
 use serde::{Deserialize, Serialize, de::DeserializeOwned};
@@ -29,7 +31,17 @@ pub struct JwtClaims<C> {
 #[serde(tag = "level")]
 #[serde(rename_all = "lowercase")]
 pub enum AuthClaims {
-    Room { room_id: i32 },
+    Room {
+        room_id: i32,
+    },
+    Moderator {
+        room_id: i32,
+        moderator_id: i32,
+        permissions: Vec<lm::ModeratorPermission>,
+    },
+    Admin {
+        key_id: String,
+    },
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -95,9 +107,62 @@ impl<C> JwtClaims<C> {
 }
 
 impl AuthClaims {
+    /// Check whether the claims grant full control of a room.
+    /// Instance administrators have full control of all rooms.
     pub fn for_room(&self, p_room_id: i32) -> bool {
-        let AuthClaims::Room { room_id } = self;
+        match self {
+            AuthClaims::Room { room_id } => *room_id == p_room_id,
+            AuthClaims::Moderator { .. } => false,
+            AuthClaims::Admin { .. } => true,
+        }
+    }
+
+    /// Check whether the claims allow performing a moderation action in a room.
+    /// Room owners and instance administrators can perform all moderation actions.
+    pub fn can(&self, p_room_id: i32, permission: lm::ModeratorPermission) -> bool {
+        match self {
+            AuthClaims::Room { room_id } => *room_id == p_room_id,
+            AuthClaims::Moderator {
+                room_id, permissions, ..
+            } => *room_id == p_room_id && permissions.contains(&permission),
+            AuthClaims::Admin { .. } => true,
+        }
+    }
 
-        *room_id == p_room_id
+    /// Room the claims are tied to, if any
+    pub fn room_id(&self) -> Option<i32> {
+        match self {
+            AuthClaims::Room { room_id } => Some(*room_id),
+            AuthClaims::Moderator { room_id, .. } => Some(*room_id),
+            AuthClaims::Admin { .. } => None,
+        }
+    }
+
+    pub fn is_admin(&self) -> bool {
+        matches!(self, AuthClaims::Admin { .. })
+    }
+
+    /// Check the claims against the current configuration and moderators,
+    /// since they may have changed after the token was issued.
+    /// Moderator claims get the current permissions of the moderator.
+    /// Returns None if the claims are no longer valid.
+    pub async fn revalidate(self, core: &AriaCore) -> Result<Option<Self>, anyhow::Error> {
+        match self {
+            AuthClaims::Room { .. } => Ok(Some(self)),
+            AuthClaims::Moderator {
+                room_id, moderator_id, ..
+            } => {
+                let moderator = core.get_moderator(room_id, moderator_id).await?;
+
+                Ok(moderator.map(|m| AuthClaims::Moderator {
+                    room_id,
+                    moderator_id,
+                    permissions: m.permissions,
+                }))
+            }
+            AuthClaims::Admin { key_id } => {
+                Ok(core.is_admin_key_valid(&key_id).then_some(AuthClaims::Admin { key_id }))
+            }
+        }
     }
 }
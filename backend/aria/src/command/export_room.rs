@@ -0,0 +1,26 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+use aria_core::AriaCore;
+use tracing::info;
+
+pub async fn export_room(core: AriaCore, name: &str, output: Option<PathBuf>) -> Result<(), anyhow::Error> {
+    let room = core
+        .get_room_by_name(name)
+        .await?
+        .with_context(|| format!("Room '{name}' does not exist"))?;
+
+    let output = output.unwrap_or_else(|| PathBuf::from(format!("{name}.zip")));
+
+    info!("Exporting room '{name}' to '{}'...", output.display());
+
+    let archive = core.export_room(room.id, &output).await?;
+
+    info!(
+        "Exported {} posts and {} emotes from room '{name}'.",
+        archive.post_count, archive.emote_count
+    );
+
+    Ok(())
+}
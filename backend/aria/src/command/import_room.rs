@@ -0,0 +1,18 @@
+use std::path::Path;
+
+use aria_core::AriaCore;
+use tracing::info;
+
+pub async fn import_room(core: AriaCore, archive: &Path, name: Option<&str>) -> Result<(), anyhow::Error> {
+    info!("Importing room from '{}'...", archive.display());
+
+    let result = core.import_room(archive, name).await?;
+
+    info!(
+        "Imported {} posts and {} emotes into room '{}'.",
+        result.post_count, result.emote_count, result.room.name
+    );
+    info!("Room password: {}", result.room.password);
+
+    Ok(())
+}
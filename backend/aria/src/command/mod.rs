@@ -1,9 +1,17 @@
+mod export_room;
+mod import_room;
 mod process_images;
 mod regenerate_emote_images;
 mod regenerate_post_images;
+mod reset_room_password;
 mod server;
+mod websocket_schema;
 
+pub(crate) use self::export_room::*;
+pub(crate) use self::import_room::*;
 pub(crate) use self::process_images::*;
 pub(crate) use self::regenerate_emote_images::*;
 pub(crate) use self::regenerate_post_images::*;
+pub(crate) use self::reset_room_password::*;
 pub(crate) use self::server::*;
+pub(crate) use self::websocket_schema::*;
@@ -0,0 +1,18 @@
+use anyhow::Context;
+
+use aria_core::AriaCore;
+use tracing::info;
+
+pub async fn reset_room_password(core: AriaCore, name: &str) -> Result<(), anyhow::Error> {
+    let room = core
+        .get_room_by_name(name)
+        .await?
+        .with_context(|| format!("Room '{name}' does not exist"))?;
+
+    let password = core.reset_room_password(room.id).await?;
+
+    info!("Password for room '{name}' has been reset. All sessions for the room have been logged out.");
+    info!("New password: {password}");
+
+    Ok(())
+}
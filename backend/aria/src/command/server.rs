@@ -4,7 +4,7 @@ use anyhow::Context;
 
 use aria_core::AriaCore;
 
-use crate::{auth::AriaAuth, server::AriaServer, websocket_server};
+use crate::{auth::AriaAuth, server::AriaServer, sweeper, websocket_server::WebSocketServer};
 
 pub async fn server(core: AriaCore, serve_files: bool) -> Result<(), anyhow::Error> {
     let jwt_secret = core
@@ -13,22 +13,24 @@ pub async fn server(core: AriaCore, serve_files: bool) -> Result<(), anyhow::Err
         .as_ref()
         .context("No JWT secret set in configuration")?;
 
+    core.clear_room_expiry_if_disabled().await?;
+
     let auth = Arc::new(AriaAuth::new(jwt_secret.as_bytes()));
     let core = Arc::new(core);
 
-    let server = AriaServer::new(auth.clone(), core.clone(), serve_files);
+    let (websocket, websocket_shutdown) = WebSocketServer::new(auth.clone(), core.clone());
+    let server = AriaServer::new(auth.clone(), core.clone(), websocket, serve_files);
 
     let shutdown = || async {
         tokio::signal::ctrl_c().await.expect("Error awaiting Ctrl-C signal");
     };
 
-    let http_server = server.run_server(shutdown());
-    let ws_server = websocket_server::run_server(auth.clone(), core.clone(), shutdown());
-
-    let (http_result, ws_result) = tokio::join!(http_server, ws_server);
+    let http_server = server.run_server(shutdown);
+    let ws_server = websocket_shutdown.run(shutdown());
+    let room_sweeper = sweeper::run_room_sweeper(core.clone(), shutdown());
 
-    http_result?;
-    ws_result?;
+    // Stop everything if any of them fails, such as when unable to listen on an address
+    tokio::try_join!(http_server, ws_server, room_sweeper)?;
 
     Ok(())
 }
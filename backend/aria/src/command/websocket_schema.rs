@@ -0,0 +1,17 @@
+use std::path::Path;
+
+use aria_models::ws;
+use tracing::info;
+
+pub fn websocket_schema(output: Option<&Path>) -> Result<(), anyhow::Error> {
+    let schema = serde_json::to_string_pretty(&ws::ProtocolSchema::generate())?;
+
+    if let Some(output) = output {
+        std::fs::write(output, schema)?;
+        info!("WebSocket protocol schema written to '{}'.", output.display());
+    } else {
+        println!("{schema}");
+    }
+
+    Ok(())
+}
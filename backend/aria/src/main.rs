@@ -1,4 +1,5 @@
 use std::env;
+use std::path::PathBuf;
 
 use clap::Parser;
 use tracing::{debug, info};
@@ -8,7 +9,9 @@ use aria_core::{AriaCore, config::AriaConfig};
 
 mod auth;
 mod command;
+mod rate_limit;
 mod server;
+mod sweeper;
 mod websocket_server;
 
 #[derive(Debug, Parser)]
@@ -17,6 +20,12 @@ struct Opt {
     #[clap(long = "migrate", help = "Run database migration on startup")]
     migrate: bool,
 
+    #[clap(
+        long = "memory-store",
+        help = "Keep all data in memory instead of a database (for development; nothing is persisted)"
+    )]
+    memory_store: bool,
+
     #[clap(subcommand)]
     command: Command,
 }
@@ -45,6 +54,30 @@ enum ToolCommand {
     RegeneratePostImages,
     #[clap(about = "Regenerate emote images from original files")]
     RegenerateEmoteImages,
+    #[clap(about = "Reset a room's password and log out all of its sessions")]
+    ResetRoomPassword {
+        #[clap(help = "Name of the room")]
+        name: String,
+    },
+    #[clap(about = "Export a room's posts, emotes and their files as a ZIP archive")]
+    ExportRoom {
+        #[clap(help = "Name of the room")]
+        name: String,
+        #[clap(short = 'o', long = "output", help = "Archive file to write (default: <name>.zip)")]
+        output: Option<PathBuf>,
+    },
+    #[clap(about = "Import a room from an archive created by export-room")]
+    ImportRoom {
+        #[clap(help = "Archive file, or directory it has been extracted to")]
+        archive: PathBuf,
+        #[clap(long = "name", help = "Name of the new room (default: name stored in the archive)")]
+        name: Option<String>,
+    },
+    #[clap(about = "Write the JSON schema of the WebSocket protocol messages")]
+    WebsocketSchema {
+        #[clap(short = 'o', long = "output", help = "File to write (default: standard output)")]
+        output: Option<PathBuf>,
+    },
 }
 
 #[tokio::main]
@@ -68,7 +101,12 @@ async fn main() -> Result<(), anyhow::Error> {
         config.files_path = Some(v.into());
     }
 
-    let core = AriaCore::new(config)?;
+    let core = if opt.memory_store {
+        info!("Using in-memory store. No data will be persisted.");
+        AriaCore::new_in_memory(config)?
+    } else {
+        AriaCore::new(config)?
+    };
 
     if opt.migrate {
         info!("Running database migrations...");
@@ -81,6 +119,10 @@ async fn main() -> Result<(), anyhow::Error> {
             ToolCommand::ProcessImages => command::process_images(core).await?,
             ToolCommand::RegeneratePostImages => command::regenerate_post_images(core).await?,
             ToolCommand::RegenerateEmoteImages => command::regenerate_emote_images(core).await?,
+            ToolCommand::ResetRoomPassword { name } => command::reset_room_password(core, &name).await?,
+            ToolCommand::ExportRoom { name, output } => command::export_room(core, &name, output).await?,
+            ToolCommand::ImportRoom { archive, name } => command::import_room(core, &archive, name.as_deref()).await?,
+            ToolCommand::WebsocketSchema { output } => command::websocket_schema(output.as_deref())?,
         },
     };
 
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use aria_core::config::RateLimit;
+
+/// Number of checks between each pruning of idle buckets
+const PRUNE_INTERVAL: u32 = 1024;
+
+pub const DEFAULT_POST_RATE_LIMIT: RateLimit = RateLimit {
+    burst: 5,
+    per_minute: 20,
+};
+
+pub const DEFAULT_IMAGE_POST_RATE_LIMIT: RateLimit = RateLimit {
+    burst: 3,
+    per_minute: 6,
+};
+
+pub const DEFAULT_REACTION_RATE_LIMIT: RateLimit = RateLimit {
+    burst: 10,
+    per_minute: 60,
+};
+
+pub const DEFAULT_WEBSOCKET_MESSAGE_RATE_LIMIT: RateLimit = RateLimit {
+    burst: 50,
+    per_minute: 600,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum RateLimitKey {
+    User(i64),
+    Ip(IpAddr),
+}
+
+/// Token bucket rate limiter with one bucket per key
+pub struct RateLimiter<K> {
+    limit: RateLimit,
+    state: Mutex<RateLimiterState<K>>,
+}
+
+struct RateLimiterState<K> {
+    buckets: HashMap<K, Bucket>,
+    checks_since_prune: u32,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl<K: Copy + Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Mutex::new(RateLimiterState {
+                buckets: HashMap::new(),
+                checks_since_prune: 0,
+            }),
+        }
+    }
+
+    /// Take a token from the bucket of every specified key.
+    /// If any of the buckets is empty, no tokens are taken and the time
+    /// until a token becomes available in all of them is returned.
+    pub fn check(&self, keys: &[K]) -> Result<(), Duration> {
+        if self.limit.burst == 0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let capacity = self.limit.burst as f64;
+        let refill_rate = self.limit.per_minute as f64 / 60.0;
+
+        let mut state = self.state.lock().unwrap();
+
+        state.checks_since_prune += 1;
+        if state.checks_since_prune >= PRUNE_INTERVAL {
+            state.checks_since_prune = 0;
+
+            // Buckets that would have refilled completely are equivalent to new ones
+            state
+                .buckets
+                .retain(|_, b| b.tokens + now.duration_since(b.updated_at).as_secs_f64() * refill_rate < capacity);
+        }
+
+        let mut wait = Duration::ZERO;
+
+        for key in keys {
+            let bucket = state.buckets.entry(*key).or_insert(Bucket {
+                tokens: capacity,
+                updated_at: now,
+            });
+
+            bucket.tokens =
+                (bucket.tokens + now.duration_since(bucket.updated_at).as_secs_f64() * refill_rate).min(capacity);
+            bucket.updated_at = now;
+
+            if bucket.tokens < 1.0 {
+                let key_wait = if refill_rate > 0.0 {
+                    Duration::from_secs_f64((1.0 - bucket.tokens) / refill_rate)
+                } else {
+                    Duration::MAX
+                };
+
+                wait = wait.max(key_wait);
+            }
+        }
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+
+        for key in keys {
+            if let Some(bucket) = state.buckets.get_mut(key) {
+                bucket.tokens -= 1.0;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Arc;
+
+use aria_models::api as am;
+use axum::body::Body;
+use axum::extract::{Path, State};
+use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::response::IntoResponse;
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Serialize;
+use tokio_util::io::ReaderStream;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::server::AriaServer;
+use crate::server::api::{ApiError, Authorized};
+
+#[derive(Debug, Serialize)]
+struct PurgePostsResponse {
+    pub deleted: usize,
+}
+
+pub fn router() -> Router<Arc<AriaServer>> {
+    Router::new()
+        .route("/rooms", get(get_rooms))
+        .route("/rooms/{room_id}", delete(delete_room))
+        .route("/rooms/{room_id}/purge", post(purge_posts))
+        .route("/rooms/{room_id}/export", get(export_room))
+        .route("/stats", get(get_stats))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_rooms(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+) -> Result<Json<Vec<am::RoomSummary>>, ApiError> {
+    if !auth.is_admin() {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let rooms = server.core.get_rooms().await?;
+
+    Ok(Json(rooms))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn delete_room(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<(), ApiError> {
+    if !auth.is_admin() {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let success = server.core.delete_room(room_id).await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn purge_posts(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<Json<PurgePostsResponse>, ApiError> {
+    if !auth.is_admin() {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let deleted = server.core.purge_room_posts(room_id).await?;
+
+    Ok(Json(PurgePostsResponse { deleted }))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn export_room(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<impl IntoResponse, ApiError> {
+    if !auth.is_admin() {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let Some(room) = server.core.get_room(room_id).await? else {
+        return Err(ApiError::NotFound);
+    };
+
+    let path = server.core.temp_path.join(format!("{}.zip", Uuid::new_v4()));
+
+    let result = server.core.export_room(room_id, &path).await;
+    let file = match result {
+        Ok(_) => tokio::fs::File::open(&path).await.map_err(anyhow::Error::from),
+        Err(err) => Err(err),
+    };
+
+    // The open file remains readable after its path has been removed
+    tokio::fs::remove_file(&path)
+        .await
+        .map_err(|err| warn!("Error removing temporary archive '{}': {err}", path.display()))
+        .ok();
+
+    let file = file?;
+
+    let filename: String = room
+        .name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+
+    Ok((
+        [
+            (CONTENT_TYPE, "application/zip".to_owned()),
+            (CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}.zip\"")),
+        ],
+        Body::from_stream(ReaderStream::new(file)),
+    ))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_stats(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+) -> Result<Json<am::InstanceStats>, ApiError> {
+    if !auth.is_admin() {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let stats = server.core.get_instance_stats().await?;
+
+    Ok(Json(stats))
+}
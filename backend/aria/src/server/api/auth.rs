@@ -15,6 +15,8 @@ use crate::server::api::ApiError;
 #[serde(rename_all = "lowercase")]
 enum LoginRequest {
     Room { room_id: i32, password: String },
+    Moderator { room_id: i32, name: String, key: String },
+    Admin { key: String },
 }
 
 #[derive(Debug, Serialize)]
@@ -29,10 +31,31 @@ struct RefreshRequest {
     pub refresh_token: Uuid,
 }
 
+#[derive(Debug, Deserialize)]
+struct LogoutRequest {
+    pub refresh_token: Uuid,
+}
+
 pub fn router() -> Router<Arc<AriaServer>> {
     Router::new()
         .route("/login", post(login))
         .route("/refresh", post(refresh))
+        .route("/logout", post(logout))
+}
+
+/// Create a new refresh token family and access token for the specified claims
+pub(super) async fn issue_tokens(server: &AriaServer, claims: AuthClaims) -> Result<LoginResponse, ApiError> {
+    let refresh_token = server.core.create_refresh_token(claims.room_id(), &claims).await?;
+
+    let claims = JwtClaims::short(claims);
+    let exp = claims.exp;
+    let access_token = server.auth.generate_token(&claims)?;
+
+    Ok(LoginResponse {
+        access_token,
+        exp,
+        refresh_token,
+    })
 }
 
 #[axum::debug_handler(state = Arc<AriaServer>)]
@@ -40,25 +63,35 @@ async fn login(
     State(server): State<Arc<AriaServer>>,
     Json(req): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, ApiError> {
-    let LoginRequest::Room { room_id, password } = req;
+    let claims = match req {
+        LoginRequest::Room { room_id, password } => {
+            if !server.core.login(room_id, &password).await? {
+                return Err(ApiError::Unauthorized);
+            }
 
-    if !server.core.login(room_id, &password).await? {
-        return Err(ApiError::Unauthorized);
-    }
+            AuthClaims::Room { room_id }
+        }
+        LoginRequest::Moderator { room_id, name, key } => {
+            let Some(moderator) = server.core.moderator_login(room_id, &name, &key).await? else {
+                return Err(ApiError::Unauthorized);
+            };
 
-    let claims = AuthClaims::Room { room_id };
+            AuthClaims::Moderator {
+                room_id,
+                moderator_id: moderator.id,
+                permissions: moderator.permissions,
+            }
+        }
+        LoginRequest::Admin { key } => {
+            let Some(key_id) = server.core.admin_login(&key) else {
+                return Err(ApiError::Unauthorized);
+            };
 
-    let refresh_token = server.core.create_refresh_token(&claims).await?;
+            AuthClaims::Admin { key_id }
+        }
+    };
 
-    let claims = JwtClaims::short(claims);
-    let exp = claims.exp;
-    let access_token = server.auth.generate_token(&claims)?;
-
-    Ok(Json(LoginResponse {
-        access_token,
-        exp,
-        refresh_token,
-    }))
+    Ok(Json(issue_tokens(&server, claims).await?))
 }
 
 #[axum::debug_handler(state = Arc<AriaServer>)]
@@ -71,6 +104,14 @@ async fn refresh(
         .refresh_refresh_token::<AuthClaims>(req.refresh_token)
         .await?
     {
+        if let AuthClaims::Admin { key_id } = &result.claims
+            && !server.core.is_admin_key_valid(key_id)
+        {
+            server.core.revoke_refresh_token_family(result.token).await?;
+
+            return Err(ApiError::Unauthorized);
+        }
+
         let claims = JwtClaims::short(result.claims);
         let access_token = server.auth.generate_token(&claims)?;
         let exp = claims.exp;
@@ -85,3 +126,10 @@ async fn refresh(
 
     Err(ApiError::Unauthorized)
 }
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn logout(State(server): State<Arc<AriaServer>>, Json(req): Json<LogoutRequest>) -> Result<(), ApiError> {
+    server.core.revoke_refresh_token_family(req.refresh_token).await?;
+
+    Ok(())
+}
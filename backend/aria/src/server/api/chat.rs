@@ -3,27 +3,62 @@ use std::sync::Arc;
 use anyhow::Context;
 use axum::{
     Json, Router,
-    extract::{DefaultBodyLimit, Multipart, Path, State},
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
     handler::Handler,
     http::StatusCode,
-    routing::{delete, post},
+    routing::{delete, get, post, put},
 };
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 
+use aria_core::MAX_NAME_LENGTH;
+use aria_models::api as am;
 use aria_models::local as lm;
 use axum_client_ip::ClientIp;
 
+use crate::rate_limit::RateLimitKey;
 use crate::server::{
     AriaServer,
     api::{ApiError, Authorized, User},
 };
 
+#[derive(Debug, Deserialize)]
+struct GetPostsQuery {
+    before: Option<i64>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EditPostRequest {
+    comment: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchPostsQuery {
+    q: Option<String>,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+    has_image: Option<bool>,
+    admin: Option<bool>,
+    user_id: Option<i64>,
+    before: Option<i64>,
+    limit: Option<u32>,
+}
+
 pub fn router(sys_config: &lm::SysConfig) -> Router<Arc<AriaServer>> {
     Router::new()
+        .route("/{room_id}/posts", get(get_posts))
+        .route("/{room_id}/search", get(search_posts))
         .route(
             "/{room_id}/post",
             post(create_post.layer(DefaultBodyLimit::max(sys_config.max_image_size))),
         )
-        .route("/{room_id}/post/{post_id}", delete(delete_post))
+        .route("/{room_id}/post/{post_id}", delete(delete_post).patch(edit_post))
+        .route("/{room_id}/post/{post_id}/revisions", get(get_post_revisions))
+        .route(
+            "/{room_id}/post/{post_id}/reaction/{reaction}",
+            put(add_post_reaction).delete(remove_post_reaction),
+        )
         .route(
             "/{room_id}/emote",
             post(create_emote.layer(DefaultBodyLimit::max(sys_config.max_emote_size))),
@@ -31,6 +66,71 @@ pub fn router(sys_config: &lm::SysConfig) -> Router<Arc<AriaServer>> {
         .route("/{room_id}/emote/{emote_id}", delete(delete_emote))
 }
 
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_posts(
+    user: Option<User>,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Query(query): Query<GetPostsQuery>,
+) -> Result<Json<Vec<am::Post>>, ApiError> {
+    let posts = server.core.get_posts(room_id, query.before, query.limit).await?;
+
+    let posts = posts
+        .iter()
+        .map(|p| {
+            let mut post = am::Post::from(p);
+            post.you = user.as_ref().is_some_and(|u| u.id == p.user_id);
+            post.replies_to_you = user.as_ref().is_some_and(|u| p.is_reply_to_user(u.id));
+            post.reactions = p.reactions_for_user(user.as_ref().map(|u| u.id));
+
+            post
+        })
+        .collect();
+
+    Ok(Json(posts))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn search_posts(
+    user: Option<User>,
+    auth: Option<Authorized>,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Query(query): Query<SearchPostsQuery>,
+) -> Result<Json<Vec<am::Post>>, ApiError> {
+    // Only moderators who can ban users may look up posts by who posted them
+    if query.user_id.is_some() && !auth.is_some_and(|a| a.can(room_id, lm::ModeratorPermission::BanUsers)) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let search = lm::PostSearch {
+        query: query.q,
+        from: query.from,
+        to: query.to,
+        has_image: query.has_image,
+        admin: query.admin,
+        user_id: query.user_id,
+        before_id: query.before,
+        limit: query.limit,
+    };
+
+    let posts = server.core.search_posts(room_id, search).await?;
+
+    let posts = posts
+        .iter()
+        .map(|p| {
+            let mut post = am::Post::from(p);
+            post.you = user.as_ref().is_some_and(|u| u.id == p.user_id);
+            post.replies_to_you = user.as_ref().is_some_and(|u| p.is_reply_to_user(u.id));
+            post.reactions = p.reactions_for_user(user.as_ref().map(|u| u.id));
+
+            post
+        })
+        .collect();
+
+    Ok(Json(posts))
+}
+
 #[axum::debug_handler(state = Arc<AriaServer>)]
 async fn create_post(
     user: User,
@@ -56,7 +156,14 @@ async fn create_post(
 
         match field_name {
             "name" => {
-                name = Some(field.text().await.map_err(|err| ApiError::Anyhow(err.into()))?);
+                let value = field.text().await.map_err(|err| ApiError::Anyhow(err.into()))?;
+
+                // Reject overly long names before any image is received
+                if value.chars().count() > MAX_NAME_LENGTH {
+                    return Err(ApiError::BadRequest);
+                }
+
+                name = Some(value);
             }
             "comment" => {
                 comment = Some(field.text().await.map_err(|err| ApiError::Anyhow(err.into()))?);
@@ -71,6 +178,11 @@ async fn create_post(
                 }
             }
             "image" => {
+                server
+                    .image_post_limiter
+                    .check(&[RateLimitKey::User(user.id), RateLimitKey::Ip(ip)])
+                    .map_err(ApiError::TooManyRequests)?;
+
                 let filename = field
                     .file_name()
                     .ok_or_else(|| anyhow::anyhow!("Image has no filename."))?
@@ -93,6 +205,14 @@ async fn create_post(
         }
     }
 
+    // Image posts are limited separately, as soon as the image is encountered
+    if image.is_none() {
+        server
+            .post_limiter
+            .check(&[RateLimitKey::User(user.id), RateLimitKey::Ip(ip)])
+            .map_err(ApiError::TooManyRequests)?;
+    }
+
     let new_post = lm::NewPost {
         name: name
             .and_then(|v| if v.is_empty() { None } else { Some(v) })
@@ -116,7 +236,9 @@ async fn delete_post(
     State(server): State<Arc<AriaServer>>,
     Path((room_id, post_id)): Path<(i32, i64)>,
 ) -> Result<(), ApiError> {
-    let is_admin = auth.map(|a| a.for_room(room_id)).unwrap_or(false);
+    let is_admin = auth
+        .map(|a| a.can(room_id, lm::ModeratorPermission::DeletePosts))
+        .unwrap_or(false);
 
     let success = server.core.delete_post(room_id, post_id, user.id, is_admin).await?;
 
@@ -127,6 +249,113 @@ async fn delete_post(
     Ok(())
 }
 
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn edit_post(
+    user: User,
+    auth: Option<Authorized>,
+    State(server): State<Arc<AriaServer>>,
+    ClientIp(ip): ClientIp,
+    Path((room_id, post_id)): Path<(i32, i64)>,
+    Json(request): Json<EditPostRequest>,
+) -> Result<Json<am::Post>, ApiError> {
+    // Don't allow blank comment
+    if request.comment.trim().is_empty() {
+        return Err(ApiError::BadRequest);
+    }
+
+    server
+        .post_limiter
+        .check(&[RateLimitKey::User(user.id), RateLimitKey::Ip(ip)])
+        .map_err(ApiError::TooManyRequests)?;
+
+    let is_admin = auth.map(|a| a.for_room(room_id)).unwrap_or(false);
+
+    let post = server
+        .core
+        .edit_post(room_id, post_id, user.id, ip, is_admin, Some(&request.comment))
+        .await?
+        .ok_or(ApiError::NotFound)?;
+
+    let mut post_am = am::Post::from(&post);
+    post_am.you = user.id == post.user_id;
+    post_am.replies_to_you = post.is_reply_to_user(user.id);
+    post_am.reactions = post.reactions_for_user(Some(user.id));
+
+    Ok(Json(post_am))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_post_revisions(
+    user: User,
+    auth: Option<Authorized>,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, post_id)): Path<(i32, i64)>,
+) -> Result<Json<Vec<am::PostRevision>>, ApiError> {
+    let is_admin = auth.map(|a| a.for_room(room_id)).unwrap_or(false);
+
+    let revisions = server
+        .core
+        .get_post_revisions(room_id, post_id, user.id, is_admin)
+        .await?;
+
+    Ok(Json(revisions))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn add_post_reaction(
+    user: User,
+    State(server): State<Arc<AriaServer>>,
+    ClientIp(ip): ClientIp,
+    Path((room_id, post_id, reaction)): Path<(i32, i64, String)>,
+) -> Result<(), ApiError> {
+    let reaction = server
+        .core
+        .normalize_reaction(room_id, &reaction)
+        .await?
+        .ok_or(ApiError::BadRequest)?;
+
+    server
+        .reaction_limiter
+        .check(&[RateLimitKey::User(user.id), RateLimitKey::Ip(ip)])
+        .map_err(ApiError::TooManyRequests)?;
+
+    let success = server
+        .core
+        .add_post_reaction(room_id, post_id, user.id, ip, &reaction)
+        .await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn remove_post_reaction(
+    user: User,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, post_id, reaction)): Path<(i32, i64, String)>,
+) -> Result<(), ApiError> {
+    // Reactions with emotes that have since been deleted can still be removed
+    let reaction = server
+        .core
+        .normalize_reaction(room_id, &reaction)
+        .await?
+        .unwrap_or(reaction);
+
+    let success = server
+        .core
+        .remove_post_reaction(room_id, post_id, user.id, &reaction)
+        .await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
 #[axum::debug_handler(state = Arc<AriaServer>)]
 async fn create_emote(
     auth: Authorized,
@@ -1,3 +1,4 @@
+mod admin;
 mod auth;
 mod chat;
 mod room;
@@ -5,11 +6,12 @@ mod sys;
 mod user;
 
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::{
-    RequestPartsExt, Router,
+    Json, RequestPartsExt, Router,
     extract::{FromRequestParts, OptionalFromRequestParts},
-    http::{StatusCode, request::Parts},
+    http::{StatusCode, header::RETRY_AFTER, request::Parts},
     response::{IntoResponse, Response},
 };
 use axum_extra::{
@@ -19,6 +21,8 @@ use axum_extra::{
 use thiserror::Error;
 use tracing::error;
 
+use aria_core::Banned;
+use aria_models::api as am;
 use aria_models::local as lm;
 
 use crate::auth::{AuthClaims, AuthError, UserClaims};
@@ -37,6 +41,8 @@ enum ApiError {
     NotFound,
     #[error("Unauthorized")]
     Unauthorized,
+    #[error("Too many requests")]
+    TooManyRequests(Duration),
 }
 
 #[derive(Debug)]
@@ -50,6 +56,7 @@ struct User {
 }
 
 pub fn router(config: &lm::SysConfig) -> Router<Arc<AriaServer>> {
+    let admin = admin::router();
     let auth = auth::router();
     let sys = sys::router();
     let room = room::router();
@@ -57,6 +64,7 @@ pub fn router(config: &lm::SysConfig) -> Router<Arc<AriaServer>> {
     let user = user::router();
 
     Router::new()
+        .nest("/admin", admin)
         .nest("/auth", auth)
         .nest("/sys", sys)
         .nest("/r", room)
@@ -79,6 +87,11 @@ impl IntoResponse for AuthError {
 impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         match self {
+            Self::Anyhow(err) if err.is::<Banned>() => {
+                let Banned(ban) = err.downcast_ref::<Banned>().unwrap();
+
+                (StatusCode::FORBIDDEN, Json(am::Ban::from(ban))).into_response()
+            }
             Self::Anyhow(err) => {
                 error!("{err:#}");
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("{err:#}")).into_response()
@@ -88,32 +101,40 @@ impl IntoResponse for ApiError {
             Self::BadRequest => (StatusCode::BAD_REQUEST, ()).into_response(),
             Self::NotFound => (StatusCode::NOT_FOUND, ()).into_response(),
             Self::Unauthorized => (StatusCode::UNAUTHORIZED, ()).into_response(),
+            Self::TooManyRequests(retry_after) => {
+                // Round up, so that the client does not retry before a token is available
+                let retry_after = retry_after
+                    .as_secs()
+                    .saturating_add(u64::from(retry_after.subsec_nanos() > 0));
+
+                (StatusCode::TOO_MANY_REQUESTS, [(RETRY_AFTER, retry_after.to_string())]).into_response()
+            }
         }
     }
 }
 
 impl FromRequestParts<Arc<AriaServer>> for Authorized {
-    type Rejection = AuthError;
+    type Rejection = ApiError;
 
     async fn from_request_parts(parts: &mut Parts, state: &Arc<AriaServer>) -> Result<Self, Self::Rejection> {
         let authorized = Option::<Authorized>::from_request_parts(parts, state).await;
 
         match authorized {
-            Ok(v) => v.ok_or(AuthError::InvalidToken),
+            Ok(v) => v.ok_or(AuthError::InvalidToken.into()),
             Err(err) => Err(err),
         }
     }
 }
 
 impl OptionalFromRequestParts<Arc<AriaServer>> for Authorized {
-    type Rejection = AuthError;
+    type Rejection = ApiError;
 
     async fn from_request_parts(parts: &mut Parts, state: &Arc<AriaServer>) -> Result<Option<Self>, Self::Rejection> {
         // Extract the token from the authorization header
         let auth_header = parts
             .extract::<Option<TypedHeader<Authorization<Bearer>>>>()
             .await
-            .map_err(|_| AuthError::InvalidToken)?;
+            .map_err(|_| ApiError::AuthError(AuthError::InvalidToken))?;
 
         let Some(TypedHeader(Authorization(bearer))) = auth_header else {
             return Ok(None);
@@ -123,6 +144,12 @@ impl OptionalFromRequestParts<Arc<AriaServer>> for Authorized {
 
         let claims = state.auth.verify::<AuthClaims>(token)?;
 
+        // Reject claims for admin keys and moderators that have since been removed,
+        // and use the current permissions of moderators
+        let Some(claims) = claims.revalidate(&state.core).await? else {
+            return Err(AuthError::InvalidToken.into());
+        };
+
         Ok(Some(Authorized { claims }))
     }
 }
@@ -131,19 +158,28 @@ impl FromRequestParts<Arc<AriaServer>> for User {
     type Rejection = StatusCode;
 
     async fn from_request_parts(parts: &mut Parts, state: &Arc<AriaServer>) -> Result<Self, Self::Rejection> {
-        let token = parts
-            .headers
-            .get("X-User")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.to_owned())
-            .ok_or(StatusCode::UNAUTHORIZED)?;
+        let user = Option::<User>::from_request_parts(parts, state).await?;
+
+        user.ok_or(StatusCode::UNAUTHORIZED)
+    }
+}
+
+impl OptionalFromRequestParts<Arc<AriaServer>> for User {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AriaServer>) -> Result<Option<Self>, Self::Rejection> {
+        let Some(token) = parts.headers.get("X-User") else {
+            return Ok(None);
+        };
+
+        let token = token.to_str().map_err(|_| StatusCode::UNAUTHORIZED)?;
 
         let claims = state
             .auth
-            .verify::<UserClaims>(&token)
+            .verify::<UserClaims>(token)
             .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
-        Ok(User { id: claims.user_id })
+        Ok(Some(User { id: claims.user_id }))
     }
 }
 
@@ -151,4 +187,12 @@ impl Authorized {
     pub fn for_room(&self, room_id: i32) -> bool {
         self.claims.for_room(room_id)
     }
+
+    pub fn can(&self, room_id: i32, permission: lm::ModeratorPermission) -> bool {
+        self.claims.can(room_id, permission)
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.claims.is_admin()
+    }
 }
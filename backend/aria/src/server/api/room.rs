@@ -2,15 +2,18 @@ use std::sync::Arc;
 
 use aria_models::api as am;
 use axum::extract::{Path, State};
-use axum::routing::{get, post};
+use axum::routing::{delete, get, post};
 use axum::{Json, Router};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::auth::{AuthClaims, JwtClaims};
+use crate::auth::AuthClaims;
 use crate::server::AriaServer;
 use crate::server::api::{ApiError, Authorized};
 
-use super::auth::LoginResponse;
+use super::auth::{LoginResponse, issue_tokens};
+
+const MIN_PASSWORD_LENGTH: usize = 6;
 
 #[derive(Debug, Deserialize)]
 struct ClaimRequest {
@@ -32,12 +35,74 @@ struct SetContentRequest {
     pub is_livestream: Option<bool>,
 }
 
+#[derive(Debug, Serialize)]
+struct SessionResponse {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub refreshed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub claims: AuthClaims,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangePasswordRequest {
+    pub password: String,
+    /// Required unless the caller is an instance administrator
+    pub current_password: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateModeratorRequest {
+    pub name: String,
+    pub permissions: Vec<am::ModeratorPermission>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateModeratorResponse {
+    pub moderator: am::Moderator,
+    pub key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateBanRequest {
+    pub post_id: i64,
+    pub reason: Option<String>,
+    /// Ban duration in seconds. If not specified, the ban is permanent.
+    pub duration: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SetEndBehaviorRequest {
+    pub end_behavior: am::ContentEndBehavior,
+}
+
+#[derive(Debug, Deserialize)]
+struct MoveQueueItemRequest {
+    pub position: i32,
+}
+
 pub fn router() -> Router<Arc<AriaServer>> {
     Router::new()
         .route("/room/{name}", get(get_room))
+        .route("/room/{name}/presence", get(get_presence))
         .route("/claim", post(claim))
         .route("/i/{room_id}/loggedin", post(logged_in))
+        .route("/i/{room_id}/password", post(change_password))
+        .route("/i/{room_id}/sessions", get(get_sessions))
+        .route("/i/{room_id}/sessions/{session_id}", delete(revoke_session))
+        .route("/i/{room_id}/bans", get(get_bans).post(create_ban))
+        .route("/i/{room_id}/bans/{ban_id}", delete(delete_ban))
+        .route("/i/{room_id}/moderators", get(get_moderators).post(create_moderator))
+        .route("/i/{room_id}/moderators/{moderator_id}", delete(delete_moderator))
         .route("/i/{room_id}/setcontent", post(set_content))
+        .route("/i/{room_id}/setendbehavior", post(set_end_behavior))
+        .route(
+            "/i/{room_id}/queue",
+            get(get_queue).post(add_queue_item).delete(clear_queue),
+        )
+        .route("/i/{room_id}/queue/next", post(play_next_queue_item))
+        .route("/i/{room_id}/queue/{item_id}", delete(delete_queue_item))
+        .route("/i/{room_id}/queue/{item_id}/move", post(move_queue_item))
 }
 
 #[axum::debug_handler(state = Arc<AriaServer>)]
@@ -50,6 +115,21 @@ async fn get_room(State(server): State<Arc<AriaServer>>, Path(name): Path<String
     }
 }
 
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_presence(
+    State(server): State<Arc<AriaServer>>,
+    Path(name): Path<String>,
+) -> Result<Json<am::Presence>, ApiError> {
+    if server.core.get_room_by_name(&name).await?.is_none() {
+        return Err(ApiError::NotFound);
+    }
+
+    // Rooms are only loaded while in use, so an unloaded room has nobody in it
+    let presence = server.websocket.get_presence(&name).await?.unwrap_or_default();
+
+    Ok(Json(presence))
+}
+
 #[axum::debug_handler(state = Arc<AriaServer>)]
 async fn claim(
     State(server): State<Arc<AriaServer>>,
@@ -59,17 +139,7 @@ async fn claim(
 
     let claims = AuthClaims::Room { room_id: room.id };
 
-    let refresh_token = server.core.create_refresh_token(&claims).await?;
-
-    let claims = JwtClaims::short(claims);
-    let exp = claims.exp;
-    let access_token = server.auth.generate_token(&claims)?;
-
-    let auth = LoginResponse {
-        access_token,
-        exp,
-        refresh_token,
-    };
+    let auth = issue_tokens(&server, claims).await?;
 
     Ok(Json(ClaimResponse {
         id: room.id,
@@ -88,6 +158,209 @@ async fn logged_in(auth: Authorized, Path(room_id): Path<i32>) -> Result<(), Api
     Err(ApiError::Unauthorized)
 }
 
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn change_password(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Json(req): Json<ChangePasswordRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    if req.password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(ApiError::BadRequest);
+    }
+
+    // Room owners must confirm the current password, so that a stolen token can not be used to take over the room
+    if !auth.is_admin() {
+        let current_password = req.current_password.as_deref().ok_or(ApiError::BadRequest)?;
+
+        if !server.core.login(room_id, current_password).await? {
+            return Err(ApiError::Unauthorized);
+        }
+    }
+
+    server.core.change_room_password(room_id, &req.password).await?;
+
+    // All refresh tokens for the room were revoked,
+    // so issue new ones to keep the caller logged in.
+    let auth = issue_tokens(&server, AuthClaims::Room { room_id }).await?;
+
+    Ok(Json(auth))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_sessions(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<Json<Vec<SessionResponse>>, ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let sessions = server
+        .core
+        .get_room_sessions::<AuthClaims>(room_id)
+        .await?
+        .into_iter()
+        .map(|s| SessionResponse {
+            id: s.id,
+            created_at: s.created_at,
+            refreshed_at: s.refreshed_at,
+            expires_at: s.expires_at,
+            claims: s.claims,
+        })
+        .collect();
+
+    Ok(Json(sessions))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn revoke_session(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, session_id)): Path<(i32, i64)>,
+) -> Result<(), ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let success = server.core.revoke_room_session(room_id, session_id).await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_bans(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<Json<Vec<am::Ban>>, ApiError> {
+    if !auth.can(room_id, am::ModeratorPermission::BanUsers) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let bans = server.core.get_bans(room_id).await?;
+
+    Ok(Json(bans.iter().map(am::Ban::from).collect()))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn create_ban(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Json(req): Json<CreateBanRequest>,
+) -> Result<Json<am::Ban>, ApiError> {
+    if !auth.can(room_id, am::ModeratorPermission::BanUsers) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let expires_at = match req.duration {
+        Some(duration) if duration <= 0 => return Err(ApiError::BadRequest),
+        Some(duration) => Some(Utc::now() + Duration::seconds(duration)),
+        None => None,
+    };
+
+    let reason = req.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+
+    let Some(ban) = server
+        .core
+        .ban_from_post(room_id, req.post_id, reason, expires_at)
+        .await?
+    else {
+        return Err(ApiError::NotFound);
+    };
+
+    Ok(Json((&ban).into()))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn delete_ban(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, ban_id)): Path<(i32, i32)>,
+) -> Result<(), ApiError> {
+    if !auth.can(room_id, am::ModeratorPermission::BanUsers) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let success = server.core.delete_ban(room_id, ban_id).await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_moderators(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<Json<Vec<am::Moderator>>, ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let moderators = server.core.get_moderators(room_id).await?;
+
+    Ok(Json(moderators))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn create_moderator(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Json(req): Json<CreateModeratorRequest>,
+) -> Result<Json<CreateModeratorResponse>, ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(ApiError::BadRequest);
+    }
+
+    let Some(created) = server.core.create_moderator(room_id, name, &req.permissions).await? else {
+        return Err(ApiError::BadRequest);
+    };
+
+    Ok(Json(CreateModeratorResponse {
+        moderator: created.moderator,
+        key: created.key,
+    }))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn delete_moderator(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, moderator_id)): Path<(i32, i32)>,
+) -> Result<(), ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let success = server.core.delete_moderator(room_id, moderator_id).await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
 #[axum::debug_handler(state = Arc<AriaServer>)]
 async fn set_content(
     auth: Authorized,
@@ -106,3 +379,122 @@ async fn set_content(
 
     Ok(())
 }
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn set_end_behavior(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Json(req): Json<SetEndBehaviorRequest>,
+) -> Result<(), ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    server.core.set_room_end_behavior(room_id, req.end_behavior).await?;
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn get_queue(
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<Json<Vec<am::QueueItem>>, ApiError> {
+    let queue = server.core.get_queue(room_id).await?;
+
+    Ok(Json(queue))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn add_queue_item(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+    Json(req): Json<SetContentRequest>,
+) -> Result<Json<am::QueueItem>, ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let content = am::Content {
+        url: req.url,
+        duration: req.duration,
+        is_livestream: req.is_livestream,
+    };
+
+    let item = server.core.add_queue_item(room_id, content).await?;
+
+    Ok(Json(item))
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn delete_queue_item(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, item_id)): Path<(i32, i64)>,
+) -> Result<(), ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let success = server.core.delete_queue_item(room_id, item_id).await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn move_queue_item(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path((room_id, item_id)): Path<(i32, i64)>,
+    Json(req): Json<MoveQueueItemRequest>,
+) -> Result<(), ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let success = server.core.move_queue_item(room_id, item_id, req.position).await?;
+
+    if !success {
+        return Err(ApiError::NotFound);
+    }
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn clear_queue(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<(), ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    server.core.clear_queue(room_id).await?;
+
+    Ok(())
+}
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn play_next_queue_item(
+    auth: Authorized,
+    State(server): State<Arc<AriaServer>>,
+    Path(room_id): Path<i32>,
+) -> Result<Json<am::QueueItem>, ApiError> {
+    if !auth.for_room(room_id) {
+        return Err(ApiError::Unauthorized);
+    }
+
+    let Some(item) = server.core.play_next_queue_item(room_id).await? else {
+        return Err(ApiError::NotFound);
+    };
+
+    Ok(Json(item))
+}
@@ -0,0 +1,128 @@
+use std::net::SocketAddr;
+use std::path::Path;
+
+use anyhow::Context;
+use axum::Router;
+use axum_server::{Handle, tls_rustls::RustlsConfig};
+use futures::Future;
+use tokio::net::TcpListener;
+use tracing::info;
+
+/// Serve HTTP on a TCP address until shutdown
+pub async fn serve_tcp(
+    app: Router,
+    addr: SocketAddr,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), anyhow::Error> {
+    let listener = TcpListener::bind(&addr)
+        .await
+        .with_context(|| format!("Error binding to {addr}"))?;
+
+    info!("Web server listening on: {addr}");
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    Ok(())
+}
+
+/// Serve HTTPS on a TCP address until shutdown
+pub async fn serve_tls(
+    app: Router,
+    addr: SocketAddr,
+    tls: RustlsConfig,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), anyhow::Error> {
+    let handle = Handle::new();
+
+    tokio::spawn({
+        let handle = handle.clone();
+
+        async move {
+            shutdown.await;
+            handle.graceful_shutdown(None);
+        }
+    });
+
+    info!("Web server listening on: {addr} (TLS)");
+    axum_server::bind_rustls(addr, tls)
+        .handle(handle)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .with_context(|| format!("Error serving on {addr}"))?;
+
+    Ok(())
+}
+
+/// Serve HTTP on a Unix domain socket until shutdown
+#[cfg(unix)]
+pub async fn serve_unix(
+    app: Router,
+    path: &Path,
+    mode: Option<u32>,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), anyhow::Error> {
+    use std::fs;
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+    use tokio::net::UnixListener;
+
+    // Remove the socket left behind by a previous run, if any
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("'{}' exists and is not a socket", path.display());
+        }
+
+        fs::remove_file(path).context("Error removing old Unix socket")?;
+    }
+
+    let listener = UnixListener::bind(path).with_context(|| format!("Error binding to '{}'", path.display()))?;
+
+    if let Some(mode) = mode {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).context("Error setting Unix socket permissions")?;
+    }
+
+    info!("Web server listening on: {}", path.display());
+    axum::serve(listener, app).with_graceful_shutdown(shutdown).await?;
+
+    fs::remove_file(path).context("Error removing Unix socket")?;
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn serve_unix(
+    _app: Router,
+    _path: &Path,
+    _mode: Option<u32>,
+    _shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), anyhow::Error> {
+    anyhow::bail!("Unix sockets are not supported on this platform")
+}
+
+/// Reload the TLS certificate and key whenever SIGHUP is received
+#[cfg(unix)]
+pub async fn reload_tls_on_hangup(tls: RustlsConfig, cert_path: &Path, key_path: &Path) -> Result<(), anyhow::Error> {
+    use tokio::signal::unix::{SignalKind, signal};
+    use tracing::error;
+
+    let mut hangup = signal(SignalKind::hangup()).context("Error listening for SIGHUP")?;
+
+    while hangup.recv().await.is_some() {
+        match tls.reload_from_pem_file(cert_path, key_path).await {
+            Ok(()) => info!("Reloaded TLS certificate."),
+            Err(err) => error!("Error reloading TLS certificate: {err}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn reload_tls_on_hangup(
+    _tls: RustlsConfig,
+    _cert_path: &Path,
+    _key_path: &Path,
+) -> Result<(), anyhow::Error> {
+    futures::future::pending().await
+}
@@ -1,40 +1,89 @@
 mod api;
+mod listen;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::{Ipv6Addr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::Context;
 
 use aria_core::AriaCore;
-use axum::Router;
-use axum_client_ip::ClientIpSource;
-use futures::Future;
-use tokio::net::TcpListener;
+use axum::{
+    Router,
+    extract::{State, WebSocketUpgrade},
+    response::Response,
+    routing::get,
+};
+use axum_client_ip::{ClientIp, ClientIpSource};
+use axum_server::tls_rustls::RustlsConfig;
+use futures::{
+    Future,
+    future::{self, BoxFuture},
+};
 use tower_http::services::ServeDir;
-use tracing::info;
 
 use crate::auth::AriaAuth;
+use crate::rate_limit::{
+    DEFAULT_IMAGE_POST_RATE_LIMIT, DEFAULT_POST_RATE_LIMIT, DEFAULT_REACTION_RATE_LIMIT, RateLimitKey, RateLimiter,
+};
+use crate::websocket_server::WebSocketServer;
+
+const DEFAULT_LISTEN_ADDRESS: SocketAddr = SocketAddr::new(std::net::IpAddr::V6(Ipv6Addr::UNSPECIFIED), 3000);
 
 pub struct AriaServer {
     auth: Arc<AriaAuth>,
     core: Arc<AriaCore>,
+    websocket: Arc<WebSocketServer>,
     serve_files: bool,
+    post_limiter: RateLimiter<RateLimitKey>,
+    image_post_limiter: RateLimiter<RateLimitKey>,
+    reaction_limiter: RateLimiter<RateLimitKey>,
 }
 
 impl AriaServer {
-    pub fn new(auth: Arc<AriaAuth>, core: Arc<AriaCore>, serve_files: bool) -> Self {
+    pub fn new(auth: Arc<AriaAuth>, core: Arc<AriaCore>, websocket: WebSocketServer, serve_files: bool) -> Self {
+        let rate_limit = core.config.rate_limit.as_ref();
+
+        let post_limiter = RateLimiter::new(rate_limit.and_then(|rl| rl.post).unwrap_or(DEFAULT_POST_RATE_LIMIT));
+
+        let image_post_limiter = RateLimiter::new(
+            rate_limit
+                .and_then(|rl| rl.image_post)
+                .unwrap_or(DEFAULT_IMAGE_POST_RATE_LIMIT),
+        );
+
+        let reaction_limiter = RateLimiter::new(
+            rate_limit
+                .and_then(|rl| rl.reaction)
+                .unwrap_or(DEFAULT_REACTION_RATE_LIMIT),
+        );
+
         Self {
             auth,
             core,
+            websocket: Arc::new(websocket),
             serve_files,
+            post_limiter,
+            image_post_limiter,
+            reaction_limiter,
         }
     }
 
-    pub async fn run_server(self, shutdown: impl Future<Output = ()> + Send + 'static) -> Result<(), anyhow::Error> {
+    pub async fn run_server<F>(self, shutdown: impl Fn() -> F) -> Result<(), anyhow::Error>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
         let public_path = self.core.public_path.clone();
+        let core = self.core.clone();
 
         let server = Arc::new(self);
 
         let api = api::router(&server.core.sys_config);
 
-        let mut app = Router::new().nest("/api", api);
+        let mut app = Router::new()
+            .nest("/api", api)
+            .route("/aria-ws", get(websocket_upgrade));
 
         // If file serving is enabled, serve public files under /f.
         // This should generally only be used for development.
@@ -44,17 +93,92 @@ impl AriaServer {
             app = app.nest_service("/f", ServeDir::new(public_path))
         }
 
+        let listen_config = core.config.listen.as_ref();
+
+        // Expect to run behind a reverse proxy unless told otherwise
+        let client_ip_source = match listen_config.and_then(|l| l.client_ip_source.as_deref()) {
+            Some(source) => source.parse::<ClientIpSource>().context("Invalid client IP source")?,
+            None => ClientIpSource::RightmostXForwardedFor,
+        };
+
+        let unix_socket = listen_config.and_then(|l| l.unix_socket.as_deref());
+
+        // Unix socket peers have no IP address, so the client IP has to come from the reverse proxy
+        if unix_socket.is_some() && matches!(client_ip_source, ClientIpSource::ConnectInfo) {
+            anyhow::bail!(
+                "Listening on a Unix socket requires a header-based client IP source, such as 'RightmostXForwardedFor'"
+            );
+        }
+
         let app = app
             .layer(tower_http::trace::TraceLayer::new_for_http())
-            .layer(ClientIpSource::RightmostXForwardedFor.into_extension())
+            .layer(client_ip_source.into_extension())
             .with_state(server);
 
-        let addr: SocketAddr = "[::]:3000".parse().unwrap();
+        // Listen on the default address unless told otherwise
+        let addresses = match listen_config.and_then(|l| l.addresses.clone()) {
+            Some(addresses) => addresses,
+            None if unix_socket.is_some() => Vec::new(),
+            None => vec![DEFAULT_LISTEN_ADDRESS],
+        };
+
+        let tls = match listen_config.and_then(|l| l.tls.as_ref()) {
+            Some(tls) => Some((
+                RustlsConfig::from_pem_file(&tls.cert_path, &tls.key_path)
+                    .await
+                    .context("Error loading TLS certificate")?,
+                tls,
+            )),
+            None => None,
+        };
 
-        info!("Web server listening on: {addr}");
-        let listener = TcpListener::bind(&addr).await?;
-        axum::serve(listener, app).with_graceful_shutdown(shutdown).await?;
+        let mut servers: Vec<BoxFuture<Result<(), anyhow::Error>>> = Vec::new();
+
+        for addr in addresses {
+            match &tls {
+                Some((config, _)) => servers.push(Box::pin(listen::serve_tls(
+                    app.clone(),
+                    addr,
+                    config.clone(),
+                    shutdown(),
+                ))),
+                None => servers.push(Box::pin(listen::serve_tcp(app.clone(), addr, shutdown()))),
+            }
+        }
+
+        if let Some(path) = unix_socket {
+            let mode = listen_config.and_then(|l| l.unix_socket_mode);
+
+            servers.push(Box::pin(listen::serve_unix(app.clone(), path, mode, shutdown())));
+        }
+
+        let servers = future::try_join_all(servers);
+
+        match &tls {
+            Some((config, tls)) => {
+                let reload = listen::reload_tls_on_hangup(config.clone(), &tls.cert_path, &tls.key_path);
+
+                tokio::select! {
+                    result = servers => result?,
+                    Err(err) = reload => return Err(err),
+                };
+            }
+            None => {
+                servers.await?;
+            }
+        }
 
         Ok(())
     }
 }
+
+#[axum::debug_handler(state = Arc<AriaServer>)]
+async fn websocket_upgrade(
+    State(server): State<Arc<AriaServer>>,
+    ClientIp(ip): ClientIp,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    let websocket = server.websocket.clone();
+
+    upgrade.on_upgrade(move |socket| async move { websocket.handle_connection(socket, ip.to_canonical()).await })
+}
@@ -0,0 +1,43 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::{Future, pin_mut};
+use tracing::{error, info};
+
+use aria_core::AriaCore;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Periodically delete expired rooms until shutdown
+pub async fn run_room_sweeper(core: Arc<AriaCore>, shutdown: impl Future) -> Result<(), anyhow::Error> {
+    // Rooms never expire without a room lifetime
+    if core.config.room_lifetime_days.is_none() {
+        return Ok(());
+    }
+
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+
+    pin_mut!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                match core.delete_expired_rooms().await {
+                    Ok(room_ids) if !room_ids.is_empty() => {
+                        info!("Deleted {} expired room(s).", room_ids.len());
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!("Error deleting expired rooms: {err:#}");
+                    }
+                }
+            }
+            _ = &mut shutdown => {
+                info!("Stopped room sweeper.");
+                break;
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,208 +1,285 @@
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::IpAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    },
+};
 
 use anyhow::Context;
-use futures::{StreamExt, pin_mut};
+use axum::extract::ws::{Message, WebSocket};
+use futures::{StreamExt, future, pin_mut};
 use futures_channel::mpsc::unbounded;
-use serde::Deserialize;
-use tokio::{
-    net::TcpStream,
-    sync::{broadcast, mpsc::Sender},
-};
+use tokio::sync::{broadcast, mpsc::Sender};
 use tracing::{error, info, warn};
 
-use aria_models::api as am;
+use aria_core::MAX_NAME_LENGTH;
+use aria_models::local as lm;
+use aria_models::ws;
 
 use crate::auth::{AuthClaims, UserClaims};
+use crate::rate_limit::RateLimitKey;
 
-use super::{ConnectionId, ServerState, Tx, room::RoomMembership, send_raw};
+use super::{ConnectionId, Outgoing, ServerState, Tx, room::RoomMembership, send};
 
 struct ConnectionState {
     tx: Tx,
     room: Option<RoomMembership>,
-}
-
-#[derive(Debug, Deserialize)]
-struct JoinRequest {
-    room: String,
-    user: String,
-}
-
-#[derive(Debug, Deserialize)]
-struct GetSinceIdRequest<T> {
-    since: T,
+    /// User the connection joined a room as
+    user_id: Option<i64>,
+    /// Protocol version negotiated when joining a room
+    protocol_version: Arc<AtomicU32>,
 }
 
 pub(super) async fn handle_connection(
     id: ConnectionId,
     sv_state: Arc<ServerState>,
-    stream: TcpStream,
-    addr: SocketAddr,
+    socket: WebSocket,
+    ip: IpAddr,
     mut shutdown_rx: broadcast::Receiver<()>,
     _shutdown_complete_tx: Sender<()>,
 ) {
-    info!("[{id}] {addr} connected.");
-
-    let ws_stream = tokio_tungstenite::accept_async(stream).await;
-
-    if let Ok(ws_stream) = ws_stream {
-        info!("[{id}] WebSocket connection established.");
-
-        let (tx, rx) = unbounded();
-
-        let (outgoing, mut incoming) = ws_stream.split();
-
-        let mut cn_state = ConnectionState { tx, room: None };
-
-        let handle_outgoing = rx.map(Ok).forward(outgoing);
-
-        pin_mut!(handle_outgoing);
-
-        // Continue to handle incoming and outgoing messages
-        // until the shutdown signal is received.
-        loop {
-            tokio::select! {
-                msg = incoming.next() => {
-                    if let Some(Ok(msg)) = msg {
-                        let sv_state = &sv_state;
-                        let cn_state = &mut cn_state;
-
-                        let result: Result<(), anyhow::Error> = async move {
-                            let tx = &cn_state.tx;
-
-                            let msg = msg.to_text().context("Error retrieving message text")?;
-                            info!("[{id}] Message received: {}", msg);
-
-                            if let Some((msg, data)) = msg.split_once('|') {
-                                match msg {
-                                    "ping" => {
-                                        send_raw(tx, "pong", data)?;
-                                    }
-                                    "join" => {
-                                        let req: JoinRequest =
-                                            serde_json::from_str(data).context("Error deserializing room name")?;
-
-                                        let user_claims = sv_state
-                                            .auth
-                                            .verify::<UserClaims>(&req.user)
-                                            .map_err(|_| anyhow::anyhow!("Error verifying user token"))?;
-
-                                        let user_id = user_claims.user_id;
-
-                                        let room_name = req.room;
-
-                                        info!("[{id}] Joining room '{room_name}'...");
-
-                                        // If connection is already joined to another room...
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            // If connection is already joined to the same room, return immediately.
-                                            if room.room_name == room_name {
-                                                return Ok(());
-                                            }
-
-                                            // Otherwise, leave the current room before joining the new one.
-                                            room.leave().await?;
-                                        }
-
-                                        let room =
-                                            sv_state.lobby.join_room(id, room_name, tx.clone(), user_id).await?;
-
-                                        cn_state.room = Some(room);
-                                    }
-                                    "leave" => {
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            info!("[{id}] Leaving room '{}'...", room.room_name);
-
-                                            room.leave().await?;
-                                        } else {
-                                            warn!("[{id}] Tried to leave the room, but was not in a room.");
-                                        }
-                                    }
-                                    "auth" => {
-                                        let token: String =
-                                            serde_json::from_str(data).context("Error deserializing authorization token")?;
-
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            let mut is_authorized = false;
-                                            if let Ok(claims) = sv_state.auth.verify::<AuthClaims>(&token) {
-                                                if claims.for_room(room.room_id) {
-                                                    is_authorized = true;
-                                                }
-                                            }
-
-                                            if is_authorized {
-                                                room.set_admin().await.context("setting admin")?;
-                                            }
-                                        }
-                                    }
-                                    "set-master" => {
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            room.set_master().await.context("setting master")?;
-                                        }
-                                    }
-                                    "not-master" => {
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            room
-                                                .relinquish_master()
-                                                .await
-                                                .context("relinquishing master")?;
-                                        }
-                                    }
-                                    "master-playbackstate" => {
-                                        let ps: am::PlaybackState =
-                                            serde_json::from_str(data).context("deserializing playback state")?;
-
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            room
-                                                .set_playback_state(ps)
-                                                .await
-                                                .context("setting playback state")?;
-                                        }
-                                    }
-                                    "get-emotes" => {
-                                        let req: GetSinceIdRequest<i32> = serde_json::from_str(data).context("Error deserializing request data")?;
-
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            room.send_emotes(req.since).await.context("getting emotes")?;
-                                        }
-                                    }
-                                    "get-recent-posts" => {
-                                        let req: GetSinceIdRequest<i64> = serde_json::from_str(data).context("Error deserializing request data")?;
-
-                                        if let Some(room) = cn_state.room.as_ref() {
-                                            room.send_recent_posts(req.since).await.context("getting recent posts")?;
-                                        }
-                                    }
-                                    _ => {
-                                        warn!("Unknown message: {msg}");
-                                    }
-                                }
-                            }
-
-                            Ok(())
-                        }
-                        .await;
+    info!("[{id}] {ip} connected.");
+
+    let (tx, rx) = unbounded();
+
+    let (outgoing, mut incoming) = socket.split();
+
+    let protocol_version = Arc::new(AtomicU32::new(ws::LEGACY_PROTOCOL_VERSION));
+
+    let mut cn_state = ConnectionState {
+        tx,
+        room: None,
+        user_id: None,
+        protocol_version: protocol_version.clone(),
+    };
+
+    // Frame outgoing messages according to the protocol version in use when they are sent
+    let handle_outgoing = rx
+        .filter_map(move |msg| {
+            let msg = match msg {
+                Outgoing::Message(msg) => match msg.encode(protocol_version.load(Ordering::Relaxed)) {
+                    Ok(text) => Some(Message::Text(text.into())),
+                    Err(err) => {
+                        error!("[{id}] Error encoding message: {err}");
+                        None
+                    }
+                },
+                Outgoing::Close => Some(Message::Close(None)),
+            };
+
+            future::ready(msg.map(Ok))
+        })
+        .forward(outgoing);
+
+    pin_mut!(handle_outgoing);
+
+    // Continue to handle incoming and outgoing messages
+    // until the shutdown signal is received.
+    loop {
+        tokio::select! {
+            msg = incoming.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    // Only text frames carry requests
+                    Some(Ok(Message::Binary(_) | Message::Ping(_) | Message::Pong(_))) => continue,
+                    // If incoming returns None, or the client closed the connection, break.
+                    // This is necessary to avoid going into an infinite loop.
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                };
+
+                let sv_state = &sv_state;
+                let cn_state = &mut cn_state;
+                let text = text.as_str();
+
+                let result: Result<(), anyhow::Error> = async {
+                    info!("[{id}] Message received: {}", text);
+
+                    // Once joined, also limit the user, so that it cannot evade the limit by switching addresses
+                    let limit_keys = match cn_state.user_id {
+                        Some(user_id) => vec![RateLimitKey::Ip(ip), RateLimitKey::User(user_id)],
+                        None => vec![RateLimitKey::Ip(ip)],
+                    };
+
+                    if let Err(retry_after) = sv_state.message_limiter.check(&limit_keys) {
+                        warn!("[{id}] Rate limited, dropping message.");
+                        send(&cn_state.tx, ws::ServerMessage::RateLimited(retry_after.as_secs_f64()))?;
+
+                        return Ok(());
+                    }
 
-                        if let Err(err) = result {
-                            error!("{err:#}");
+                    let request = match ws::ClientRequest::decode(text) {
+                        Ok(request) => request,
+                        Err(err) => {
+                            warn!("[{id}] Invalid message: {err}");
+                            send_error(&cn_state.tx, ws::ClientRequest::peek_id(text), format!("Invalid message: {err}"))?;
+
+                            return Ok(());
                         }
-                    } else {
-                        // If incoming returns None, break.
-                        // This is necessary to avoid going into an infinite loop.
-                        break;
+                    };
+
+                    if let Err(err) = handle_message(id, sv_state, cn_state, ip, request.message).await {
+                        error!("[{id}] {err:#}");
+                        send_error(&cn_state.tx, request.id, err.to_string())?;
                     }
-                },
-                _ = &mut handle_outgoing => { },
-                _ = shutdown_rx.recv() => { break; },
-            }
+
+                    Ok(())
+                }
+                .await;
+
+                if let Err(err) = result {
+                    error!("{err:#}");
+                }
+            },
+            _ = &mut handle_outgoing => { },
+            _ = shutdown_rx.recv() => { break; },
+        }
+    }
+
+    info!("[{id}] Disconnected.");
+
+    // Leave the room, to prevent dangling members
+    if let Some(room) = cn_state.room.as_ref() {
+        room.leave().await.ok();
+    };
+}
+
+async fn handle_message(
+    id: ConnectionId,
+    sv_state: &ServerState,
+    cn_state: &mut ConnectionState,
+    ip: IpAddr,
+    msg: ws::ClientMessage,
+) -> Result<(), anyhow::Error> {
+    let tx = &cn_state.tx;
+
+    match msg {
+        ws::ClientMessage::Ping(data) => {
+            send(tx, ws::ServerMessage::Pong(data))?;
         }
+        ws::ClientMessage::Join(req) => {
+            let protocol_version = ws::negotiate_version(req.protocol);
+
+            let user_claims = sv_state
+                .auth
+                .verify::<UserClaims>(&req.user)
+                .map_err(|_| anyhow::anyhow!("Error verifying user token"))?;
+
+            let user_id = user_claims.user_id;
+
+            let room_name = req.room;
+            let display_name = req.name.map(|n| n.trim().to_owned()).filter(|n| !n.is_empty());
+
+            if display_name
+                .as_ref()
+                .is_some_and(|n| n.chars().count() > MAX_NAME_LENGTH)
+            {
+                anyhow::bail!("Name is too long");
+            }
+
+            info!("[{id}] Joining room '{room_name}'...");
 
-        info!("[{id}] Disconnected.");
+            // If connection is already joined to another room...
+            if let Some(room) = cn_state.room.as_ref() {
+                // If connection is already joined to the same room, return immediately.
+                if room.room_name == room_name {
+                    return Ok(());
+                }
 
-        // Leave the room, to prevent dangling members
-        if let Some(room) = cn_state.room.as_ref() {
-            room.leave().await.ok();
-        };
-    } else {
-        error!("[{id}] Error occurred during the websocket handshake.");
+                // Otherwise, leave the current room before joining the new one.
+                room.leave().await?;
+            }
+
+            let room = sv_state
+                .lobby
+                .join_room(id, room_name, tx.clone(), user_id, display_name, ip)
+                .await?;
+
+            cn_state.room = Some(room);
+            cn_state.user_id = Some(user_id);
+
+            // Only switch the framing once joined, so that errors joining are framed like the request
+            cn_state.protocol_version.store(protocol_version, Ordering::Relaxed);
+
+            send(
+                &cn_state.tx,
+                ws::ServerMessage::Joined(ws::Joined {
+                    protocol: protocol_version,
+                }),
+            )?;
+        }
+        ws::ClientMessage::Leave => {
+            if let Some(room) = cn_state.room.as_ref() {
+                info!("[{id}] Leaving room '{}'...", room.room_name);
+
+                room.leave().await?;
+            } else {
+                warn!("[{id}] Tried to leave the room, but was not in a room.");
+            }
+        }
+        ws::ClientMessage::Auth(token) => {
+            if let Some(room) = cn_state.room.as_ref() {
+                let claims = match sv_state.auth.verify::<AuthClaims>(&token) {
+                    Ok(claims) => claims.revalidate(&sv_state.core).await?,
+                    Err(_) => None,
+                };
+
+                // Moderators are room admins in the chat if they can moderate it
+                let is_authorized = claims.is_some_and(|claims| {
+                    claims.for_room(room.room_id)
+                        || claims.can(room.room_id, lm::ModeratorPermission::DeletePosts)
+                        || claims.can(room.room_id, lm::ModeratorPermission::BanUsers)
+                });
+
+                if is_authorized {
+                    room.set_admin().await.context("Error setting admin")?;
+                }
+            }
+        }
+        ws::ClientMessage::SetMaster => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.set_master().await.context("Error setting master")?;
+            }
+        }
+        ws::ClientMessage::NotMaster => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.relinquish_master().await.context("Error relinquishing master")?;
+            }
+        }
+        ws::ClientMessage::MasterPlaybackState(ps) => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.set_playback_state(ps)
+                    .await
+                    .context("Error setting playback state")?;
+            }
+        }
+        ws::ClientMessage::GetEmotes(req) => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.send_emotes(req.since).await.context("Error getting emotes")?;
+            }
+        }
+        ws::ClientMessage::GetRecentPosts(req) => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.send_recent_posts(req.since)
+                    .await
+                    .context("Error getting recent posts")?;
+            }
+        }
+        ws::ClientMessage::TypingStart => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.set_typing(true).await.context("Error setting typing")?;
+            }
+        }
+        ws::ClientMessage::TypingStop => {
+            if let Some(room) = cn_state.room.as_ref() {
+                room.set_typing(false).await.context("Error setting typing")?;
+            }
+        }
     }
+
+    Ok(())
+}
+
+fn send_error(tx: &Tx, request_id: Option<u64>, message: String) -> Result<(), anyhow::Error> {
+    send(tx, ws::ServerMessage::Error(ws::ErrorReply { request_id, message }))
 }
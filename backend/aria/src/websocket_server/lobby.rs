@@ -1,4 +1,4 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, net::IpAddr, sync::Arc};
 
 use anyhow::anyhow;
 use futures::StreamExt;
@@ -7,10 +7,12 @@ use tokio::sync::mpsc::Sender;
 use tokio::sync::{broadcast, oneshot};
 use tracing::{error, info, warn};
 
-use aria_core::{AriaCore, Notification};
+use aria_core::{AriaCore, Banned, Notification};
+use aria_models::api as am;
+use aria_models::ws;
 
-use super::ConnectionId;
 use super::room::RoomMembership;
+use super::{ConnectionId, send};
 use super::{Tx, room::Room};
 
 pub(super) struct Lobby {
@@ -29,8 +31,14 @@ pub(super) enum LobbyRequest {
         name: String,
         member_tx: Tx,
         user_id: i64,
+        display_name: Option<String>,
+        ip: IpAddr,
         result_tx: oneshot::Sender<Result<RoomMembership, anyhow::Error>>,
     },
+    GetPresence {
+        name: String,
+        result_tx: oneshot::Sender<Result<Option<am::Presence>, anyhow::Error>>,
+    },
     UnloadRoom {
         room_id: i32,
         result_tx: oneshot::Sender<Result<(), anyhow::Error>>,
@@ -59,6 +67,8 @@ impl Lobby {
         name: String,
         member_tx: Tx,
         user_id: i64,
+        display_name: Option<String>,
+        ip: IpAddr,
     ) -> Result<RoomMembership, anyhow::Error> {
         let (result_tx, result_rx) = oneshot::channel::<Result<RoomMembership, anyhow::Error>>();
 
@@ -68,10 +78,21 @@ impl Lobby {
             member_tx,
             result_tx,
             user_id,
+            display_name,
+            ip,
         })?;
 
         result_rx.await?
     }
+
+    /// Get the users connected to a room, or None if the room is not loaded
+    pub async fn get_presence(&self, name: String) -> Result<Option<am::Presence>, anyhow::Error> {
+        let (result_tx, result_rx) = oneshot::channel::<Result<Option<am::Presence>, anyhow::Error>>();
+
+        self.tx.unbounded_send(LobbyRequest::GetPresence { name, result_tx })?;
+
+        result_rx.await?
+    }
 }
 
 async fn handle_lobby_requests(
@@ -92,8 +113,18 @@ async fn handle_lobby_requests(
             // Handle lobby requests
             req = request_rx.select_next_some() => {
                 match req {
-                    LobbyRequest::JoinRoom { connection_id, name, member_tx, user_id, result_tx } => {
-                        result_tx.send(handle_join_room(&mut state, core.clone(), &request_tx, connection_id, name, member_tx, user_id, room_shutdown_tx.subscribe(), shutdown_complete_tx.clone()).await).map_err(|_| {
+                    LobbyRequest::JoinRoom { connection_id, name, member_tx, user_id, display_name, ip, result_tx } => {
+                        result_tx.send(handle_join_room(&mut state, core.clone(), &request_tx, connection_id, name, member_tx, user_id, display_name, ip, room_shutdown_tx.subscribe(), shutdown_complete_tx.clone()).await).map_err(|_| {
+                            warn!("Lobby request sender dropped.");
+                        }).ok();
+                    }
+                    LobbyRequest::GetPresence { name, result_tx } => {
+                        let res = match state.rooms_by_name.get(&name) {
+                            Some(room) => room.get_presence().await.map(Some),
+                            None => Ok(None),
+                        };
+
+                        result_tx.send(res).map_err(|_| {
                             warn!("Lobby request sender dropped.");
                         }).ok();
                     }
@@ -125,6 +156,16 @@ async fn handle_lobby_requests(
                                         room.delete_post(*post_id).await?;
                                     }
                                 }
+                                Notification::EditPost(room, post) => {
+                                    if let Some(room) = state.rooms_by_id.get(room) {
+                                        room.edit_post(post.clone()).await?;
+                                    }
+                                }
+                                Notification::Reaction(room, change) => {
+                                    if let Some(room) = state.rooms_by_id.get(room) {
+                                        room.reaction(change.clone()).await?;
+                                    }
+                                }
                                 Notification::DeleteEmote(room, emote_id) => {
                                     if let Some(room) = state.rooms_by_id.get(room) {
                                         room.delete_emote(*emote_id).await?;
@@ -135,6 +176,32 @@ async fn handle_lobby_requests(
                                         room.set_content(content.clone()).await?;
                                     }
                                 }
+                                Notification::Queue(room, queue) => {
+                                    if let Some(room) = state.rooms_by_id.get(room) {
+                                        room.set_queue(queue.clone()).await?;
+                                    }
+                                }
+                                Notification::EndBehavior(room, end_behavior) => {
+                                    if let Some(room) = state.rooms_by_id.get(room) {
+                                        room.set_end_behavior(*end_behavior).await?;
+                                    }
+                                }
+                                Notification::PurgePosts(room, post_ids) => {
+                                    if let Some(room) = state.rooms_by_id.get(room) {
+                                        room.purge_posts(post_ids.clone()).await?;
+                                    }
+                                }
+                                Notification::Ban(room, ban) => {
+                                    if let Some(room) = state.rooms_by_id.get(room) {
+                                        room.ban(ban.clone()).await?;
+                                    }
+                                }
+                                Notification::DeleteRoom(room_id) => {
+                                    if let Some(room) = state.rooms_by_id.get(room_id).cloned() {
+                                        handle_unload_room(&mut state, *room_id)?;
+                                        room.close().await?;
+                                    }
+                                }
                             }
 
                             Ok(())
@@ -171,13 +238,23 @@ async fn handle_join_room(
     name: String,
     member_tx: Tx,
     user_id: i64,
+    display_name: Option<String>,
+    ip: IpAddr,
     shutdown_rx: broadcast::Receiver<()>,
     shutdown_complete_tx: Sender<()>,
 ) -> Result<RoomMembership, anyhow::Error> {
     let room = if let Some(room) = state.rooms_by_name.get(&name) {
         room.clone()
     } else {
-        match Room::load(core, &name, lobby_request_tx.clone(), shutdown_rx, shutdown_complete_tx).await? {
+        match Room::load(
+            core.clone(),
+            &name,
+            lobby_request_tx.clone(),
+            shutdown_rx,
+            shutdown_complete_tx,
+        )
+        .await?
+        {
             Some(room) => {
                 state.rooms_by_id.insert(room.id, room.clone());
                 state.rooms_by_name.insert(name.clone(), room.clone());
@@ -190,7 +267,15 @@ async fn handle_join_room(
         }
     };
 
-    room.join(connection_id, member_tx, user_id).await
+    if let Some(ban) = core.get_active_ban(room.id, user_id, ip).await? {
+        send(&member_tx, ws::ServerMessage::Banned(am::Ban::from(&ban)))?;
+
+        return Err(Banned(ban).into());
+    }
+
+    core.extend_room_expiry(room.id).await?;
+
+    room.join(connection_id, member_tx, user_id, display_name, ip).await
 }
 
 fn handle_unload_room(state: &mut LobbyState, room_id: i32) -> Result<(), anyhow::Error> {
@@ -2,88 +2,143 @@ mod connection;
 mod lobby;
 mod room;
 
-use std::{net::SocketAddr, sync::Arc};
+use std::{
+    net::IpAddr,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
 use aria_core::AriaCore;
-use futures::{Future, pin_mut};
+use axum::extract::ws::WebSocket;
+use futures::Future;
 use futures_channel::mpsc::UnboundedSender;
-use serde::Serialize;
-use tokio::{net::TcpListener, sync::broadcast};
-use tokio_tungstenite::tungstenite::Message;
+use tokio::sync::{broadcast, mpsc};
 use tracing::info;
 
+use aria_models::api as am;
+use aria_models::ws;
+
 use crate::auth::AriaAuth;
+use crate::rate_limit::{DEFAULT_WEBSOCKET_MESSAGE_RATE_LIMIT, RateLimitKey, RateLimiter};
 
 use self::{connection::*, lobby::Lobby};
 
 type ConnectionId = u64;
-type Tx = UnboundedSender<Message>;
+type Tx = UnboundedSender<Outgoing>;
+
+/// Message to a client, framed according to the connection's protocol version when it is sent
+enum Outgoing {
+    Message(Box<ws::ServerMessage>),
+    Close,
+}
 
 struct ServerState {
     auth: Arc<AriaAuth>,
+    core: Arc<AriaCore>,
     lobby: Arc<Lobby>,
+    message_limiter: RateLimiter<RateLimitKey>,
 }
 
-pub async fn run_server(auth: Arc<AriaAuth>, core: Arc<AriaCore>, shutdown: impl Future) -> Result<(), anyhow::Error> {
-    let (shutdown_tx, _) = broadcast::channel::<()>(1);
-    let (shutdown_complete_tx, mut shutdown_complete_rx) = tokio::sync::mpsc::channel(1);
-
-    let lobby = Arc::new(Lobby::new(
-        core.clone(),
-        shutdown_tx.subscribe(),
-        shutdown_complete_tx.clone(),
-    ));
-
-    let state = Arc::new(ServerState { auth, lobby });
-
-    let addr: SocketAddr = "[::]:3001".parse().unwrap();
-
-    let listener = TcpListener::bind(addr).await?;
-    info!("WebSocket server listening on: {addr}");
+/// Handles WebSocket connections upgraded by the HTTP server
+pub struct WebSocketServer {
+    state: Arc<ServerState>,
+    next_id: AtomicU64,
+    shutdown_tx: broadcast::Sender<()>,
+    shutdown_complete_tx: mpsc::Sender<()>,
+}
 
-    // Accept connections
-    let mut next_id: ConnectionId = 1;
+/// Waits for shutdown of the lobby, rooms and connections of a [`WebSocketServer`]
+pub struct WebSocketShutdown {
+    shutdown_tx: broadcast::Sender<()>,
+    shutdown_complete_rx: mpsc::Receiver<()>,
+}
 
-    pin_mut!(shutdown);
+impl WebSocketServer {
+    pub fn new(auth: Arc<AriaAuth>, core: Arc<AriaCore>) -> (Self, WebSocketShutdown) {
+        let (shutdown_tx, _) = broadcast::channel::<()>(1);
+        let (shutdown_complete_tx, shutdown_complete_rx) = mpsc::channel(1);
+
+        let lobby = Arc::new(Lobby::new(
+            core.clone(),
+            shutdown_tx.subscribe(),
+            shutdown_complete_tx.clone(),
+        ));
+
+        let message_limiter = RateLimiter::new(
+            core.config
+                .rate_limit
+                .as_ref()
+                .and_then(|rl| rl.websocket_message)
+                .unwrap_or(DEFAULT_WEBSOCKET_MESSAGE_RATE_LIMIT),
+        );
+
+        let state = Arc::new(ServerState {
+            auth,
+            core,
+            lobby,
+            message_limiter,
+        });
+
+        let server = Self {
+            state,
+            next_id: AtomicU64::new(1),
+            shutdown_tx: shutdown_tx.clone(),
+            shutdown_complete_tx,
+        };
+
+        let shutdown = WebSocketShutdown {
+            shutdown_tx,
+            shutdown_complete_rx,
+        };
+
+        (server, shutdown)
+    }
 
-    loop {
-        tokio::select! {
-            res = listener.accept() => {
-                if let Ok((stream, addr)) = res {
-                    let id = next_id;
-                    next_id += 1;
+    /// Get the users connected to a room, or None if the room is not loaded
+    pub async fn get_presence(&self, room_name: &str) -> Result<Option<am::Presence>, anyhow::Error> {
+        self.state.lobby.get_presence(room_name.to_owned()).await
+    }
 
-                    tokio::spawn(handle_connection(id, state.clone(), stream, addr, shutdown_tx.subscribe(), shutdown_complete_tx.clone()));
-                }
-            }
-            _ = &mut shutdown => {
-                info!("Stopped accepting connections.");
-                break;
-            }
-        }
+    /// Handle an upgraded connection until it is closed or the server shuts down
+    pub async fn handle_connection(&self, socket: WebSocket, ip: IpAddr) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        handle_connection(
+            id,
+            self.state.clone(),
+            socket,
+            ip,
+            self.shutdown_tx.subscribe(),
+            self.shutdown_complete_tx.clone(),
+        )
+        .await;
     }
+}
 
-    // Send shutdown signal
-    shutdown_tx.send(())?;
+impl WebSocketShutdown {
+    /// Wait for the shutdown signal, then shut down all connections and rooms.
+    /// Completes once the [`WebSocketServer`] and every connection has been dropped.
+    pub async fn run(mut self, shutdown: impl Future) -> Result<(), anyhow::Error> {
+        shutdown.await;
 
-    info!("Waiting for graceful shutdown...");
+        // Send shutdown signal
+        self.shutdown_tx.send(())?;
 
-    // Wait for graceful shutdown of all connections
-    drop(shutdown_complete_tx);
-    shutdown_complete_rx.recv().await;
+        info!("Waiting for graceful shutdown of WebSocket connections...");
 
-    info!("Graceful shutdown complete.");
+        // Wait for graceful shutdown of all connections
+        self.shutdown_complete_rx.recv().await;
 
-    Ok(())
+        info!("Graceful shutdown of WebSocket connections complete.");
+
+        Ok(())
+    }
 }
 
-fn send_raw(tx: &Tx, msg: &str, data: &str) -> Result<(), anyhow::Error> {
-    let msg = Message::Text(format!("{msg}|{data}").into());
-    tx.unbounded_send(msg)?;
+fn send(tx: &Tx, msg: ws::ServerMessage) -> Result<(), anyhow::Error> {
+    tx.unbounded_send(Outgoing::Message(Box::new(msg)))?;
 
     Ok(())
 }
-
-fn send<T: Serialize>(tx: &Tx, msg: &str, data: T) -> Result<(), anyhow::Error> {
-    send_raw(tx, msg, &serde_json::to_string(&data)?)
-}
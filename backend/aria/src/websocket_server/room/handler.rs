@@ -1,3 +1,4 @@
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use chrono::{DateTime, Duration, Utc};
@@ -7,7 +8,7 @@ use futures_channel::mpsc::UnboundedSender;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc::Sender;
 use tokio::sync::oneshot;
-use tracing::info;
+use tracing::{error, info};
 
 use aria_core::AriaCore;
 use aria_models::api as am;
@@ -26,6 +27,8 @@ pub enum RoomRequest {
         tx: Tx,
         connection_id: ConnectionId,
         user_id: i64,
+        name: Option<String>,
+        ip: IpAddr,
         result_tx: RoomRequestTx<()>,
     },
     SendEmotes {
@@ -42,6 +45,9 @@ pub enum RoomRequest {
         connection_id: ConnectionId,
         result_tx: RoomRequestTx<()>,
     },
+    GetPresence {
+        result_tx: RoomRequestTx<am::Presence>,
+    },
     Post {
         post: lm::Post,
         result_tx: RoomRequestTx<()>,
@@ -50,10 +56,34 @@ pub enum RoomRequest {
         post_id: i64,
         result_tx: RoomRequestTx<()>,
     },
+    EditPost {
+        post: lm::Post,
+        result_tx: RoomRequestTx<()>,
+    },
+    Reaction {
+        change: lm::ReactionChange,
+        result_tx: RoomRequestTx<()>,
+    },
+    PurgePosts {
+        post_ids: Vec<i64>,
+        result_tx: RoomRequestTx<()>,
+    },
+    Ban {
+        ban: lm::Ban,
+        result_tx: RoomRequestTx<()>,
+    },
     SetContent {
         content: am::Content,
         result_tx: RoomRequestTx<()>,
     },
+    SetQueue {
+        queue: Vec<am::QueueItem>,
+        result_tx: RoomRequestTx<()>,
+    },
+    SetEndBehavior {
+        end_behavior: am::ContentEndBehavior,
+        result_tx: RoomRequestTx<()>,
+    },
     SetAdmin {
         connection_id: ConnectionId,
         result_tx: RoomRequestTx<()>,
@@ -66,6 +96,11 @@ pub enum RoomRequest {
         connection_id: ConnectionId,
         result_tx: RoomRequestTx<()>,
     },
+    Typing {
+        connection_id: ConnectionId,
+        typing: bool,
+        result_tx: RoomRequestTx<()>,
+    },
     SetPlaybackState {
         connection_id: ConnectionId,
         ps: am::PlaybackState,
@@ -79,6 +114,9 @@ pub enum RoomRequest {
         emote_id: i32,
         result_tx: RoomRequestTx<()>,
     },
+    Close {
+        result_tx: RoomRequestTx<()>,
+    },
 }
 
 pub(super) async fn handle_room_requests(
@@ -98,8 +136,8 @@ pub(super) async fn handle_room_requests(
             // Handle room requests
             req = request_rx.select_next_some() => {
                 match req {
-                    RoomRequest::Join { tx, connection_id, user_id, result_tx } => {
-                        let res = state.join(connection_id, user_id, tx);
+                    RoomRequest::Join { tx, connection_id, user_id, name, ip, result_tx } => {
+                        let res = state.join(connection_id, user_id, name, ip, tx);
                         result_tx.send(res).ok();
 
                         unload_at = None;
@@ -122,6 +160,9 @@ pub(super) async fn handle_room_requests(
                             unload_at = Some(Utc::now() + Duration::hours(1));
                         }
                     }
+                    RoomRequest::GetPresence { result_tx } => {
+                        result_tx.send(Ok(state.presence())).ok();
+                    }
                     RoomRequest::Post { post, result_tx } => {
                         let res = state.post(post);
                         result_tx.send(res).ok();
@@ -130,6 +171,22 @@ pub(super) async fn handle_room_requests(
                         let res = state.delete_post(post_id);
                         result_tx.send(res).ok();
                     }
+                    RoomRequest::EditPost { post, result_tx } => {
+                        let res = state.edit_post(post);
+                        result_tx.send(res).ok();
+                    }
+                    RoomRequest::Reaction { change, result_tx } => {
+                        let res = state.reaction(&change);
+                        result_tx.send(res).ok();
+                    }
+                    RoomRequest::PurgePosts { post_ids, result_tx } => {
+                        let res = state.purge_posts(&post_ids);
+                        result_tx.send(res).ok();
+                    }
+                    RoomRequest::Ban { ban, result_tx } => {
+                        let res = state.ban(&ban);
+                        result_tx.send(res).ok();
+                    }
                     RoomRequest::Emote { emote, result_tx } => {
                         let res = state.add_emote(emote);
                         result_tx.send(res).ok();
@@ -142,6 +199,14 @@ pub(super) async fn handle_room_requests(
                         let res = state.set_content(content);
                         result_tx.send(res).ok();
                     }
+                    RoomRequest::SetQueue { queue, result_tx } => {
+                        let res = state.set_queue(queue);
+                        result_tx.send(res).ok();
+                    }
+                    RoomRequest::SetEndBehavior { end_behavior, result_tx } => {
+                        let res = state.set_end_behavior(end_behavior);
+                        result_tx.send(res).ok();
+                    }
                     RoomRequest::SetAdmin { connection_id, result_tx } => {
                         let res = state.set_admin(connection_id);
                         result_tx.send(res).ok();
@@ -154,10 +219,21 @@ pub(super) async fn handle_room_requests(
                         let res = state.relinquish_master(connection_id);
                         result_tx.send(res).ok();
                     }
+                    RoomRequest::Typing { connection_id, typing, result_tx } => {
+                        let res = state.set_typing(connection_id, typing);
+                        result_tx.send(res).ok();
+                    }
                     RoomRequest::SetPlaybackState { connection_id, ps, result_tx } => {
                         let res = state.set_playback_state(connection_id, &ps, &core).await;
                         result_tx.send(res).ok();
                     }
+                    RoomRequest::Close { result_tx } => {
+                        info!("Closing room '{}'...", state.name);
+
+                        let res = state.close();
+                        result_tx.send(res).ok();
+                        break;
+                    }
                 }
             }
             _ = tokio::time::sleep(std::time::Duration::from_secs(state.get_content_duration_remaining().map(|v| v as u64 + 1).unwrap_or(u64::MAX))) => {
@@ -173,20 +249,28 @@ pub(super) async fn handle_room_requests(
                     continue;
                 }
 
-                // Reset server playback state
-                let pbs = am::PlaybackState::default();
-                state.set_playback_state(0, &pbs, &core).await.ok();
+                // Apply the room's end-of-content behavior
+                state.end_content(&core).await.map_err(|err| error!("{err:#}")).ok();
+            }
+            _ = tokio::time::sleep(state.get_typing_update_delay().unwrap_or(std::time::Duration::MAX)) => {
+                // Expire typists and send any throttled typing update
+                state.update_typing();
             }
             _ = unload_check_interval.tick() => {
-                if let Some(unload_at) = unload_at {
-                    if Utc::now() > unload_at {
-                        info!("Unloading room '{}'...", state.name);
-
-                        let (result_tx, result_rx) = oneshot::channel();
-                        if lobby_request_tx.unbounded_send(LobbyRequest::UnloadRoom { room_id: state.id, result_tx }).is_ok() {
-                            result_rx.await.ok();
-                            break;
-                        }
+                // Keep the room from expiring while it is in use
+                if !state.is_deserted() {
+                    core.extend_room_expiry(state.id).await.map_err(|err| error!("{err:#}")).ok();
+                }
+
+                if let Some(unload_at) = unload_at
+                    && Utc::now() > unload_at
+                {
+                    info!("Unloading room '{}'...", state.name);
+
+                    let (result_tx, result_rx) = oneshot::channel();
+                    if lobby_request_tx.unbounded_send(LobbyRequest::UnloadRoom { room_id: state.id, result_tx }).is_ok() {
+                        result_rx.await.ok();
+                        break;
                     }
                 }
             }
@@ -65,6 +65,15 @@ impl RoomMembership {
         .await
     }
 
+    pub async fn set_typing(&self, typing: bool) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::Typing {
+            connection_id: self.connection_id,
+            typing,
+            result_tx,
+        })
+        .await
+    }
+
     pub async fn set_playback_state(&self, ps: am::PlaybackState) -> Result<(), anyhow::Error> {
         send_room_request(&self.tx, |result_tx| RoomRequest::SetPlaybackState {
             connection_id: self.connection_id,
@@ -2,6 +2,7 @@ mod handler;
 mod membership;
 mod state;
 
+use std::net::IpAddr;
 use std::sync::Arc;
 
 use futures_channel::mpsc::UnboundedSender;
@@ -19,10 +20,12 @@ use self::state::RoomState;
 
 use super::ConnectionId;
 use super::lobby::LobbyRequest;
-use super::{Tx, send};
+use super::{Outgoing, Tx, send};
 
 struct Member {
     user_id: i64,
+    name: Option<String>,
+    ip: IpAddr,
     is_admin: bool,
     tx: Tx,
 }
@@ -50,11 +53,15 @@ impl Room {
         connection_id: ConnectionId,
         tx: Tx,
         user_id: i64,
+        name: Option<String>,
+        ip: IpAddr,
     ) -> Result<RoomMembership, anyhow::Error> {
         send_room_request(&self.tx, |result_tx| RoomRequest::Join {
             tx,
             connection_id,
             user_id,
+            name,
+            ip,
             result_tx,
         })
         .await?;
@@ -67,6 +74,10 @@ impl Room {
         })
     }
 
+    pub async fn get_presence(&self) -> Result<am::Presence, anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::GetPresence { result_tx }).await
+    }
+
     pub async fn post(&self, post: lm::Post) -> Result<(), anyhow::Error> {
         send_room_request(&self.tx, |result_tx| RoomRequest::Post { post, result_tx }).await
     }
@@ -75,6 +86,27 @@ impl Room {
         send_room_request(&self.tx, |result_tx| RoomRequest::DeletePost { post_id, result_tx }).await
     }
 
+    pub async fn edit_post(&self, post: lm::Post) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::EditPost { post, result_tx }).await
+    }
+
+    pub async fn reaction(&self, change: lm::ReactionChange) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::Reaction { change, result_tx }).await
+    }
+
+    pub async fn purge_posts(&self, post_ids: Vec<i64>) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::PurgePosts { post_ids, result_tx }).await
+    }
+
+    /// Disconnect all members and stop the room request handler
+    pub async fn close(&self) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::Close { result_tx }).await
+    }
+
+    pub async fn ban(&self, ban: lm::Ban) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::Ban { ban, result_tx }).await
+    }
+
     pub async fn emote(&self, emote: lm::Emote) -> Result<(), anyhow::Error> {
         send_room_request(&self.tx, |result_tx| RoomRequest::Emote { emote, result_tx }).await
     }
@@ -86,4 +118,16 @@ impl Room {
     pub async fn set_content(&self, content: am::Content) -> Result<(), anyhow::Error> {
         send_room_request(&self.tx, |result_tx| RoomRequest::SetContent { content, result_tx }).await
     }
+
+    pub async fn set_queue(&self, queue: Vec<am::QueueItem>) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::SetQueue { queue, result_tx }).await
+    }
+
+    pub async fn set_end_behavior(&self, end_behavior: am::ContentEndBehavior) -> Result<(), anyhow::Error> {
+        send_room_request(&self.tx, |result_tx| RoomRequest::SetEndBehavior {
+            end_behavior,
+            result_tx,
+        })
+        .await
+    }
 }
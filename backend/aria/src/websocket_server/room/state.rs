@@ -1,6 +1,9 @@
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::VecDeque;
+use std::net::IpAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use aria_models::local::PlaybackStateAndTimestamp;
@@ -10,9 +13,10 @@ use tokio::sync::broadcast;
 use tokio::sync::mpsc::Sender;
 use tracing::{error, info};
 
-use aria_core::AriaCore;
+use aria_core::{AriaCore, parse_comment};
 use aria_models::api as am;
 use aria_models::local as lm;
+use aria_models::ws;
 
 use crate::websocket_server::ConnectionId;
 use crate::websocket_server::lobby::LobbyRequest;
@@ -20,10 +24,16 @@ use crate::websocket_server::lobby::LobbyRequest;
 use super::Room;
 use super::handler::RoomRequest;
 use super::handler::handle_room_requests;
-use super::{Member, Tx, send};
+use super::{Member, Outgoing, Tx, send};
 
 const MAX_POSTS: usize = 50;
 
+/// How long a user is considered to be typing after they last reported it
+const TYPING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Minimum interval between typing updates sent to members
+const TYPING_UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
 pub(super) struct RoomState {
     pub id: i32,
     pub name: String,
@@ -32,8 +42,14 @@ pub(super) struct RoomState {
     emotes: Vec<am::Emote>,
     master: ConnectionId,
     content: Option<am::Content>,
+    queue: Vec<am::QueueItem>,
+    end_behavior: am::ContentEndBehavior,
     playback_state_timestamp: DateTime<Utc>,
     playback_state: am::PlaybackState,
+    /// Users who are typing, and when they stop being considered to be typing
+    typists: HashMap<i64, Instant>,
+    typists_changed: bool,
+    last_typing_update: Option<Instant>,
 }
 
 impl RoomState {
@@ -54,6 +70,8 @@ impl RoomState {
                 .await
                 .context("Error getting recent posts")?;
 
+            let queue = core.get_queue(room.id).await.context("Error getting queue")?;
+
             // Prepare emotes
             let emotes = emotes.iter().map(am::Emote::from).collect();
 
@@ -70,8 +88,13 @@ impl RoomState {
                 emotes,
                 master: 0,
                 content: room.content,
+                queue,
+                end_behavior: room.end_behavior,
                 playback_state_timestamp,
                 playback_state,
+                typists: HashMap::new(),
+                typists_changed: false,
+                last_typing_update: None,
             };
 
             let (tx, rx) = futures_channel::mpsc::unbounded::<RoomRequest>();
@@ -96,34 +119,96 @@ impl RoomState {
         }
     }
 
-    pub fn join(&mut self, connection_id: ConnectionId, user_id: i64, tx: Tx) -> Result<(), anyhow::Error> {
+    pub fn join(
+        &mut self,
+        connection_id: ConnectionId,
+        user_id: i64,
+        name: Option<String>,
+        ip: IpAddr,
+        tx: Tx,
+    ) -> Result<(), anyhow::Error> {
         let member = Member {
             user_id,
+            name,
+            ip,
             is_admin: false,
             tx: tx.clone(),
         };
         self.members.insert(connection_id, member);
 
-        send(&tx, "content", &self.content)?;
-        send(&tx, "playbackstate", self.get_playback_state())?;
+        send(&tx, ws::ServerMessage::Content(self.content.clone()))?;
+        send(&tx, ws::ServerMessage::PlaybackState(self.get_playback_state()))?;
+        send(&tx, ws::ServerMessage::Queue(self.queue.clone()))?;
 
-        send(&tx, "joined", ())?;
+        if !self.typists.is_empty() {
+            send(&tx, ws::ServerMessage::Typing(self.get_typists(user_id)))?;
+        }
+
+        self.broadcast_presence();
 
         Ok(())
     }
 
     pub fn leave(&mut self, id: ConnectionId) -> Result<(), anyhow::Error> {
-        self.members.remove(&id);
+        if let Some(member) = self.members.remove(&id) {
+            // Users stop typing once their last connection leaves
+            if !self.members.values().any(|m| m.user_id == member.user_id) {
+                self.stop_typing(member.user_id);
+            }
+
+            self.broadcast_presence();
+        }
+
         Ok(())
     }
 
+    /// Get the users connected to the room
+    pub fn presence(&self) -> am::Presence {
+        // Go through connections in the order they were made, so that the most recent display name is used
+        let mut connections: Vec<_> = self.members.iter().collect();
+        connections.sort_unstable_by_key(|(id, _)| **id);
+
+        let mut members: BTreeMap<i64, am::PresenceMember> = BTreeMap::new();
+
+        for (id, m) in connections {
+            let member = members.entry(m.user_id).or_insert_with(|| am::PresenceMember {
+                user_id: m.user_id,
+                name: None,
+                admin: false,
+                master: false,
+            });
+
+            if m.name.is_some() {
+                member.name = m.name.clone();
+            }
+
+            member.admin |= m.is_admin;
+            member.master |= *id == self.master;
+        }
+
+        am::Presence {
+            viewers: self.members.len(),
+            members: members.into_values().collect(),
+        }
+    }
+
+    fn broadcast_presence(&self) {
+        let presence = self.presence();
+
+        for m in self.members.values() {
+            send(&m.tx, ws::ServerMessage::Presence(presence.clone()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+    }
+
     pub fn send_emotes(&self, connection_id: ConnectionId, since_id: i32) -> Result<(), anyhow::Error> {
         let Some(member) = self.members.get(&connection_id) else {
             return Err(anyhow::anyhow!("No member with connection ID {connection_id}!"));
         };
 
-        let emotes: Vec<_> = self.emotes.iter().filter(|e| e.id > since_id).collect();
-        send(&member.tx, "emotes", emotes)?;
+        let emotes: Vec<_> = self.emotes.iter().filter(|e| e.id > since_id).cloned().collect();
+        send(&member.tx, ws::ServerMessage::Emotes(emotes))?;
 
         Ok(())
     }
@@ -140,16 +225,116 @@ impl RoomState {
             .map(|p| {
                 let mut post = am::Post::from(p);
                 post.you = p.user_id == member.user_id;
+                post.replies_to_you = p.is_reply_to_user(member.user_id);
+                post.reactions = p.reactions_for_user(Some(member.user_id));
 
                 post
             })
             .collect();
 
-        send(&member.tx, "oldposts", posts)?;
+        send(&member.tx, ws::ServerMessage::OldPosts(posts))?;
+
+        Ok(())
+    }
+
+    pub fn set_typing(&mut self, connection_id: ConnectionId, typing: bool) -> Result<(), anyhow::Error> {
+        let user_id = self
+            .members
+            .get(&connection_id)
+            .context("Error getting member")?
+            .user_id;
+
+        if typing {
+            if self.typists.insert(user_id, Instant::now() + TYPING_TIMEOUT).is_none() {
+                self.typists_changed = true;
+                self.update_typing();
+            }
+        } else {
+            self.stop_typing(user_id);
+        }
 
         Ok(())
     }
 
+    fn stop_typing(&mut self, user_id: i64) {
+        if self.typists.remove(&user_id).is_some() {
+            self.typists_changed = true;
+            self.update_typing();
+        }
+    }
+
+    /// Expire typists, and send changes to members unless an update was sent too recently
+    pub fn update_typing(&mut self) {
+        let now = Instant::now();
+
+        let typist_count = self.typists.len();
+        self.typists.retain(|_, expires_at| *expires_at > now);
+
+        if self.typists.len() != typist_count {
+            self.typists_changed = true;
+        }
+
+        if !self.typists_changed
+            || self
+                .last_typing_update
+                .is_some_and(|t| now < t + TYPING_UPDATE_INTERVAL)
+        {
+            return;
+        }
+
+        for m in self.members.values() {
+            send(&m.tx, ws::ServerMessage::Typing(self.get_typists(m.user_id)))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+
+        self.typists_changed = false;
+        self.last_typing_update = Some(now);
+    }
+
+    /// Get the time until typists need to be updated, if ever
+    pub fn get_typing_update_delay(&self) -> Option<Duration> {
+        let next_expiry = self.typists.values().min().copied();
+
+        let next_update = if self.typists_changed {
+            self.last_typing_update.map(|t| t + TYPING_UPDATE_INTERVAL)
+        } else {
+            None
+        };
+
+        [next_expiry, next_update]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|t| t.saturating_duration_since(Instant::now()))
+    }
+
+    /// Get the users who are typing, except the specified user
+    fn get_typists(&self, except_user_id: i64) -> Vec<am::Typist> {
+        let mut typists: Vec<_> = self
+            .typists
+            .keys()
+            .filter(|user_id| **user_id != except_user_id)
+            .map(|user_id| am::Typist {
+                user_id: *user_id,
+                name: self.get_member_name(*user_id),
+            })
+            .collect();
+
+        typists.sort_unstable_by_key(|t| t.user_id);
+
+        typists
+    }
+
+    /// Get the display name of a user's most recent connection that has one
+    fn get_member_name(&self, user_id: i64) -> Option<String> {
+        self.members
+            .iter()
+            .filter(|(_, m)| m.user_id == user_id && m.name.is_some())
+            .max_by_key(|(id, _)| **id)
+            .and_then(|(_, m)| m.name.clone())
+    }
+
     /// Add post
     pub fn post(&mut self, post: lm::Post) -> Result<(), anyhow::Error> {
         // If the maximum number of posts is reached, remove the oldest one.
@@ -157,17 +342,30 @@ impl RoomState {
             self.posts.pop_front();
         }
 
-        let mut post_am = am::Post::from(&post);
-        let post_user_id = post.user_id;
+        // Record the new post as a reply on the posts it replies to
+        for reply_to in post.replies_to.iter() {
+            if let Some(p) = self.posts.iter_mut().find(|p| p.id == reply_to.post_id) {
+                p.replied_by.push(post.id);
+            }
+        }
 
-        self.posts.push_back(post);
+        let mut post_am = am::Post::from(&post);
 
         for m in self.members.values() {
-            post_am.you = post_user_id == m.user_id;
+            post_am.you = post.user_id == m.user_id;
+            post_am.replies_to_you = post.is_reply_to_user(m.user_id);
+            post_am.reactions = post.reactions_for_user(Some(m.user_id));
 
-            send(&m.tx, "post", &post_am).map_err(|err| error!("{err:?}")).ok();
+            send(&m.tx, ws::ServerMessage::Post(post_am.clone()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
         }
 
+        // Users are done typing once they post
+        self.stop_typing(post.user_id);
+
+        self.posts.push_back(post);
+
         Ok(())
     }
 
@@ -175,8 +373,12 @@ impl RoomState {
     pub fn delete_post(&mut self, post_id: i64) -> Result<(), anyhow::Error> {
         self.posts.retain(|p| p.id != post_id);
 
+        for p in self.posts.iter_mut() {
+            p.replied_by.retain(|id| *id != post_id);
+        }
+
         for m in self.members.values() {
-            send(&m.tx, "delete-post", post_id)
+            send(&m.tx, ws::ServerMessage::DeletePost(post_id))
                 .map_err(|err| error!("{err:?}"))
                 .ok();
         }
@@ -184,6 +386,115 @@ impl RoomState {
         Ok(())
     }
 
+    /// Replace an edited post
+    pub fn edit_post(&mut self, post: lm::Post) -> Result<(), anyhow::Error> {
+        // Quote links may have changed, so update which cached posts it replies to
+        for p in self.posts.iter_mut() {
+            p.replied_by.retain(|id| *id != post.id);
+
+            if post.replies_to.iter().any(|r| r.post_id == p.id) {
+                p.replied_by.push(post.id);
+                p.replied_by.sort_unstable();
+            }
+        }
+
+        let mut post_am = am::Post::from(&post);
+
+        for m in self.members.values() {
+            post_am.you = post.user_id == m.user_id;
+            post_am.replies_to_you = post.is_reply_to_user(m.user_id);
+            post_am.reactions = post.reactions_for_user(Some(m.user_id));
+
+            send(&m.tx, ws::ServerMessage::PostEdited(post_am.clone()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+
+        if let Some(p) = self.posts.iter_mut().find(|p| p.id == post.id) {
+            *p = post;
+        }
+
+        Ok(())
+    }
+
+    /// Update the reactions to a post
+    pub fn reaction(&mut self, change: &lm::ReactionChange) -> Result<(), anyhow::Error> {
+        if let Some(p) = self.posts.iter_mut().find(|p| p.id == change.post_id) {
+            p.apply_reaction_change(change);
+        }
+
+        let mut update = am::ReactionUpdate {
+            post_id: change.post_id,
+            reaction: change.reaction.clone(),
+            count: change.count,
+            you_reacted: None,
+        };
+
+        for m in self.members.values() {
+            update.you_reacted = (m.user_id == change.user_id).then_some(change.added);
+
+            send(&m.tx, ws::ServerMessage::Reaction(update.clone()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+
+        Ok(())
+    }
+
+    /// Delete multiple posts
+    pub fn purge_posts(&mut self, post_ids: &[i64]) -> Result<(), anyhow::Error> {
+        self.posts.retain(|p| !post_ids.contains(&p.id));
+
+        for p in self.posts.iter_mut() {
+            p.replied_by.retain(|id| !post_ids.contains(id));
+        }
+
+        for m in self.members.values() {
+            send(&m.tx, ws::ServerMessage::DeletePosts(post_ids.to_vec()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+
+        Ok(())
+    }
+
+    /// Notify members affected by a ban and disconnect them
+    pub fn ban(&mut self, ban: &lm::Ban) -> Result<(), anyhow::Error> {
+        let ban_am = am::Ban::from(ban);
+
+        let banned: Vec<ConnectionId> = self
+            .members
+            .iter()
+            .filter(|(_, m)| ban.user_id == Some(m.user_id) || ban.ip == Some(m.ip))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in banned {
+            if let Some(m) = self.members.get(&id) {
+                send(&m.tx, ws::ServerMessage::Banned(ban_am.clone()))
+                    .map_err(|err| error!("{err:?}"))
+                    .ok();
+                m.tx.unbounded_send(Outgoing::Close).ok();
+            }
+
+            self.leave(id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Notify all members that the room has been deleted and disconnect them
+    pub fn close(&mut self) -> Result<(), anyhow::Error> {
+        for (_, m) in self.members.drain() {
+            send(&m.tx, ws::ServerMessage::RoomDeleted)
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+            m.tx.unbounded_send(Outgoing::Close).ok();
+        }
+
+        Ok(())
+    }
+
     pub fn set_content(&mut self, content: am::Content) -> Result<(), anyhow::Error> {
         // Set content
         self.content = Some(content);
@@ -199,10 +510,59 @@ impl RoomState {
         Ok(())
     }
 
+    pub fn set_queue(&mut self, queue: Vec<am::QueueItem>) -> Result<(), anyhow::Error> {
+        self.queue = queue;
+
+        // Broadcast queue change to members
+        for m in self.members.values() {
+            send(&m.tx, ws::ServerMessage::Queue(self.queue.clone()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+
+        Ok(())
+    }
+
+    pub fn set_end_behavior(&mut self, end_behavior: am::ContentEndBehavior) -> Result<(), anyhow::Error> {
+        self.end_behavior = end_behavior;
+
+        Ok(())
+    }
+
+    /// Apply end-of-content behavior when content playback has finished
+    pub async fn end_content(&mut self, core: &AriaCore) -> Result<(), anyhow::Error> {
+        let rate = self.playback_state.rate;
+
+        let pbs = match self.end_behavior {
+            am::ContentEndBehavior::Loop => am::PlaybackState {
+                time: 0.,
+                rate,
+                is_playing: true,
+            },
+            am::ContentEndBehavior::Hold => am::PlaybackState {
+                time: self.content.as_ref().and_then(|c| c.duration).unwrap_or_default(),
+                rate,
+                is_playing: false,
+            },
+            am::ContentEndBehavior::Stop => am::PlaybackState::default(),
+        };
+
+        for m in self.members.values() {
+            send(&m.tx, ws::ServerMessage::ContentEnded(self.end_behavior))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
+        }
+
+        self.set_playback_state(0, &pbs, core).await
+    }
+
     pub fn set_admin(&mut self, id: ConnectionId) -> Result<(), anyhow::Error> {
         let me = self.members.get_mut(&id).context("Error getting member")?;
 
-        me.is_admin = true;
+        if !me.is_admin {
+            me.is_admin = true;
+            self.broadcast_presence();
+        }
 
         Ok(())
     }
@@ -220,11 +580,12 @@ impl RoomState {
 
         // Notify the previous master that it is no longer mater.
         if let Some(old_master) = self.members.get(&self.master) {
-            send(&old_master.tx, "not-master", ())?;
+            send(&old_master.tx, ws::ServerMessage::NotMaster)?;
         }
 
         // Set new master
         self.master = id;
+        self.broadcast_presence();
 
         Ok(())
     }
@@ -237,6 +598,7 @@ impl RoomState {
 
         // Clear master
         self.master = 0;
+        self.broadcast_presence();
 
         Ok(())
     }
@@ -270,7 +632,9 @@ impl RoomState {
         let ps = self.get_playback_state();
 
         for m in self.members.values() {
-            send(&m.tx, "playbackstate", ps).map_err(|err| error!("{err:?}")).ok();
+            send(&m.tx, ws::ServerMessage::PlaybackState(ps))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
         }
 
         Ok(())
@@ -282,9 +646,12 @@ impl RoomState {
 
         self.emotes.retain(|e| e.name != emote.name);
         self.emotes.push(emote.clone());
+        self.tokenize_posts();
 
         for m in self.members.values() {
-            send(&m.tx, "emote", &emote).map_err(|err| error!("{err:?}")).ok();
+            send(&m.tx, ws::ServerMessage::Emote(emote.clone()))
+                .map_err(|err| error!("{err:?}"))
+                .ok();
         }
 
         Ok(())
@@ -294,9 +661,10 @@ impl RoomState {
     pub fn delete_emote(&mut self, emote_id: i32) -> Result<(), anyhow::Error> {
         if let Some(index) = self.emotes.iter().position(|e| e.id == emote_id) {
             let emote = self.emotes.remove(index);
+            self.tokenize_posts();
 
             for m in self.members.values() {
-                send(&m.tx, "delete-emote", &emote.name)
+                send(&m.tx, ws::ServerMessage::DeleteEmote(emote.name.clone()))
                     .map_err(|err| error!("{err:?}"))
                     .ok();
             }
@@ -305,6 +673,17 @@ impl RoomState {
         Ok(())
     }
 
+    /// Reparse the comments of cached posts, so that their emote references reflect the current emotes
+    fn tokenize_posts(&mut self) {
+        let emotes = &self.emotes;
+
+        for post in self.posts.iter_mut() {
+            if let Some(comment) = &post.comment {
+                post.tokens = parse_comment(comment, |name| emotes.iter().any(|e| e.name == name));
+            }
+        }
+    }
+
     pub fn is_deserted(&self) -> bool {
         self.members.is_empty()
     }
@@ -346,7 +725,9 @@ impl RoomState {
     fn send_content(&self) -> Result<(), anyhow::Error> {
         if let Some(content) = self.content.as_ref() {
             for m in self.members.values() {
-                send(&m.tx, "content", content).map_err(|err| error!("{err:?}")).ok();
+                send(&m.tx, ws::ServerMessage::Content(Some(content.clone())))
+                    .map_err(|err| error!("{err:?}"))
+                    .ok();
             }
         }
 
@@ -0,0 +1,71 @@
+use aria_models::local as lm;
+
+use crate::{
+    Notification,
+    transform::{dbm_instance_stats_to_lm, dbm_room_summary_to_lm},
+    util::password::constant_time_eq,
+};
+
+use super::AriaCore;
+
+impl AriaCore {
+    /// Check an admin key against the configured admin keys.
+    /// Returns an identifier for the matching key, which can later be used to check that it is still valid.
+    pub fn admin_login(&self, key: &str) -> Option<String> {
+        let admin_keys = self.config.admin_keys.as_deref().unwrap_or_default();
+
+        admin_keys
+            .iter()
+            .find(|k| !k.is_empty() && constant_time_eq(k.as_bytes(), key.as_bytes()))
+            .map(|k| admin_key_id(k))
+    }
+
+    /// Check whether an admin key identifier still refers to a configured admin key
+    pub fn is_admin_key_valid(&self, key_id: &str) -> bool {
+        let admin_keys = self.config.admin_keys.as_deref().unwrap_or_default();
+
+        admin_keys.iter().any(|k| !k.is_empty() && admin_key_id(k) == key_id)
+    }
+
+    pub async fn get_rooms(&self) -> Result<Vec<lm::RoomSummary>, anyhow::Error> {
+        let rooms = self.store.get_rooms().await?;
+
+        Ok(rooms.into_iter().map(dbm_room_summary_to_lm).collect())
+    }
+
+    pub async fn delete_room(&self, room_id: i32) -> Result<bool, anyhow::Error> {
+        // Images have to be looked up before their posts are deleted along with the room
+        let images = self.store.get_room_images(room_id).await?;
+
+        let success = self.store.delete_room(room_id).await?;
+
+        if success {
+            self.notify(Notification::DeleteRoom(room_id))?;
+            self.delete_unused_image_files(images).await?;
+        }
+
+        Ok(success)
+    }
+
+    /// Delete all posts in a room, returning the number of posts deleted
+    pub async fn purge_room_posts(&self, room_id: i32) -> Result<usize, anyhow::Error> {
+        let post_ids = self.store.purge_room_posts(room_id).await?;
+        let count = post_ids.len();
+
+        if count > 0 {
+            self.notify(Notification::PurgePosts(room_id, post_ids))?;
+        }
+
+        Ok(count)
+    }
+
+    pub async fn get_instance_stats(&self) -> Result<lm::InstanceStats, anyhow::Error> {
+        let stats = self.store.get_instance_stats().await?;
+
+        Ok(dbm_instance_stats_to_lm(stats))
+    }
+}
+
+fn admin_key_id(key: &str) -> String {
+    blake3::hash(key.as_bytes()).to_hex()[..16].to_owned()
+}
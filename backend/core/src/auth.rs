@@ -1,15 +1,20 @@
-use aria_models::local::RefreshRefreshTokenResult;
-use aria_store::AriaStore;
+use aria_models::local::{RefreshRefreshTokenResult, Session};
 use serde::{Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
 use super::AriaCore;
 
 impl AriaCore {
-    pub async fn create_refresh_token<T: Serialize>(&self, claims: &T) -> Result<Uuid, anyhow::Error> {
+    /// Create a refresh token for the specified claims.
+    /// If the claims are tied to a room, the token is revoked along with the rest of the room's tokens.
+    pub async fn create_refresh_token<T: Serialize>(
+        &self,
+        room_id: Option<i32>,
+        claims: &T,
+    ) -> Result<Uuid, anyhow::Error> {
         let json = serde_json::to_string(claims)?;
 
-        self.store.create_refresh_token(&json).await
+        self.store.create_refresh_token(&json, room_id).await
     }
 
     pub async fn refresh_refresh_token<T: DeserializeOwned>(
@@ -26,4 +31,34 @@ impl AriaCore {
             Ok(None)
         }
     }
+
+    pub async fn revoke_room_refresh_tokens(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        self.store.revoke_room_refresh_tokens(room_id).await
+    }
+
+    /// Revoke the entire token family of a refresh token
+    pub async fn revoke_refresh_token_family(&self, token: Uuid) -> Result<bool, anyhow::Error> {
+        self.store.revoke_refresh_token_family(token).await
+    }
+
+    pub async fn get_room_sessions<T: DeserializeOwned>(&self, room_id: i32) -> Result<Vec<Session<T>>, anyhow::Error> {
+        let sessions = self.store.get_room_sessions(room_id).await?;
+
+        sessions
+            .into_iter()
+            .map(|s| {
+                Ok(Session {
+                    id: s.family.unwrap(),
+                    created_at: s.created_at.unwrap(),
+                    refreshed_at: s.refreshed_at.unwrap(),
+                    expires_at: s.expires_at.unwrap(),
+                    claims: serde_json::from_str(&s.claims.unwrap())?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn revoke_room_session(&self, room_id: i32, session_id: i64) -> Result<bool, anyhow::Error> {
+        self.store.revoke_room_session(room_id, session_id).await
+    }
 }
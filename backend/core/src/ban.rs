@@ -0,0 +1,72 @@
+use std::fmt;
+use std::net::IpAddr;
+
+use aria_models::local as lm;
+use chrono::{DateTime, Utc};
+
+use crate::{Notification, transform::dbm_ban_to_lm};
+
+use super::AriaCore;
+
+/// Error returned when a banned user attempts an action in a room
+#[derive(Debug)]
+pub struct Banned(pub lm::Ban);
+
+impl AriaCore {
+    pub async fn get_bans(&self, room_id: i32) -> Result<Vec<lm::Ban>, anyhow::Error> {
+        let bans = self.store.get_bans(room_id).await?;
+
+        Ok(bans.into_iter().map(dbm_ban_to_lm).collect())
+    }
+
+    /// Get the active ban, if any, matching either the user ID or IP address
+    pub async fn get_active_ban(
+        &self,
+        room_id: i32,
+        user_id: i64,
+        ip: IpAddr,
+    ) -> Result<Option<lm::Ban>, anyhow::Error> {
+        let ban = self.store.get_active_ban(room_id, user_id, ip).await?;
+
+        Ok(ban.map(dbm_ban_to_lm))
+    }
+
+    /// Ban the user ID and IP address of the author of a post.
+    /// Returns None if the post does not exist, or has neither a user ID nor an IP address.
+    pub async fn ban_from_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reason: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<lm::Ban>, anyhow::Error> {
+        let Some(ban) = self
+            .store
+            .create_ban_from_post(room_id, post_id, reason, expires_at)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let ban = dbm_ban_to_lm(ban);
+
+        self.notify(Notification::Ban(room_id, ban.clone()))?;
+
+        Ok(Some(ban))
+    }
+
+    pub async fn delete_ban(&self, room_id: i32, ban_id: i32) -> Result<bool, anyhow::Error> {
+        self.store.delete_ban(room_id, ban_id).await
+    }
+}
+
+impl fmt::Display for Banned {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0.reason {
+            Some(reason) => write!(f, "Banned: {reason}"),
+            None => write!(f, "Banned"),
+        }
+    }
+}
+
+impl std::error::Error for Banned {}
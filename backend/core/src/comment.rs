@@ -0,0 +1,301 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use aria_models::local as lm;
+
+const SPOILER_OPEN: &str = "[spoiler]";
+const SPOILER_CLOSE: &str = "[/spoiler]";
+const CODE_BLOCK_DELIMITER: &str = "```";
+const CODE_DELIMITER: char = '`';
+
+static RE_EMOTE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^!([\w-]+)").unwrap());
+static RE_QUOTE_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"^>>(\d+)").unwrap());
+static RE_GREENTEXT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^>[^\n]+").unwrap());
+// Links end before any '[', so that they do not swallow a closing spoiler tag
+static RE_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r"^https?://[^\s\[]+").unwrap());
+
+/// Parse comment markup into tokens.
+///
+/// Emote references are only recognized for emotes for which `is_emote` returns true,
+/// and are otherwise left as text. Spoilers can not be nested, and an unclosed spoiler extends to the end of the comment.
+pub fn parse_comment(comment: &str, is_emote: impl Fn(&str) -> bool) -> Vec<lm::CommentToken> {
+    let mut parser = CommentParser::default();
+
+    let mut rest = comment;
+    let mut line_start = true;
+
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = if parser.spoiler.is_none() && rest.starts_with(SPOILER_OPEN) {
+            parser.begin_spoiler();
+            (None, SPOILER_OPEN.len())
+        } else if parser.spoiler.is_some() && rest.starts_with(SPOILER_CLOSE) {
+            parser.end_spoiler();
+            (None, SPOILER_CLOSE.len())
+        } else if let Some((code, len)) = code_block(rest) {
+            (
+                Some(lm::CommentToken::Code {
+                    text: code.to_owned(),
+                    block: true,
+                }),
+                len,
+            )
+        } else if let Some((code, len)) = inline_code(rest) {
+            (
+                Some(lm::CommentToken::Code {
+                    text: code.to_owned(),
+                    block: false,
+                }),
+                len,
+            )
+        } else if let Some(m) = RE_EMOTE.captures(rest).filter(|m| is_emote(&m[1])) {
+            (Some(lm::CommentToken::Emote { name: m[1].to_owned() }), m[0].len())
+        } else if let Some((post_id, len)) = RE_QUOTE_LINK
+            .captures(rest)
+            .and_then(|m| Some((m[1].parse().ok()?, m[0].len())))
+        {
+            (Some(lm::CommentToken::QuoteLink { post_id }), len)
+        } else if let Some(m) = RE_GREENTEXT.find(rest).filter(|_| line_start) {
+            let mut text = m.as_str();
+
+            // Greentext inside a spoiler ends at the closing tag
+            if parser.spoiler.is_some() {
+                text = text.split(SPOILER_CLOSE).next().unwrap_or(text);
+            }
+
+            (Some(lm::CommentToken::Greentext { text: text.to_owned() }), text.len())
+        } else if let Some(m) = RE_LINK.find(rest) {
+            (
+                Some(lm::CommentToken::Link {
+                    url: m.as_str().to_owned(),
+                }),
+                m.len(),
+            )
+        } else if c == '\n' {
+            (Some(lm::CommentToken::Newline), 1)
+        } else {
+            parser.text.push(c);
+            (None, c.len_utf8())
+        };
+
+        if let Some(token) = token {
+            parser.push(token);
+        }
+
+        line_start = c == '\n';
+        rest = &rest[len..];
+    }
+
+    parser.end_spoiler();
+    parser.flush_text();
+
+    parser.tokens
+}
+
+#[derive(Default)]
+struct CommentParser {
+    tokens: Vec<lm::CommentToken>,
+    spoiler: Option<Vec<lm::CommentToken>>,
+    text: String,
+}
+
+impl CommentParser {
+    fn current(&mut self) -> &mut Vec<lm::CommentToken> {
+        self.spoiler.as_mut().unwrap_or(&mut self.tokens)
+    }
+
+    fn flush_text(&mut self) {
+        if !self.text.is_empty() {
+            let text = std::mem::take(&mut self.text);
+            self.current().push(lm::CommentToken::Text { text });
+        }
+    }
+
+    fn push(&mut self, token: lm::CommentToken) {
+        self.flush_text();
+        self.current().push(token);
+    }
+
+    fn begin_spoiler(&mut self) {
+        self.flush_text();
+        self.spoiler = Some(Vec::new());
+    }
+
+    fn end_spoiler(&mut self) {
+        self.flush_text();
+
+        if let Some(children) = self.spoiler.take() {
+            self.tokens.push(lm::CommentToken::Spoiler { children });
+        }
+    }
+}
+
+/// Match a code block delimited by "```", returning the code and the length of the match
+fn code_block(s: &str) -> Option<(&str, usize)> {
+    let rest = s.strip_prefix(CODE_BLOCK_DELIMITER)?;
+    let end = rest.find(CODE_BLOCK_DELIMITER)?;
+
+    // Ignore the line break following the opening delimiter
+    let code = &rest[..end];
+    let code = code.strip_prefix('\n').unwrap_or(code);
+
+    Some((code, end + CODE_BLOCK_DELIMITER.len() * 2))
+}
+
+/// Match inline code delimited by "`" on a single line, returning the code and the length of the match
+fn inline_code(s: &str) -> Option<(&str, usize)> {
+    let rest = s.strip_prefix(CODE_DELIMITER)?;
+    let end = rest.find([CODE_DELIMITER, '\n'])?;
+
+    if end == 0 || !rest[end..].starts_with(CODE_DELIMITER) {
+        return None;
+    }
+
+    Some((&rest[..end], end + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use lm::CommentToken as T;
+
+    fn parse(comment: &str) -> Vec<T> {
+        parse_comment(comment, |name| name == "pog")
+    }
+
+    fn text(text: &str) -> T {
+        T::Text { text: text.to_owned() }
+    }
+
+    #[test]
+    fn spoiler() {
+        assert_eq!(
+            parse("a [spoiler]b !pog[/spoiler] c"),
+            vec![
+                text("a "),
+                T::Spoiler {
+                    children: vec![text("b "), T::Emote { name: "pog".to_owned() }],
+                },
+                text(" c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_spoiler_extends_to_end() {
+        assert_eq!(
+            parse("[spoiler]a\nb"),
+            vec![T::Spoiler {
+                children: vec![text("a"), T::Newline, text("b")],
+            }]
+        );
+    }
+
+    #[test]
+    fn link_ends_before_spoiler_close() {
+        assert_eq!(
+            parse("[spoiler]https://example.com/a[/spoiler]b"),
+            vec![
+                T::Spoiler {
+                    children: vec![T::Link {
+                        url: "https://example.com/a".to_owned(),
+                    }],
+                },
+                text("b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn greentext_ends_before_spoiler_close() {
+        assert_eq!(
+            parse("[spoiler]a\n>implying[/spoiler] b"),
+            vec![
+                T::Spoiler {
+                    children: vec![
+                        text("a"),
+                        T::Newline,
+                        T::Greentext {
+                            text: ">implying".to_owned(),
+                        },
+                    ],
+                },
+                text(" b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn code_block_is_not_parsed() {
+        assert_eq!(
+            parse("```\n>>1 !pog [spoiler]```after"),
+            vec![
+                T::Code {
+                    text: ">>1 !pog [spoiler]".to_owned(),
+                    block: true,
+                },
+                text("after"),
+            ]
+        );
+    }
+
+    #[test]
+    fn inline_code() {
+        assert_eq!(
+            parse("a `b` `c\nd`"),
+            vec![
+                text("a "),
+                T::Code {
+                    text: "b".to_owned(),
+                    block: false,
+                },
+                text(" `c"),
+                T::Newline,
+                text("d`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn quote_link_is_not_greentext() {
+        assert_eq!(
+            parse(">>12 hi\n>quote"),
+            vec![
+                T::QuoteLink { post_id: 12 },
+                text(" hi"),
+                T::Newline,
+                T::Greentext {
+                    text: ">quote".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn greentext_only_at_line_start() {
+        assert_eq!(parse("a >b"), vec![text("a >b")]);
+    }
+
+    #[test]
+    fn unknown_emote_is_text() {
+        assert_eq!(
+            parse("!pog !nope"),
+            vec![T::Emote { name: "pog".to_owned() }, text(" !nope")]
+        );
+    }
+
+    #[test]
+    fn multi_byte_text() {
+        assert_eq!(
+            parse("日本語 !pog ✨ https://例え.jp/パス"),
+            vec![
+                text("日本語 "),
+                T::Emote { name: "pog".to_owned() },
+                text(" ✨ "),
+                T::Link {
+                    url: "https://例え.jp/パス".to_owned(),
+                },
+            ]
+        );
+    }
+}
@@ -1,6 +1,7 @@
 use std::env;
 use std::fs;
 use std::io::Write;
+use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
@@ -23,8 +24,82 @@ pub struct AriaConfig {
 
     pub jwt_secret: Option<String>,
 
+    pub admin_keys: Option<Vec<String>>,
+
     pub max_emote_size: Option<usize>,
     pub max_image_size: Option<usize>,
+
+    pub room_lifetime_days: Option<u32>,
+
+    pub post_edit_window_minutes: Option<u32>,
+
+    pub rate_limit: Option<RateLimitConfig>,
+
+    pub listen: Option<ListenConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RateLimitConfig {
+    pub post: Option<RateLimit>,
+    pub image_post: Option<RateLimit>,
+    pub reaction: Option<RateLimit>,
+    pub websocket_message: Option<RateLimit>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListenConfig {
+    /// TCP addresses to listen on for HTTP and WebSocket connections
+    pub addresses: Option<Vec<SocketAddr>>,
+    /// Path of a Unix domain socket to listen on
+    pub unix_socket: Option<PathBuf>,
+    /// Permissions of the Unix domain socket file
+    pub unix_socket_mode: Option<u32>,
+    /// Serve HTTPS on the TCP addresses
+    pub tls: Option<TlsConfig>,
+    /// Where to get the client IP address from, such as 'ConnectInfo' or 'RightmostXForwardedFor'
+    pub client_ip_source: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct TlsConfig {
+    /// Path of a PEM file containing the certificate chain
+    pub cert_path: PathBuf,
+    /// Path of a PEM file containing the private key
+    pub key_path: PathBuf,
+}
+
+impl RateLimitConfig {
+    fn validate(&self) -> Result<(), anyhow::Error> {
+        let limits = [
+            ("post", self.post),
+            ("image-post", self.image_post),
+            ("reaction", self.reaction),
+            ("websocket-message", self.websocket_message),
+        ];
+
+        for (name, limit) in limits {
+            // A bucket that is never replenished would lock clients out forever
+            if limit.is_some_and(|l| l.burst > 0 && l.per_minute == 0) {
+                anyhow::bail!("Rate limit '{name}' has a burst but a per-minute of 0");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Token bucket rate limit
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RateLimit {
+    /// Maximum number of actions that can be performed in a burst.
+    /// Setting this to 0 disables the limit.
+    pub burst: u32,
+    /// Number of actions replenished per minute
+    pub per_minute: u32,
 }
 
 impl AriaConfig {
@@ -98,6 +173,10 @@ impl FromStr for AriaConfig {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let config: Self = toml::from_str(s).context("Error parsing config file")?;
 
+        if let Some(rate_limit) = config.rate_limit.as_ref() {
+            rate_limit.validate()?;
+        }
+
         Ok(config)
     }
 }
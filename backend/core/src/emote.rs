@@ -5,7 +5,7 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 
 use aria_models::local as lm;
-use aria_store::{AriaStore, models as dbm};
+use aria_store::models as dbm;
 
 use super::AriaCore;
 use crate::{
@@ -0,0 +1,208 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use chrono::Utc;
+use tracing::warn;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use aria_models::api as am;
+use aria_models::local as lm;
+
+use super::AriaCore;
+
+/// Version of the room archive format written by [`AriaCore::export_room`]
+pub const ROOM_ARCHIVE_VERSION: u32 = 1;
+
+pub const ROOM_ARCHIVE_MANIFEST: &str = "room.json";
+pub const ROOM_ARCHIVE_POSTS: &str = "posts.jsonl";
+pub const ROOM_ARCHIVE_EMOTES: &str = "emotes.json";
+
+const EXPORT_PAGE_SIZE: u32 = 200;
+
+/// Locations of the files to include in an archive
+struct ArchiveFilePaths {
+    original_image_path: PathBuf,
+    original_emote_path: PathBuf,
+    public_image_path: PathBuf,
+    public_thumbnail_path: PathBuf,
+    public_emote_path: PathBuf,
+}
+
+impl AriaCore {
+    /// Export a room's entire post history and its emotes as a ZIP archive written to `path`.
+    ///
+    /// Posts are stored one per line in "posts.jsonl" and emotes in "emotes.json",
+    /// and the files they reference are included under "files/", using the same layout as the files path.
+    pub async fn export_room(&self, room_id: i32, path: &Path) -> Result<am::RoomArchive, anyhow::Error> {
+        let room = self.get_room(room_id).await?.context("Room not found")?;
+
+        // Page backwards through the post history, then restore chronological order
+        let mut pages = Vec::new();
+        let mut before_id = None;
+
+        loop {
+            let page = self.get_posts(room_id, before_id, Some(EXPORT_PAGE_SIZE)).await?;
+
+            let Some(first) = page.first() else {
+                break;
+            };
+
+            before_id = Some(first.id);
+
+            let is_last_page = page.len() < EXPORT_PAGE_SIZE as usize;
+            pages.push(page);
+
+            if is_last_page {
+                break;
+            }
+        }
+
+        let posts: Vec<lm::Post> = pages.into_iter().rev().flatten().collect();
+        let emotes = self.get_emotes(room_id).await?;
+
+        let manifest = am::RoomArchive {
+            version: ROOM_ARCHIVE_VERSION,
+            name: room.name,
+            exported_at: Utc::now(),
+            post_count: posts.len(),
+            emote_count: emotes.len(),
+        };
+
+        let paths = ArchiveFilePaths {
+            original_image_path: self.original_image_path.clone(),
+            original_emote_path: self.original_emote_path.clone(),
+            public_image_path: self.public_image_path.clone(),
+            public_thumbnail_path: self.public_thumbnail_path.clone(),
+            public_emote_path: self.public_emote_path.clone(),
+        };
+
+        let path = path.to_owned();
+        let archive_manifest = manifest.clone();
+
+        tokio::task::spawn_blocking(move || write_room_archive(&path, &archive_manifest, &posts, &emotes, &paths))
+            .await??;
+
+        Ok(manifest)
+    }
+}
+
+fn write_room_archive(
+    path: &Path,
+    manifest: &am::RoomArchive,
+    posts: &[lm::Post],
+    emotes: &[lm::Emote],
+    paths: &ArchiveFilePaths,
+) -> Result<(), anyhow::Error> {
+    let file = File::create(path).with_context(|| format!("Error creating archive '{}'", path.display()))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+
+    let json_options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    zip.start_file(ROOM_ARCHIVE_MANIFEST, json_options)?;
+    serde_json::to_writer_pretty(&mut zip, manifest)?;
+
+    zip.start_file(ROOM_ARCHIVE_POSTS, json_options)?;
+    for post in posts {
+        serde_json::to_writer(&mut zip, &am::Post::from(post))?;
+        zip.write_all(b"\n")?;
+    }
+
+    zip.start_file(ROOM_ARCHIVE_EMOTES, json_options)?;
+    serde_json::to_writer_pretty(&mut zip, &emotes.iter().map(am::Emote::from).collect::<Vec<_>>())?;
+
+    let mut files = ArchiveFileWriter {
+        zip,
+        added: HashSet::new(),
+    };
+
+    let original_images = index_original_files(&paths.original_image_path)?;
+    for image in posts.iter().filter_map(|p| p.image.as_ref()) {
+        if let Some(filename) = original_images.get(&image.hash) {
+            files.add("files/original/i", &paths.original_image_path, filename)?;
+        }
+
+        files.add(
+            "files/public/i",
+            &paths.public_image_path,
+            &format!("{}.{}", image.hash, image.ext),
+        )?;
+        files.add(
+            "files/public/t",
+            &paths.public_thumbnail_path,
+            &format!("{}.{}", image.hash, image.tn_ext),
+        )?;
+    }
+
+    let original_emotes = index_original_files(&paths.original_emote_path)?;
+    for emote in emotes {
+        if let Some(filename) = original_emotes.get(&emote.hash) {
+            files.add("files/original/e", &paths.original_emote_path, filename)?;
+        }
+
+        files.add(
+            "files/public/e",
+            &paths.public_emote_path,
+            &format!("{}.{}", emote.hash, emote.ext),
+        )?;
+    }
+
+    files.zip.finish()?.flush()?;
+
+    Ok(())
+}
+
+struct ArchiveFileWriter<W: Write + io::Seek> {
+    zip: ZipWriter<W>,
+    added: HashSet<String>,
+}
+
+impl<W: Write + io::Seek> ArchiveFileWriter<W> {
+    /// Add a file to the archive, unless it has already been added.
+    /// Missing files are skipped with a warning, so that one lost file does not prevent archiving the rest.
+    fn add(&mut self, archive_dir: &str, dir: &Path, filename: &str) -> Result<(), anyhow::Error> {
+        let archive_path = format!("{archive_dir}/{filename}");
+
+        if self.added.contains(&archive_path) {
+            return Ok(());
+        }
+
+        let mut file = match File::open(dir.join(filename)) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                warn!("File '{archive_path}' not found, skipping.");
+                return Ok(());
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        // Images are already compressed
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+        self.zip.start_file(archive_path.as_str(), options)?;
+        io::copy(&mut file, &mut self.zip)?;
+
+        self.added.insert(archive_path);
+
+        Ok(())
+    }
+}
+
+/// Map the hashes of original files to their filenames,
+/// as the extension of an original file is not stored
+pub(crate) fn index_original_files(dir: &Path) -> Result<HashMap<String, String>, anyhow::Error> {
+    let mut files = HashMap::new();
+
+    for entry in std::fs::read_dir(dir)? {
+        let filename = entry?.file_name();
+
+        if let Some((hash, _)) = filename.to_str().and_then(|v| v.split_once('.')) {
+            files.insert(hash.to_owned(), filename.to_string_lossy().into_owned());
+        }
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use regex::Captures;
+use tracing::warn;
+use zip::ZipArchive;
+
+use aria_models::api as am;
+use aria_models::local as lm;
+use aria_store::models as dbm;
+
+use super::AriaCore;
+use crate::{
+    GeneratePostImageResult, ROOM_ARCHIVE_EMOTES, ROOM_ARCHIVE_MANIFEST, ROOM_ARCHIVE_POSTS, ROOM_ARCHIVE_VERSION,
+    export::index_original_files, file::ProcessFileResult, post::RE_QUOTE_LINK,
+};
+
+pub struct ImportRoomResult {
+    pub room: lm::ClaimedRoom,
+    pub post_count: usize,
+    pub emote_count: usize,
+}
+
+/// Files contained in an archive, indexed by hash
+struct ArchiveFiles {
+    original: HashMap<String, PathBuf>,
+    public: HashMap<String, PathBuf>,
+}
+
+impl AriaCore {
+    /// Import a room from an archive written by [`AriaCore::export_room`],
+    /// either a ZIP file or a directory it has been extracted to.
+    ///
+    /// The room is created as a new room, named `name` if specified, or otherwise the name stored in the archive.
+    /// Images and emotes are processed the same way as uploaded files, so that all public files are regenerated.
+    pub async fn import_room(&self, path: &Path, name: Option<&str>) -> Result<ImportRoomResult, anyhow::Error> {
+        if path.is_dir() {
+            return self.import_room_dir(path, name).await;
+        }
+
+        let extract_path = self.temp_path.join(uuid::Uuid::new_v4().to_string());
+
+        let result = {
+            let path = path.to_owned();
+            let extract_path = extract_path.clone();
+
+            tokio::task::spawn_blocking(move || extract_room_archive(&path, &extract_path)).await?
+        };
+
+        let result = match result {
+            Ok(()) => self.import_room_dir(&extract_path, name).await,
+            Err(err) => Err(err),
+        };
+
+        if let Err(err) = tokio::fs::remove_dir_all(&extract_path).await {
+            warn!("Error removing '{}': {err}", extract_path.display());
+        }
+
+        result
+    }
+
+    async fn import_room_dir(&self, path: &Path, name: Option<&str>) -> Result<ImportRoomResult, anyhow::Error> {
+        let manifest: am::RoomArchive = serde_json::from_slice(
+            &tokio::fs::read(path.join(ROOM_ARCHIVE_MANIFEST))
+                .await
+                .context("Error reading archive manifest")?,
+        )?;
+
+        if manifest.version > ROOM_ARCHIVE_VERSION {
+            return Err(anyhow::anyhow!(
+                "Unsupported archive version {} (supported: {ROOM_ARCHIVE_VERSION})",
+                manifest.version
+            ));
+        }
+
+        let emotes: Vec<am::Emote> = serde_json::from_slice(
+            &tokio::fs::read(path.join(ROOM_ARCHIVE_EMOTES))
+                .await
+                .context("Error reading archive emotes")?,
+        )?;
+
+        let posts = tokio::fs::read_to_string(path.join(ROOM_ARCHIVE_POSTS))
+            .await
+            .context("Error reading archive posts")?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<am::Post>, _>>()?;
+
+        let name = name.unwrap_or(&manifest.name);
+
+        if self.get_room_by_name(name).await?.is_some() {
+            return Err(anyhow::anyhow!("Room '{name}' already exists"));
+        }
+
+        let image_files = ArchiveFiles::index(&path.join("files/original/i"), &path.join("files/public/i"))?;
+        let emote_files = ArchiveFiles::index(&path.join("files/original/e"), &path.join("files/public/e"))?;
+
+        let room = self.claim_room(name).await?;
+
+        // Remove the partially imported room if anything goes wrong, so that the import can be retried
+        let emote_count = match self
+            .import_room_contents(room.id, emotes, &posts, &image_files, &emote_files)
+            .await
+        {
+            Ok(v) => v,
+            Err(err) => {
+                if let Err(err) = self.store.delete_room(room.id).await {
+                    warn!("Error removing partially imported room '{name}': {err:#}");
+                }
+
+                return Err(err);
+            }
+        };
+
+        Ok(ImportRoomResult {
+            room,
+            post_count: posts.len(),
+            emote_count,
+        })
+    }
+
+    /// Import emotes and posts into a newly created room, returning the number of emotes imported
+    async fn import_room_contents(
+        &self,
+        room_id: i32,
+        emotes: Vec<am::Emote>,
+        posts: &[am::Post],
+        image_files: &ArchiveFiles,
+        emote_files: &ArchiveFiles,
+    ) -> Result<usize, anyhow::Error> {
+        let mut emote_count = 0;
+        for emote in emotes {
+            let Some(source_path) = emote_files.find(&emote.url) else {
+                warn!("File for emote '{}' not found, skipping.", emote.name);
+                continue;
+            };
+
+            let ProcessFileResult {
+                hash,
+                original_ext,
+                original_file_path,
+            } = self.import_file(&source_path, &self.original_emote_path).await?;
+
+            let ext = self
+                .generate_emote_image(&original_file_path, &hash, &original_ext, false)
+                .await?;
+
+            let new_emote = dbm::NewEmote {
+                name: Some(emote.name),
+                hash: Some(hash.into_owned()),
+                ext: Some(ext),
+            };
+
+            self.store.create_emote(room_id, &new_emote).await?;
+            emote_count += 1;
+        }
+
+        // Imported posts can not be attributed to users on this instance,
+        // so they are all given the same new user ID.
+        let user_id = self.generate_user_id().await?;
+
+        // Map post IDs in the archive to the IDs of the imported posts, to keep quote links working
+        let mut post_ids: HashMap<i64, i64> = HashMap::new();
+
+        for post in posts.iter() {
+            let image = match &post.image {
+                Some(image) => match image_files.find(&image.url) {
+                    Some(source_path) => {
+                        let ProcessFileResult {
+                            hash,
+                            original_ext,
+                            original_file_path,
+                        } = self.import_file(&source_path, &self.original_image_path).await?;
+
+                        let GeneratePostImageResult { ext, tn_ext } = self
+                            .generate_post_image(&original_file_path, &hash, &original_ext, false)
+                            .await?;
+
+                        Some(dbm::NewImage {
+                            filename: Some(image.filename.clone()),
+                            hash: Some(hash.into_owned()),
+                            ext: Some(ext.into_owned()),
+                            tn_ext: Some(tn_ext.into_owned()),
+                        })
+                    }
+                    None => {
+                        warn!("Image for post {} not found, skipping.", post.id);
+                        None
+                    }
+                },
+                None => None,
+            };
+
+            let comment = post.comment.as_deref().map(|c| {
+                RE_QUOTE_LINK
+                    .replace_all(c, |caps: &Captures| {
+                        match caps[1].parse().ok().and_then(|id: i64| post_ids.get(&id)) {
+                            Some(id) => format!(">>{id}"),
+                            None => caps[0].to_owned(),
+                        }
+                    })
+                    .into_owned()
+            });
+
+            let new_post = dbm::NewPost {
+                name: post.name.clone(),
+                comment,
+                ip: Some(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+                user_id,
+                admin: post.admin,
+            };
+
+            let p = self
+                .store
+                .import_post(room_id, &new_post, image.as_ref(), post.posted)
+                .await?;
+
+            let new_post_id = p.post.id.unwrap();
+            post_ids.insert(post.id, new_post_id);
+
+            self.create_post_replies(room_id, new_post_id, new_post.comment.as_deref())
+                .await?;
+        }
+
+        Ok(emote_count)
+    }
+
+    /// Copy a file from an archive into the originals directory
+    async fn import_file(
+        &self,
+        source_path: &Path,
+        destination_path: &Path,
+    ) -> Result<ProcessFileResult<'static>, anyhow::Error> {
+        let filename = source_path
+            .file_name()
+            .and_then(|v| v.to_str())
+            .context("Invalid filename")?
+            .to_owned();
+
+        let temp_path = self.temp_path.join(uuid::Uuid::new_v4().to_string());
+        tokio::fs::copy(source_path, &temp_path).await?;
+
+        let file = self.hash_file(&temp_path).await?;
+
+        let ProcessFileResult {
+            hash,
+            original_ext,
+            original_file_path,
+        } = self.process_file(file, &filename, destination_path).await?;
+
+        Ok(ProcessFileResult {
+            hash: hash.into_owned().into(),
+            original_ext: original_ext.into_owned().into(),
+            original_file_path,
+        })
+    }
+}
+
+impl ArchiveFiles {
+    fn index(original_path: &Path, public_path: &Path) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            original: index_archive_dir(original_path)?,
+            public: index_archive_dir(public_path)?,
+        })
+    }
+
+    /// Find the file for a public URL, preferring the original file if the archive contains it
+    fn find(&self, url: &str) -> Option<PathBuf> {
+        let (hash, _) = url.rsplit('/').next()?.split_once('.')?;
+
+        self.original.get(hash).or_else(|| self.public.get(hash)).cloned()
+    }
+}
+
+fn index_archive_dir(dir: &Path) -> Result<HashMap<String, PathBuf>, anyhow::Error> {
+    if !dir.is_dir() {
+        return Ok(HashMap::new());
+    }
+
+    Ok(index_original_files(dir)?
+        .into_iter()
+        .map(|(hash, filename)| (hash, dir.join(filename)))
+        .collect())
+}
+
+fn extract_room_archive(path: &Path, extract_path: &Path) -> Result<(), anyhow::Error> {
+    let file = File::open(path).with_context(|| format!("Error opening archive '{}'", path.display()))?;
+    let mut zip = ZipArchive::new(BufReader::new(file))?;
+
+    zip.extract(extract_path)?;
+
+    Ok(())
+}
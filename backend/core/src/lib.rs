@@ -6,19 +6,30 @@ use anyhow::Context;
 
 use aria_models::local as lm;
 use aria_models::local::SysConfig;
-use aria_store::{AriaStore, PgStore};
+use aria_store::{AriaStore, MemoryStore, PgStore};
 
+mod admin;
 mod auth;
+mod ban;
+mod comment;
 pub mod config;
 mod emote;
+mod export;
 mod file;
+mod import;
+mod moderator;
 mod post;
+mod queue;
 mod room;
 mod transform;
 mod user;
 mod util;
 
+pub use self::ban::Banned;
+pub use self::comment::*;
+pub use self::export::*;
 pub use self::file::*;
+pub use self::import::*;
 pub use self::post::*;
 
 use self::config::AriaConfig;
@@ -28,8 +39,15 @@ pub enum Notification {
     NewPost(i32, lm::Post),
     NewEmote(i32, lm::Emote),
     DeletePost(i32, i64),
+    EditPost(i32, lm::Post),
+    Reaction(i32, lm::ReactionChange),
     DeleteEmote(i32, i32),
     Content(i32, lm::Content),
+    Queue(i32, Vec<lm::QueueItem>),
+    EndBehavior(i32, lm::ContentEndBehavior),
+    PurgePosts(i32, Vec<i64>),
+    DeleteRoom(i32),
+    Ban(i32, lm::Ban),
 }
 
 pub struct AriaCore {
@@ -44,15 +62,49 @@ pub struct AriaCore {
     pub public_image_path: PathBuf,
     pub public_thumbnail_path: PathBuf,
     pub public_emote_path: PathBuf,
-    store: PgStore,
+    store: Box<dyn AriaStore>,
     notify_tx: tokio::sync::broadcast::Sender<Arc<Notification>>,
 }
 
+fn open_store(database_uri: &str) -> Result<Box<dyn AriaStore>, anyhow::Error> {
+    let scheme = database_uri
+        .split_once(':')
+        .map(|(scheme, _)| scheme)
+        .unwrap_or_default();
+
+    match scheme {
+        "postgres" | "postgresql" => Ok(Box::new(PgStore::new(database_uri))),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Ok(Box::new(aria_store::SqliteStore::new(database_uri)?)),
+        #[cfg(not(feature = "sqlite"))]
+        "sqlite" => Err(anyhow::anyhow!("SQLite support is not enabled in this build")),
+        _ => Err(anyhow::anyhow!("Unsupported database URI scheme: '{scheme}'")),
+    }
+}
+
 const DEFAULT_MAX_EMOTE_SIZE: usize = 4 * 1024 * 1024;
 const DEFAULT_MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024;
 
 impl AriaCore {
+    /// Create core backed by the database configured in `database_uri`.
+    /// The storage backend is selected by the scheme of the URI.
     pub fn new(config: AriaConfig) -> Result<Self, anyhow::Error> {
+        let database_uri = config
+            .database_uri
+            .as_ref()
+            .context("Database URI not set in configuration")?;
+
+        let store = open_store(database_uri)?;
+
+        Self::with_store(config, store)
+    }
+
+    /// Create core backed by a non-persistent in-memory store
+    pub fn new_in_memory(config: AriaConfig) -> Result<Self, anyhow::Error> {
+        Self::with_store(config, Box::new(MemoryStore::new()))
+    }
+
+    pub fn with_store(config: AriaConfig, store: Box<dyn AriaStore>) -> Result<Self, anyhow::Error> {
         let sys_config = SysConfig {
             max_emote_size: config.max_emote_size.unwrap_or(DEFAULT_MAX_EMOTE_SIZE),
             max_image_size: config.max_image_size.unwrap_or(DEFAULT_MAX_IMAGE_SIZE),
@@ -87,13 +139,6 @@ impl AriaCore {
         fs::create_dir_all(&public_thumbnail_path)?;
         fs::create_dir_all(&public_emote_path)?;
 
-        let database_uri = config
-            .database_uri
-            .as_ref()
-            .context("Database URI not set in configuration")?;
-
-        let store = PgStore::new(database_uri);
-
         let (notify_tx, _) = tokio::sync::broadcast::channel(16);
 
         Ok(Self {
@@ -0,0 +1,80 @@
+use aria_models::local as lm;
+
+use crate::{
+    transform::dbm_moderator_to_lm,
+    util::password::{generate_simple_password, hash_password, verify_password},
+};
+
+use super::AriaCore;
+
+const MODERATOR_KEY_LENGTH: usize = 12;
+
+impl AriaCore {
+    pub async fn get_moderators(&self, room_id: i32) -> Result<Vec<lm::Moderator>, anyhow::Error> {
+        let moderators = self.store.get_moderators(room_id).await?;
+
+        moderators.into_iter().map(dbm_moderator_to_lm).collect()
+    }
+
+    pub async fn get_moderator(&self, room_id: i32, moderator_id: i32) -> Result<Option<lm::Moderator>, anyhow::Error> {
+        let moderator = self.store.get_moderator(room_id, moderator_id).await?;
+
+        moderator.map(dbm_moderator_to_lm).transpose()
+    }
+
+    /// Create a moderator with a newly generated key.
+    /// Returns None if a moderator with the same name already exists in the room.
+    pub async fn create_moderator(
+        &self,
+        room_id: i32,
+        name: &str,
+        permissions: &[lm::ModeratorPermission],
+    ) -> Result<Option<lm::CreatedModerator>, anyhow::Error> {
+        let key = generate_simple_password(MODERATOR_KEY_LENGTH);
+
+        let key_hash = {
+            let key = key.clone();
+            tokio::task::spawn_blocking(move || hash_password(&key)).await??
+        };
+
+        let permissions: Vec<String> = permissions.iter().map(|p| p.as_str().to_owned()).collect();
+
+        let Some(moderator) = self
+            .store
+            .create_moderator(room_id, name, &key_hash, &permissions)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(lm::CreatedModerator {
+            moderator: dbm_moderator_to_lm(moderator)?,
+            key,
+        }))
+    }
+
+    /// Delete a moderator and revoke all of their sessions
+    pub async fn delete_moderator(&self, room_id: i32, moderator_id: i32) -> Result<bool, anyhow::Error> {
+        self.store.delete_moderator(room_id, moderator_id).await
+    }
+
+    pub async fn moderator_login(
+        &self,
+        room_id: i32,
+        name: &str,
+        key: &str,
+    ) -> Result<Option<lm::Moderator>, anyhow::Error> {
+        let Some(moderator) = self.store.get_moderator_by_name(room_id, name).await? else {
+            return Ok(None);
+        };
+
+        let stored_key = moderator.key.clone().unwrap();
+        let key = key.to_owned();
+
+        if !tokio::task::spawn_blocking(move || verify_password(&key, &stored_key)).await?? {
+            return Ok(None);
+        }
+
+        Ok(Some(dbm_moderator_to_lm(moderator)?))
+    }
+}
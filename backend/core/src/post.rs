@@ -1,14 +1,26 @@
-use std::{borrow::Cow, path::Path};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    net::IpAddr,
+    path::Path,
+};
 
 use anyhow::Context;
+use chrono::{Duration, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::warn;
+
 use aria_models::local as lm;
-use aria_store::{AriaStore, models as dbm};
+use aria_store::models as dbm;
 
 use super::AriaCore;
 use crate::{
-    ANIM_IMAGE_EXT, FileKind, IMAGE_EXT, Notification,
+    ANIM_IMAGE_EXT, Banned, FileKind, IMAGE_EXT, Notification,
+    comment::parse_comment,
+    export::index_original_files,
     file::ProcessFileResult,
-    transform::dbm_post_to_lm,
+    transform::{dbm_post_reply_to_lm, dbm_post_revision_to_lm, dbm_post_to_lm},
     util::thumbnail::{AnimatedThumbnailGenerator, StaticThumbnailGenerator, ThumbnailGenerator, ThumbnailQuality},
 };
 
@@ -17,19 +29,75 @@ pub struct GeneratePostImageResult<'a> {
     pub tn_ext: Cow<'a, str>,
 }
 
+/// Number of posts returned per page of post history, unless otherwise specified
+const DEFAULT_POST_PAGE_SIZE: u32 = 50;
+/// Maximum number of posts returned per page of post history
+const MAX_POST_PAGE_SIZE: u32 = 200;
+
+/// Maximum length of a poster name or display name, in characters
+pub const MAX_NAME_LENGTH: usize = 50;
+
+/// Number of minutes after posting during which a post can be edited by its poster, unless otherwise configured
+const DEFAULT_POST_EDIT_WINDOW_MINUTES: u32 = 5;
+
 const MAX_IMAGE_WIDTH: u32 = 350;
 const MAX_IMAGE_HEIGHT: u32 = 350;
 const THUMBNAIL_WIDTH: u32 = 100;
 const THUMBNAIL_HEIGHT: u32 = 100;
 
+/// Matches quote links referencing other posts, such as ">>12345"
+pub(crate) static RE_QUOTE_LINK: Lazy<Regex> = Lazy::new(|| Regex::new(r">>(\d+)").unwrap());
+
 impl AriaCore {
     pub async fn get_recent_posts(&self, room_id: i32, count: i32) -> Result<Vec<lm::Post>, anyhow::Error> {
         let posts = self.store.get_recent_posts(room_id, count).await?;
 
-        Ok(posts.into_iter().map(dbm_post_to_lm).collect())
+        self.complete_posts(room_id, posts).await
+    }
+
+    /// Get a page of a room's post history, oldest first.
+    /// Returns the newest posts older than `before_id`, or the newest posts in the room if not specified.
+    pub async fn get_posts(
+        &self,
+        room_id: i32,
+        before_id: Option<i64>,
+        limit: Option<u32>,
+    ) -> Result<Vec<lm::Post>, anyhow::Error> {
+        let limit = limit.unwrap_or(DEFAULT_POST_PAGE_SIZE).clamp(1, MAX_POST_PAGE_SIZE);
+
+        let posts = self.store.get_posts(room_id, before_id, limit as i32).await?;
+
+        self.complete_posts(room_id, posts).await
+    }
+
+    /// Search a room's posts, newest first
+    pub async fn search_posts(&self, room_id: i32, search: lm::PostSearch) -> Result<Vec<lm::Post>, anyhow::Error> {
+        let limit = search
+            .limit
+            .unwrap_or(DEFAULT_POST_PAGE_SIZE)
+            .clamp(1, MAX_POST_PAGE_SIZE);
+
+        let search = dbm::PostSearch {
+            query: search.query.filter(|q| !q.trim().is_empty()),
+            from: search.from,
+            to: search.to,
+            has_image: search.has_image,
+            admin: search.admin,
+            user_id: search.user_id,
+            before_id: search.before_id,
+            count: limit as i32,
+        };
+
+        let posts = self.store.search_posts(room_id, &search).await?;
+
+        self.complete_posts(room_id, posts).await
     }
 
     pub async fn create_post(&self, room_id: i32, post: lm::NewPost<'_>) -> Result<lm::Post, anyhow::Error> {
+        if let Some(ban) = self.get_active_ban(room_id, post.user_id, post.ip).await? {
+            return Err(Banned(ban).into());
+        }
+
         let image = if let Some(i) = post.image {
             // Process image
             let ProcessFileResult {
@@ -66,7 +134,15 @@ impl AriaCore {
 
         let p = self.store.create_post(room_id, &post, image.as_ref()).await?;
 
-        let post = dbm_post_to_lm(p);
+        let emote_names = self.get_emote_names(room_id).await?;
+
+        let mut post = dbm_post_to_lm(p);
+        post.tokens = tokenize_comment(post.comment.as_deref(), &emote_names);
+        post.replies_to = self
+            .create_post_replies(room_id, post.id, post.comment.as_deref())
+            .await?;
+
+        self.extend_room_expiry(room_id).await?;
 
         self.notify(Notification::NewPost(room_id, post.clone()))?;
 
@@ -89,6 +165,292 @@ impl AriaCore {
         Ok(success)
     }
 
+    /// Replace the comment of a post, returning the edited post.
+    /// Unless `is_admin` is set, posts can only be edited by their poster, within the configured edit window.
+    pub async fn edit_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        ip: IpAddr,
+        is_admin: bool,
+        comment: Option<&str>,
+    ) -> Result<Option<lm::Post>, anyhow::Error> {
+        if let Some(ban) = self.get_active_ban(room_id, user_id, ip).await? {
+            return Err(Banned(ban).into());
+        }
+
+        let edit_window_minutes = self
+            .config
+            .post_edit_window_minutes
+            .unwrap_or(DEFAULT_POST_EDIT_WINDOW_MINUTES);
+
+        let editable_since = Utc::now() - Duration::minutes(edit_window_minutes.into());
+
+        let Some(p) = self
+            .store
+            .edit_post(room_id, post_id, user_id, is_admin, comment, editable_since)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        self.create_post_replies(room_id, post_id, comment).await?;
+
+        let post = self
+            .complete_posts(room_id, vec![p])
+            .await?
+            .pop()
+            .context("Edited post missing")?;
+
+        self.notify(Notification::EditPost(room_id, post.clone()))?;
+
+        Ok(Some(post))
+    }
+
+    /// Get the previous comments of an edited post, oldest first.
+    /// Unless `is_admin` is set, only the poster can view them.
+    pub async fn get_post_revisions(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<Vec<lm::PostRevision>, anyhow::Error> {
+        let revisions = self
+            .store
+            .get_post_revisions(room_id, post_id, user_id, is_admin)
+            .await?;
+
+        Ok(revisions.into_iter().map(dbm_post_revision_to_lm).collect())
+    }
+
+    /// Get the key to store a reaction as, if it is either the name of one of the room's emotes or a Unicode emoji.
+    /// Emoji are normalized, so that different spellings of the same emoji count as the same reaction.
+    pub async fn normalize_reaction(&self, room_id: i32, reaction: &str) -> Result<Option<String>, anyhow::Error> {
+        if let Some(emoji) = emojis::get(reaction) {
+            return Ok(Some(emoji.as_str().to_owned()));
+        }
+
+        let emote_names = self.get_emote_names(room_id).await?;
+
+        Ok(emote_names.contains(reaction).then(|| reaction.to_owned()))
+    }
+
+    /// Add a user's reaction to a post, returning false if the post was not found.
+    /// The reaction should be normalized using [`AriaCore::normalize_reaction`].
+    pub async fn add_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        ip: IpAddr,
+        reaction: &str,
+    ) -> Result<bool, anyhow::Error> {
+        if let Some(ban) = self.get_active_ban(room_id, user_id, ip).await? {
+            return Err(Banned(ban).into());
+        }
+
+        let count = self
+            .store
+            .add_post_reaction(room_id, post_id, user_id, reaction)
+            .await?;
+
+        self.notify_reaction_change(room_id, post_id, user_id, reaction, true, count)
+    }
+
+    /// Remove a user's reaction to a post, returning false if the post was not found
+    /// or the user had not reacted with the reaction
+    pub async fn remove_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let count = self
+            .store
+            .remove_post_reaction(room_id, post_id, user_id, reaction)
+            .await?;
+
+        self.notify_reaction_change(room_id, post_id, user_id, reaction, false, count)
+    }
+
+    fn notify_reaction_change(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+        added: bool,
+        count: Option<i64>,
+    ) -> Result<bool, anyhow::Error> {
+        let Some(count) = count else {
+            return Ok(false);
+        };
+
+        self.notify(Notification::Reaction(
+            room_id,
+            lm::ReactionChange {
+                post_id,
+                reaction: reaction.to_owned(),
+                user_id,
+                added,
+                count: count as usize,
+            },
+        ))?;
+
+        Ok(true)
+    }
+
+    /// Record the posts quote linked in a comment as being replied to
+    pub(crate) async fn create_post_replies(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        comment: Option<&str>,
+    ) -> Result<Vec<lm::ReplyTo>, anyhow::Error> {
+        let Some(comment) = comment else {
+            return Ok(Vec::new());
+        };
+
+        let mut reply_to_ids: Vec<i64> = RE_QUOTE_LINK
+            .captures_iter(comment)
+            .filter_map(|c| c[1].parse().ok())
+            .collect();
+
+        if reply_to_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        reply_to_ids.sort_unstable();
+        reply_to_ids.dedup();
+
+        let replies = self.store.create_post_replies(room_id, post_id, &reply_to_ids).await?;
+
+        Ok(replies.into_iter().map(dbm_post_reply_to_lm).collect())
+    }
+
+    /// Convert posts, filling in their comment tokens, reactions and which posts they reply to and are replied to by
+    async fn complete_posts(
+        &self,
+        room_id: i32,
+        posts: Vec<dbm::PostAndImage>,
+    ) -> Result<Vec<lm::Post>, anyhow::Error> {
+        let mut posts: Vec<lm::Post> = posts.into_iter().map(dbm_post_to_lm).collect();
+
+        if posts.is_empty() {
+            return Ok(posts);
+        }
+
+        let emote_names = self.get_emote_names(room_id).await?;
+
+        for post in posts.iter_mut() {
+            post.tokens = tokenize_comment(post.comment.as_deref(), &emote_names);
+        }
+
+        let post_ids: Vec<i64> = posts.iter().map(|p| p.id).collect();
+        let replies = self.store.get_post_replies(&post_ids).await?;
+
+        let index: HashMap<i64, usize> = post_ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+        for r in replies {
+            let (Some(post_id), Some(reply_to_id)) = (r.post_id, r.reply_to_id) else {
+                continue;
+            };
+
+            if let Some(i) = index.get(&post_id) {
+                posts[*i].replies_to.push(dbm_post_reply_to_lm(r));
+            }
+
+            if let Some(i) = index.get(&reply_to_id) {
+                posts[*i].replied_by.push(post_id);
+            }
+        }
+
+        for r in self.store.get_post_reactions(&post_ids).await? {
+            let (Some(post_id), Some(reaction), Some(user_id)) = (r.post_id, r.reaction, r.user_id) else {
+                continue;
+            };
+
+            let Some(i) = index.get(&post_id) else {
+                continue;
+            };
+
+            let reactions = &mut posts[*i].reactions;
+
+            match reactions.iter_mut().find(|pr| pr.reaction == reaction) {
+                Some(pr) => pr.user_ids.push(user_id),
+                None => reactions.push(lm::PostReaction {
+                    reaction,
+                    user_ids: vec![user_id],
+                }),
+            }
+        }
+
+        Ok(posts)
+    }
+
+    async fn get_emote_names(&self, room_id: i32) -> Result<HashSet<String>, anyhow::Error> {
+        let emotes = self.store.get_emotes(room_id).await?;
+
+        Ok(emotes.into_iter().filter_map(|e| e.name).collect())
+    }
+
+    /// Delete the original, image and thumbnail files of images that are no longer used by any post,
+    /// such as after the rooms they were posted in have been deleted.
+    pub(crate) async fn delete_unused_image_files(&self, images: Vec<dbm::Image>) -> Result<(), anyhow::Error> {
+        let hashes: Vec<String> = images
+            .iter()
+            .filter_map(|i| i.hash.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        if hashes.is_empty() {
+            return Ok(());
+        }
+
+        let used_hashes: HashSet<String> = self.store.get_used_image_hashes(&hashes).await?.into_iter().collect();
+
+        let unused: Vec<_> = images
+            .iter()
+            .filter(|i| i.hash.as_ref().is_some_and(|h| !used_hashes.contains(h)))
+            .collect();
+
+        if unused.is_empty() {
+            return Ok(());
+        }
+
+        let original_images = index_original_files(&self.original_image_path)?;
+
+        for image in unused {
+            let (Some(hash), Some(ext), Some(tn_ext)) = (&image.hash, &image.ext, &image.tn_ext) else {
+                continue;
+            };
+
+            let mut paths = vec![
+                self.public_image_path.join(format!("{hash}.{ext}")),
+                self.public_thumbnail_path.join(format!("{hash}.{tn_ext}")),
+            ];
+
+            if let Some(filename) = original_images.get(hash) {
+                paths.push(self.original_image_path.join(filename));
+            }
+
+            for path in paths {
+                // Several deleted posts may have shared the same file
+                match tokio::fs::remove_file(&path).await {
+                    Ok(()) => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(err) => warn!("Error deleting '{}': {err}", path.display()),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn update_post_images(&self, hash: &str, ext: &str, tn_ext: &str) -> Result<(), anyhow::Error> {
         self.store.update_post_images(hash, ext, tn_ext).await?;
 
@@ -173,3 +535,9 @@ impl AriaCore {
         })
     }
 }
+
+fn tokenize_comment(comment: Option<&str>, emote_names: &HashSet<String>) -> Vec<lm::CommentToken> {
+    comment
+        .map(|c| parse_comment(c, |name| emote_names.contains(name)))
+        .unwrap_or_default()
+}
@@ -0,0 +1,84 @@
+use aria_models::local as lm;
+
+use crate::{Notification, transform::dbm_queue_item_to_lm};
+
+use super::AriaCore;
+
+impl AriaCore {
+    pub async fn get_queue(&self, room_id: i32) -> Result<Vec<lm::QueueItem>, anyhow::Error> {
+        let queue = self.store.get_queue(room_id).await?;
+
+        queue.into_iter().map(dbm_queue_item_to_lm).collect()
+    }
+
+    pub async fn add_queue_item(&self, room_id: i32, content: lm::Content) -> Result<lm::QueueItem, anyhow::Error> {
+        let content_json = serde_json::to_string(&content)?;
+
+        let item = self.store.add_queue_item(room_id, &content_json).await?;
+        let item = dbm_queue_item_to_lm(item)?;
+
+        self.notify_queue(room_id).await?;
+
+        Ok(item)
+    }
+
+    pub async fn delete_queue_item(&self, room_id: i32, item_id: i64) -> Result<bool, anyhow::Error> {
+        let success = self.store.delete_queue_item(room_id, item_id).await?;
+
+        if success {
+            self.notify_queue(room_id).await?;
+        }
+
+        Ok(success)
+    }
+
+    pub async fn move_queue_item(&self, room_id: i32, item_id: i64, position: i32) -> Result<bool, anyhow::Error> {
+        let success = self.store.move_queue_item(room_id, item_id, position).await?;
+
+        if success {
+            self.notify_queue(room_id).await?;
+        }
+
+        Ok(success)
+    }
+
+    pub async fn clear_queue(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        self.store.clear_queue(room_id).await?;
+        self.notify(Notification::Queue(room_id, Vec::new()))?;
+
+        Ok(())
+    }
+
+    /// Remove the first item from the queue and set it as the room's content
+    pub async fn play_next_queue_item(&self, room_id: i32) -> Result<Option<lm::QueueItem>, anyhow::Error> {
+        let item = loop {
+            let Some(item) = self.get_queue(room_id).await?.into_iter().next() else {
+                return Ok(None);
+            };
+
+            // If the item was removed by someone else in the meantime, try the next one,
+            // so that the same item is never played twice.
+            if self.store.delete_queue_item(room_id, item.id).await? {
+                break item;
+            }
+        };
+
+        let lm::Content {
+            url,
+            duration,
+            is_livestream,
+        } = &item.content;
+
+        self.set_room_content(room_id, url, *duration, *is_livestream).await?;
+        self.notify_queue(room_id).await?;
+
+        Ok(Some(item))
+    }
+
+    async fn notify_queue(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        let queue = self.get_queue(room_id).await?;
+        self.notify(Notification::Queue(room_id, queue))?;
+
+        Ok(())
+    }
+}
@@ -1,7 +1,11 @@
 use aria_models::local as lm;
-use aria_store::AriaStore;
+use chrono::{DateTime, Duration, Utc};
 
-use crate::{Notification, transform::dbm_room_to_lm, util::password::generate_simple_password};
+use crate::{
+    Notification,
+    transform::dbm_room_to_lm,
+    util::password::{constant_time_eq, generate_simple_password, hash_password, is_password_hash, verify_password},
+};
 
 use super::AriaCore;
 
@@ -19,15 +23,44 @@ impl AriaCore {
     }
 
     pub async fn login(&self, room_id: i32, password: &str) -> Result<bool, anyhow::Error> {
-        let room = self.store.get_room(room_id).await?;
+        let Some(room) = self.store.get_room(room_id).await? else {
+            return Ok(false);
+        };
+
+        let stored_password = room.password.unwrap();
+        let password = password.to_owned();
+
+        if is_password_hash(&stored_password) {
+            return tokio::task::spawn_blocking(move || verify_password(&password, &stored_password)).await?;
+        }
+
+        // Legacy plaintext password
+        if !constant_time_eq(password.as_bytes(), stored_password.as_bytes()) {
+            return Ok(false);
+        }
+
+        // Replace the plaintext password with a hash now that we know it is correct,
+        // unless it was changed in the meantime
+        let password_hash = tokio::task::spawn_blocking(move || hash_password(&password)).await??;
+        self.store
+            .replace_room_password(room_id, &stored_password, &password_hash)
+            .await?;
 
-        Ok(room.map(|r| r.password.unwrap() == password).unwrap_or(false))
+        Ok(true)
     }
 
     pub async fn claim_room(&self, name: &str) -> Result<lm::ClaimedRoom, anyhow::Error> {
         let password = generate_simple_password(6);
 
-        let room = self.store.create_room(name, &password).await?;
+        let password_hash = {
+            let password = password.clone();
+            tokio::task::spawn_blocking(move || hash_password(&password)).await??
+        };
+
+        let room = self
+            .store
+            .create_room(name, &password_hash, self.room_expires_at())
+            .await?;
 
         Ok(lm::ClaimedRoom {
             id: room.id.unwrap(),
@@ -36,6 +69,28 @@ impl AriaCore {
         })
     }
 
+    /// Change the room password and revoke all outstanding refresh tokens for the room
+    pub async fn change_room_password(&self, room_id: i32, password: &str) -> Result<(), anyhow::Error> {
+        let password_hash = {
+            let password = password.to_owned();
+            tokio::task::spawn_blocking(move || hash_password(&password)).await??
+        };
+
+        self.store.set_room_password(room_id, &password_hash).await?;
+        self.store.revoke_room_refresh_tokens(room_id).await?;
+
+        Ok(())
+    }
+
+    /// Replace the room password with a newly generated one
+    pub async fn reset_room_password(&self, room_id: i32) -> Result<String, anyhow::Error> {
+        let password = generate_simple_password(6);
+
+        self.change_room_password(room_id, &password).await?;
+
+        Ok(password)
+    }
+
     pub async fn set_room_content(
         &self,
         room_id: i32,
@@ -68,4 +123,57 @@ impl AriaCore {
 
         Ok(())
     }
+
+    pub async fn set_room_end_behavior(
+        &self,
+        room_id: i32,
+        end_behavior: lm::ContentEndBehavior,
+    ) -> Result<(), anyhow::Error> {
+        self.store.set_room_end_behavior(room_id, end_behavior.as_str()).await?;
+        self.notify(Notification::EndBehavior(room_id, end_behavior))?;
+
+        Ok(())
+    }
+
+    /// Push back the expiry of a room, if room expiry is enabled
+    pub async fn extend_room_expiry(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        if let Some(expires_at) = self.room_expires_at() {
+            self.store.extend_room_expiry(room_id, expires_at).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Delete all expired rooms, returning the IDs of the deleted rooms
+    pub async fn delete_expired_rooms(&self) -> Result<Vec<i32>, anyhow::Error> {
+        // Images have to be looked up before their posts are deleted along with the rooms
+        let images = self.store.get_expired_room_images().await?;
+
+        let room_ids = self.store.delete_expired_rooms().await?;
+
+        for room_id in room_ids.iter() {
+            self.notify(Notification::DeleteRoom(*room_id))?;
+        }
+
+        self.delete_unused_image_files(images).await?;
+
+        Ok(room_ids)
+    }
+
+    /// Make all rooms never expire if room expiry is disabled,
+    /// since rooms keep the expiry time they were given while it was enabled
+    pub async fn clear_room_expiry_if_disabled(&self) -> Result<(), anyhow::Error> {
+        if self.config.room_lifetime_days.is_none() {
+            self.store.clear_room_expiry().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Expiry time for a room active right now
+    fn room_expires_at(&self) -> Option<DateTime<Utc>> {
+        self.config
+            .room_lifetime_days
+            .map(|days| Utc::now() + Duration::days(days.into()))
+    }
 }
@@ -13,6 +13,11 @@ pub fn dbm_room_to_lm(r: &dbm::Room) -> lm::Room {
             .playback_state
             .as_ref()
             .and_then(|ps| serde_json::from_str(ps).unwrap_or_default()),
+        end_behavior: r
+            .end_behavior
+            .as_ref()
+            .and_then(|eb| eb.parse().ok())
+            .unwrap_or_default(),
     }
 }
 
@@ -21,6 +26,7 @@ pub fn dbm_post_to_lm(p: dbm::PostAndImage) -> lm::Post {
         id: p.post.id.unwrap(),
         name: p.post.name,
         comment: p.post.comment,
+        tokens: Vec::new(),
         image: p.image.map(|i| lm::PostImage {
             filename: i.filename.unwrap(),
             hash: i.hash.unwrap(),
@@ -28,8 +34,26 @@ pub fn dbm_post_to_lm(p: dbm::PostAndImage) -> lm::Post {
             tn_ext: i.tn_ext.unwrap(),
         }),
         posted_at: p.post.created_at.unwrap(),
+        edited_at: p.post.edited_at,
         user_id: p.post.user_id.unwrap(),
         admin: p.post.admin,
+        replies_to: Vec::new(),
+        replied_by: Vec::new(),
+        reactions: Vec::new(),
+    }
+}
+
+pub fn dbm_post_revision_to_lm(r: dbm::PostRevision) -> lm::PostRevision {
+    lm::PostRevision {
+        comment: r.comment,
+        posted: r.created_at.unwrap(),
+    }
+}
+
+pub fn dbm_post_reply_to_lm(r: dbm::PostReply) -> lm::ReplyTo {
+    lm::ReplyTo {
+        post_id: r.reply_to_id.unwrap(),
+        user_id: r.reply_to_user_id.unwrap(),
     }
 }
 
@@ -41,3 +65,56 @@ pub fn dbm_emote_to_lm(e: dbm::Emote) -> lm::Emote {
         ext: e.ext.unwrap(),
     }
 }
+
+pub fn dbm_queue_item_to_lm(q: dbm::QueueItem) -> Result<lm::QueueItem, anyhow::Error> {
+    Ok(lm::QueueItem {
+        id: q.id.unwrap(),
+        content: serde_json::from_str(&q.content.unwrap())?,
+    })
+}
+
+pub fn dbm_moderator_to_lm(m: dbm::Moderator) -> Result<lm::Moderator, anyhow::Error> {
+    let permissions = m
+        .permissions
+        .unwrap()
+        .iter()
+        .map(|p| p.parse().map_err(|err: String| anyhow::anyhow!(err)))
+        .collect::<Result<_, _>>()?;
+
+    Ok(lm::Moderator {
+        id: m.id.unwrap(),
+        name: m.name.unwrap(),
+        permissions,
+    })
+}
+
+pub fn dbm_room_summary_to_lm(r: dbm::RoomSummary) -> lm::RoomSummary {
+    lm::RoomSummary {
+        id: r.id.unwrap(),
+        name: r.name.unwrap(),
+        created_at: r.created_at.unwrap(),
+        post_count: r.post_count.unwrap_or_default(),
+        last_post_at: r.last_post_at,
+    }
+}
+
+pub fn dbm_instance_stats_to_lm(s: dbm::InstanceStats) -> lm::InstanceStats {
+    lm::InstanceStats {
+        room_count: s.room_count.unwrap_or_default(),
+        post_count: s.post_count.unwrap_or_default(),
+        image_count: s.image_count.unwrap_or_default(),
+        emote_count: s.emote_count.unwrap_or_default(),
+    }
+}
+
+pub fn dbm_ban_to_lm(b: dbm::Ban) -> lm::Ban {
+    lm::Ban {
+        id: b.id.unwrap(),
+        post_id: b.post_id,
+        user_id: b.user_id,
+        ip: b.ip,
+        reason: b.reason,
+        created_at: b.created_at.unwrap(),
+        expires_at: b.expires_at,
+    }
+}
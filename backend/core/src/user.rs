@@ -1,5 +1,3 @@
-use aria_store::AriaStore;
-
 use super::AriaCore;
 
 impl AriaCore {
@@ -1,5 +1,11 @@
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{SaltString, rand_core::OsRng},
+};
 use rand::{Rng, rng};
 
+const ARGON2_HASH_PREFIX: &str = "$argon2";
+
 pub struct PasswordRules {
     pub alphabet: Vec<char>,
     pub password_length: usize,
@@ -37,3 +43,35 @@ pub fn generate_simple_password(length: usize) -> String {
 
     pw_rules.generate_password()
 }
+
+/// Hash password using Argon2id with a random salt
+pub fn hash_password(password: &str) -> Result<String, anyhow::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|err| anyhow::anyhow!("Error hashing password: {err}"))?;
+
+    Ok(hash.to_string())
+}
+
+/// Verify password against a stored Argon2 hash
+pub fn verify_password(password: &str, hash: &str) -> Result<bool, anyhow::Error> {
+    let hash = PasswordHash::new(hash).map_err(|err| anyhow::anyhow!("Error parsing password hash: {err}"))?;
+
+    Ok(Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+/// Check whether a stored password is a hash, as opposed to a legacy plaintext password
+pub fn is_password_hash(password: &str) -> bool {
+    password.starts_with(ARGON2_HASH_PREFIX)
+}
+
+/// Compare two byte strings in constant time
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
@@ -0,0 +1,270 @@
+use std::borrow::Cow;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use aria_core::{AriaCore, Banned, Notification, config::AriaConfig};
+use aria_models::local as lm;
+use aria_store::{AriaStore, MemoryStore, SqliteStore};
+use tokio::sync::broadcast;
+
+const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+const OTHER_IP: IpAddr = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+
+/// Run each test against every store, so that they behave the same
+macro_rules! store_tests {
+    ($($name:ident),* $(,)?) => {
+        mod memory {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(super::TestCore::memory().await).await;
+                }
+            )*
+        }
+
+        mod sqlite {
+            $(
+                #[tokio::test]
+                async fn $name() {
+                    super::$name(super::TestCore::sqlite().await).await;
+                }
+            )*
+        }
+    };
+}
+
+store_tests!(
+    deleted_posts_are_hidden,
+    only_poster_or_admin_can_delete_posts,
+    refresh_tokens_rotate,
+    reused_refresh_token_revokes_family,
+    bans_apply_to_user_and_ip,
+    search_filters_posts,
+);
+
+/// Core with its files in a temporary directory, which is removed when dropped
+struct TestCore {
+    core: AriaCore,
+    files_path: PathBuf,
+    // Notifications fail to send without a receiver
+    _notifications: broadcast::Receiver<Arc<Notification>>,
+}
+
+impl TestCore {
+    async fn memory() -> Self {
+        Self::with_store(Box::new(MemoryStore::new())).await
+    }
+
+    async fn sqlite() -> Self {
+        Self::with_store(Box::new(SqliteStore::new("sqlite::memory:").unwrap())).await
+    }
+
+    async fn with_store(store: Box<dyn AriaStore>) -> Self {
+        let files_path = std::env::temp_dir().join(format!("aria-test-{}", uuid::Uuid::new_v4()));
+
+        let config = AriaConfig {
+            files_path: Some(files_path.clone()),
+            ..Default::default()
+        };
+
+        let core = AriaCore::with_store(config, store).unwrap();
+        core.migrate().await.unwrap();
+
+        let notifications = core.subscribe_notifications();
+
+        Self {
+            core,
+            files_path,
+            _notifications: notifications,
+        }
+    }
+
+    async fn post(&self, room_id: i32, user_id: i64, comment: &str) -> lm::Post {
+        self.try_post(room_id, user_id, IP, comment).await.unwrap()
+    }
+
+    async fn try_post(&self, room_id: i32, user_id: i64, ip: IpAddr, comment: &str) -> Result<lm::Post, anyhow::Error> {
+        self.core
+            .create_post(
+                room_id,
+                lm::NewPost {
+                    name: None,
+                    comment: Some(Cow::Borrowed(comment)),
+                    image: None,
+                    ip,
+                    user_id,
+                    admin: false,
+                },
+            )
+            .await
+    }
+
+    async fn post_ids(&self, room_id: i32) -> Vec<i64> {
+        let posts = self.core.get_recent_posts(room_id, 100).await.unwrap();
+
+        posts.iter().map(|p| p.id).collect()
+    }
+}
+
+impl Drop for TestCore {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.files_path).ok();
+    }
+}
+
+async fn deleted_posts_are_hidden(t: TestCore) {
+    let room = t.core.claim_room("delete").await.unwrap();
+
+    let kept = t.post(room.id, 1, "kept").await;
+    let deleted = t.post(room.id, 1, "deleted").await;
+
+    assert!(t.core.delete_post(room.id, deleted.id, 1, false).await.unwrap());
+    assert_eq!(t.post_ids(room.id).await, vec![kept.id]);
+
+    // Deleting an already deleted post does nothing
+    assert!(!t.core.delete_post(room.id, deleted.id, 1, false).await.unwrap());
+    assert!(!t.core.delete_post(room.id, deleted.id, 1, true).await.unwrap());
+}
+
+async fn only_poster_or_admin_can_delete_posts(t: TestCore) {
+    let room = t.core.claim_room("admin-delete").await.unwrap();
+    let other_room = t.core.claim_room("admin-delete-other").await.unwrap();
+
+    let post = t.post(room.id, 1, "post").await;
+
+    // Other users cannot delete the post
+    assert!(!t.core.delete_post(room.id, post.id, 2, false).await.unwrap());
+    assert_eq!(t.post_ids(room.id).await, vec![post.id]);
+
+    // Admins cannot delete posts through another room
+    assert!(!t.core.delete_post(other_room.id, post.id, 2, true).await.unwrap());
+    assert_eq!(t.post_ids(room.id).await, vec![post.id]);
+
+    // Admins can delete other users' posts
+    assert!(t.core.delete_post(room.id, post.id, 2, true).await.unwrap());
+    assert!(t.post_ids(room.id).await.is_empty());
+}
+
+async fn refresh_tokens_rotate(t: TestCore) {
+    let token = t.core.create_refresh_token(None, &"claims").await.unwrap();
+
+    let refreshed = t.core.refresh_refresh_token::<String>(token).await.unwrap().unwrap();
+
+    assert_ne!(refreshed.token, token);
+    assert_eq!(refreshed.claims, "claims");
+
+    // The new token can be refreshed in turn
+    let refreshed = t
+        .core
+        .refresh_refresh_token::<String>(refreshed.token)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(refreshed.claims, "claims");
+}
+
+async fn reused_refresh_token_revokes_family(t: TestCore) {
+    let token = t.core.create_refresh_token(None, &"claims").await.unwrap();
+    let other_token = t.core.create_refresh_token(None, &"other").await.unwrap();
+
+    let refreshed = t.core.refresh_refresh_token::<String>(token).await.unwrap().unwrap();
+
+    // Presenting the rotated token again is rejected...
+    assert!(t.core.refresh_refresh_token::<String>(token).await.unwrap().is_none());
+
+    // ...and revokes the token it was rotated into
+    assert!(
+        t.core
+            .refresh_refresh_token::<String>(refreshed.token)
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    // Other token families are unaffected
+    assert!(
+        t.core
+            .refresh_refresh_token::<String>(other_token)
+            .await
+            .unwrap()
+            .is_some()
+    );
+}
+
+async fn bans_apply_to_user_and_ip(t: TestCore) {
+    let room = t.core.claim_room("ban").await.unwrap();
+    let other_room = t.core.claim_room("ban-other").await.unwrap();
+
+    let post = t.post(room.id, 1, "spam").await;
+    let ban = t
+        .core
+        .ban_from_post(room.id, post.id, Some("spam"), None)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Both the user and the IP address are banned
+    let err = t.try_post(room.id, 1, OTHER_IP, "more spam").await.unwrap_err();
+    assert!(err.is::<Banned>());
+
+    let err = t.try_post(room.id, 2, IP, "more spam").await.unwrap_err();
+    assert!(err.is::<Banned>());
+
+    assert!(t.try_post(room.id, 2, OTHER_IP, "hello").await.is_ok());
+
+    // Bans only apply to the room they were made in
+    assert!(t.try_post(other_room.id, 1, IP, "hello").await.is_ok());
+
+    assert!(t.core.delete_ban(room.id, ban.id).await.unwrap());
+    assert!(t.core.get_active_ban(room.id, 1, IP).await.unwrap().is_none());
+    assert!(t.try_post(room.id, 1, IP, "sorry").await.is_ok());
+}
+
+async fn search_filters_posts(t: TestCore) {
+    let room = t.core.claim_room("search").await.unwrap();
+    let other_room = t.core.claim_room("search-other").await.unwrap();
+
+    let hello = t.post(room.id, 1, "Hello world").await;
+    let goodbye = t.post(room.id, 2, "Goodbye world").await;
+    let deleted = t.post(room.id, 1, "Deleted world").await;
+    t.post(other_room.id, 1, "Other world").await;
+
+    assert!(t.core.delete_post(room.id, deleted.id, 1, false).await.unwrap());
+
+    let search = |search: lm::PostSearch| async {
+        let posts = t.core.search_posts(room.id, search).await.unwrap();
+
+        posts.iter().map(|p| p.id).collect::<Vec<_>>()
+    };
+
+    let query = |q: &str| lm::PostSearch {
+        query: Some(q.to_owned()),
+        ..Default::default()
+    };
+
+    // Newest first, without deleted posts or posts from other rooms
+    assert_eq!(search(query("world")).await, vec![goodbye.id, hello.id]);
+    assert_eq!(search(query("HELLO")).await, vec![hello.id]);
+    assert_eq!(search(query("hello world")).await, vec![hello.id]);
+    assert!(search(query("missing")).await.is_empty());
+
+    let by_user = lm::PostSearch {
+        user_id: Some(1),
+        ..query("world")
+    };
+    assert_eq!(search(by_user).await, vec![hello.id]);
+
+    let page = lm::PostSearch {
+        limit: Some(1),
+        ..query("world")
+    };
+    assert_eq!(search(page).await, vec![goodbye.id]);
+
+    let next_page = lm::PostSearch {
+        before_id: Some(goodbye.id),
+        ..query("world")
+    };
+    assert_eq!(search(next_page).await, vec![hello.id]);
+}
@@ -1,4 +1,7 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::local as lm;
@@ -8,55 +11,234 @@ pub struct Room {
     pub id: i32,
     pub name: String,
     pub content: Option<Content>,
+    pub end_behavior: ContentEndBehavior,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct Content {
     pub url: String,
     pub duration: Option<f64>,
     pub is_livestream: Option<bool>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct QueueItem {
+    pub id: i64,
+    pub content: Content,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct Image {
     pub filename: String,
     pub url: String,
     pub tn_url: String,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct Post {
     pub id: i64,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub comment: Option<String>,
+    /// Comment parsed into tokens
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tokens: Vec<CommentToken>,
     pub posted: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited: Option<DateTime<Utc>>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub image: Option<Image>,
 
-    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub admin: bool,
 
-    #[serde(skip_serializing_if = "is_false")]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub you: bool,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replies_to: Vec<i64>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub replied_by: Vec<i64>,
+
+    /// Whether the post replies to any of your posts
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub replies_to_you: bool,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reactions: Vec<Reaction>,
+}
+
+/// Number of users who have reacted to a post with a reaction
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct Reaction {
+    /// Emote name or Unicode emoji
+    pub reaction: String,
+    pub count: usize,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub you_reacted: bool,
+}
+
+/// Change in the number of users who have reacted to a post with a reaction
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct ReactionUpdate {
+    pub post_id: i64,
+    pub reaction: String,
+    pub count: usize,
+    /// Whether you have reacted, if the change was made by you
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub you_reacted: Option<bool>,
+}
+
+/// Users connected to a room
+#[derive(Clone, Debug, Default, JsonSchema, Serialize)]
+pub struct Presence {
+    /// Number of connections, which can exceed the number of members if a user is connected more than once
+    pub viewers: usize,
+    /// Connected users, each listed once regardless of how many connections they have
+    pub members: Vec<PresenceMember>,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct PresenceMember {
+    pub user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub admin: bool,
+    /// Whether the user controls playback
+    #[serde(skip_serializing_if = "is_false")]
+    pub master: bool,
+}
+
+/// User who is typing a post
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct Typist {
+    pub user_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
-#[derive(Clone, Copy, Debug, Deserialize, Serialize)]
+/// Previous comment of an edited post
+#[derive(Clone, Debug, Serialize)]
+pub struct PostRevision {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// When the comment was posted
+    pub posted: DateTime<Utc>,
+}
+
+/// Token of a parsed comment
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CommentToken {
+    Text {
+        text: String,
+    },
+    Newline,
+    Link {
+        url: String,
+    },
+    /// Reference to an emote in the room's emote list
+    Emote {
+        name: String,
+    },
+    QuoteLink {
+        post_id: i64,
+    },
+    /// Line of quoted text, including the leading '>'
+    Greentext {
+        text: String,
+    },
+    Spoiler {
+        children: Vec<CommentToken>,
+    },
+    Code {
+        text: String,
+        block: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct PlaybackState {
     pub time: f64,
     pub rate: f64,
     pub is_playing: bool,
 }
 
+/// What happens to playback when the end of the content is reached
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentEndBehavior {
+    /// Restart playback from the beginning
+    Loop,
+    /// Pause on the last frame
+    Hold,
+    /// Reset playback to the beginning and pause
+    #[default]
+    Stop,
+}
+
 #[derive(Clone, Debug, Serialize)]
+pub struct RoomSummary {
+    pub id: i32,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub post_count: i64,
+    pub last_post_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct InstanceStats {
+    pub room_count: i64,
+    pub post_count: i64,
+    pub image_count: i64,
+    pub emote_count: i64,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct Ban {
+    pub id: i32,
+    pub post_id: Option<i64>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Action a room moderator may be permitted to perform
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModeratorPermission {
+    DeletePosts,
+    BanUsers,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Moderator {
+    pub id: i32,
+    pub name: String,
+    pub permissions: Vec<ModeratorPermission>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
 pub struct Emote {
     pub id: i32,
     pub name: String,
     pub url: String,
 }
 
+/// Manifest of a room archive, stored in the archive as "room.json"
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RoomArchive {
+    pub version: u32,
+    pub name: String,
+    pub exported_at: DateTime<Utc>,
+    pub post_count: usize,
+    pub emote_count: usize,
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct SysConfig {
     pub max_emote_size: usize,
@@ -73,12 +255,36 @@ impl Default for PlaybackState {
     }
 }
 
+impl ContentEndBehavior {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Loop => "loop",
+            Self::Hold => "hold",
+            Self::Stop => "stop",
+        }
+    }
+}
+
+impl FromStr for ContentEndBehavior {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "loop" => Ok(Self::Loop),
+            "hold" => Ok(Self::Hold),
+            "stop" => Ok(Self::Stop),
+            _ => Err(format!("Invalid content end behavior: {s}")),
+        }
+    }
+}
+
 impl From<&lm::Room> for Room {
     fn from(r: &lm::Room) -> Self {
         Self {
             id: r.id,
             name: r.name.clone(),
             content: r.content.clone(),
+            end_behavior: r.end_behavior,
         }
     }
 }
@@ -89,14 +295,32 @@ impl From<&lm::Post> for Post {
             id: p.id,
             name: p.name.clone(),
             comment: p.comment.as_ref().cloned(),
+            tokens: p.tokens.clone(),
             image: p.image.as_ref().map(|i| Image {
                 filename: i.filename.clone(),
                 url: format!("/f/i/{}.{}", i.hash, i.ext),
                 tn_url: format!("/f/t/{}.{}", i.hash, i.tn_ext),
             }),
             posted: p.posted_at,
+            edited: p.edited_at,
             admin: p.admin,
             you: false,
+            replies_to: p.replies_to.iter().map(|r| r.post_id).collect(),
+            replied_by: p.replied_by.clone(),
+            replies_to_you: false,
+            reactions: p.reactions_for_user(None),
+        }
+    }
+}
+
+impl From<&lm::Ban> for Ban {
+    fn from(b: &lm::Ban) -> Self {
+        Self {
+            id: b.id,
+            post_id: b.post_id,
+            reason: b.reason.clone(),
+            created_at: b.created_at,
+            expires_at: b.expires_at,
         }
     }
 }
@@ -114,3 +338,24 @@ impl From<&lm::Emote> for Emote {
 fn is_false(v: &bool) -> bool {
     !v
 }
+
+impl ModeratorPermission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::DeletePosts => "delete_posts",
+            Self::BanUsers => "ban_users",
+        }
+    }
+}
+
+impl FromStr for ModeratorPermission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "delete_posts" => Ok(Self::DeletePosts),
+            "ban_users" => Ok(Self::BanUsers),
+            _ => Err(format!("Invalid moderator permission: {s}")),
+        }
+    }
+}
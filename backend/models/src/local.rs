@@ -7,8 +7,16 @@ use uuid::Uuid;
 use crate::api as am;
 
 pub type SysConfig = am::SysConfig;
+pub type CommentToken = am::CommentToken;
 pub type Content = am::Content;
+pub type ContentEndBehavior = am::ContentEndBehavior;
 pub type PlaybackState = am::PlaybackState;
+pub type PostRevision = am::PostRevision;
+pub type QueueItem = am::QueueItem;
+pub type RoomSummary = am::RoomSummary;
+pub type InstanceStats = am::InstanceStats;
+pub type Moderator = am::Moderator;
+pub type ModeratorPermission = am::ModeratorPermission;
 
 #[derive(Debug)]
 pub struct HashedFile {
@@ -23,6 +31,23 @@ pub struct ClaimedRoom {
     pub password: String,
 }
 
+#[derive(Debug)]
+pub struct CreatedModerator {
+    pub moderator: Moderator,
+    pub key: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct Ban {
+    pub id: i32,
+    pub post_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub ip: Option<IpAddr>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct PostImage {
     pub filename: String,
@@ -36,10 +61,85 @@ pub struct Post {
     pub id: i64,
     pub name: Option<String>,
     pub comment: Option<String>,
+    pub tokens: Vec<CommentToken>,
     pub image: Option<PostImage>,
     pub posted_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
     pub user_id: i64,
     pub admin: bool,
+    pub replies_to: Vec<ReplyTo>,
+    pub replied_by: Vec<i64>,
+    pub reactions: Vec<PostReaction>,
+}
+
+/// Post replied to by another post
+#[derive(Clone, Debug)]
+pub struct ReplyTo {
+    pub post_id: i64,
+    pub user_id: i64,
+}
+
+/// Users who have reacted to a post with a reaction
+#[derive(Clone, Debug)]
+pub struct PostReaction {
+    pub reaction: String,
+    pub user_ids: Vec<i64>,
+}
+
+/// Reaction added to or removed from a post by a user
+#[derive(Clone, Debug)]
+pub struct ReactionChange {
+    pub post_id: i64,
+    pub reaction: String,
+    pub user_id: i64,
+    pub added: bool,
+    /// Number of users who have reacted with the reaction after the change
+    pub count: usize,
+}
+
+impl Post {
+    /// Whether the post replies to any post made by the specified user
+    pub fn is_reply_to_user(&self, user_id: i64) -> bool {
+        self.replies_to.iter().any(|r| r.user_id == user_id)
+    }
+
+    /// Aggregate the post's reactions, flagging the ones made by the specified user
+    pub fn reactions_for_user(&self, user_id: Option<i64>) -> Vec<am::Reaction> {
+        self.reactions
+            .iter()
+            .map(|r| am::Reaction {
+                reaction: r.reaction.clone(),
+                count: r.user_ids.len(),
+                you_reacted: user_id.is_some_and(|id| r.user_ids.contains(&id)),
+            })
+            .collect()
+    }
+
+    /// Apply a change to the post's reactions
+    pub fn apply_reaction_change(&mut self, change: &ReactionChange) {
+        let index = match self.reactions.iter().position(|r| r.reaction == change.reaction) {
+            Some(i) => i,
+            None => {
+                self.reactions.push(PostReaction {
+                    reaction: change.reaction.clone(),
+                    user_ids: Vec::new(),
+                });
+
+                self.reactions.len() - 1
+            }
+        };
+
+        let user_ids = &mut self.reactions[index].user_ids;
+        user_ids.retain(|id| *id != change.user_id);
+
+        if change.added {
+            user_ids.push(change.user_id);
+        }
+
+        if user_ids.is_empty() {
+            self.reactions.remove(index);
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -79,12 +179,35 @@ pub struct NewRoom<'a> {
     pub password: Cow<'a, str>,
 }
 
+/// Filters for searching a room's posts.
+/// Filters that are not specified are not applied.
+#[derive(Debug, Default)]
+pub struct PostSearch {
+    pub query: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub has_image: Option<bool>,
+    pub admin: Option<bool>,
+    pub user_id: Option<i64>,
+    pub before_id: Option<i64>,
+    pub limit: Option<u32>,
+}
+
 #[derive(Debug)]
 pub struct RefreshRefreshTokenResult<C> {
     pub token: Uuid,
     pub claims: C,
 }
 
+#[derive(Debug)]
+pub struct Session<C> {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub refreshed_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub claims: C,
+}
+
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct PlaybackStateAndTimestamp {
     pub state: PlaybackState,
@@ -97,4 +220,5 @@ pub struct Room {
     pub name: String,
     pub content: Option<Content>,
     pub playback_state: Option<PlaybackStateAndTimestamp>,
+    pub end_behavior: ContentEndBehavior,
 }
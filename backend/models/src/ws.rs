@@ -0,0 +1,184 @@
+use schemars::{JsonSchema, Schema, schema_for};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::api as am;
+
+/// Newest protocol version supported.
+///
+/// Version 1 frames a message as its type and JSON data separated by a pipe, such as `post|{"id":1}`.
+/// Version 2 frames a message as a JSON object, such as `{"type":"post","data":{"id":1}}`,
+/// and client messages can include an "id" which is returned in any error caused by the message.
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// Protocol version used by clients that do not request a version
+pub const LEGACY_PROTOCOL_VERSION: u32 = 1;
+
+/// Message sent by a client, along with an optional ID to correlate any error caused by it
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct ClientRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub message: ClientMessage,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "kebab-case")]
+pub enum ClientMessage {
+    /// Echoed back in a "pong" message
+    Ping(Value),
+    Join(JoinRequest),
+    Leave,
+    /// Authorize as a room admin using an access token
+    Auth(String),
+    SetMaster,
+    NotMaster,
+    #[serde(rename = "master-playbackstate")]
+    MasterPlaybackState(am::PlaybackState),
+    GetEmotes(GetSinceId<i32>),
+    GetRecentPosts(GetSinceId<i64>),
+    /// Sent repeatedly while typing, as typing stops automatically after a few seconds
+    TypingStart,
+    TypingStop,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct JoinRequest {
+    pub room: String,
+    /// User token
+    pub user: String,
+    /// Display name shown to other members
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Requested protocol version, or version 1 if not specified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<u32>,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct GetSinceId<T> {
+    pub since: T,
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "kebab-case")]
+pub enum ServerMessage {
+    Pong(Value),
+    /// Number of seconds until messages will be accepted again
+    RateLimited(f64),
+    Joined(Joined),
+    Content(Option<am::Content>),
+    #[serde(rename = "playbackstate")]
+    PlaybackState(am::PlaybackState),
+    Queue(Vec<am::QueueItem>),
+    ContentEnded(am::ContentEndBehavior),
+    NotMaster,
+    Emotes(Vec<am::Emote>),
+    Emote(am::Emote),
+    /// Name of the deleted emote
+    DeleteEmote(String),
+    #[serde(rename = "oldposts")]
+    OldPosts(Vec<am::Post>),
+    Post(am::Post),
+    PostEdited(am::Post),
+    DeletePost(i64),
+    /// IDs of posts deleted at once, such as when purging a room
+    DeletePosts(Vec<i64>),
+    Reaction(am::ReactionUpdate),
+    Presence(am::Presence),
+    /// Other users currently typing
+    Typing(Vec<am::Typist>),
+    Banned(am::Ban),
+    RoomDeleted,
+    Error(ErrorReply),
+}
+
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct Joined {
+    /// Protocol version used for messages sent by the server
+    pub protocol: u32,
+}
+
+/// Error caused by a client message
+#[derive(Clone, Debug, JsonSchema, Serialize)]
+pub struct ErrorReply {
+    /// ID of the client message that caused the error, if it had one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<u64>,
+    pub message: String,
+}
+
+/// Schemas of the messages sent by clients and the server
+#[derive(Serialize)]
+pub struct ProtocolSchema {
+    pub version: u32,
+    pub client: Schema,
+    pub server: Schema,
+}
+
+impl ClientRequest {
+    /// Decode a message in either framing, as clients may use either regardless of the negotiated version
+    pub fn decode(text: &str) -> Result<Self, serde_json::Error> {
+        if text.trim_start().starts_with('{') {
+            return serde_json::from_str(text);
+        }
+
+        let (msg, data) = text.split_once('|').unwrap_or((text, ""));
+
+        let mut value = serde_json::Map::new();
+        value.insert("type".to_owned(), Value::String(msg.to_owned()));
+
+        if !data.trim().is_empty() {
+            value.insert("data".to_owned(), serde_json::from_str(data)?);
+        }
+
+        serde_json::from_value(Value::Object(value))
+    }
+
+    /// Get the ID of a message that could not be decoded, if it has one
+    pub fn peek_id(text: &str) -> Option<u64> {
+        #[derive(Deserialize)]
+        struct Id {
+            id: Option<u64>,
+        }
+
+        serde_json::from_str::<Id>(text).ok()?.id
+    }
+}
+
+impl ServerMessage {
+    /// Encode the message using the framing of a protocol version
+    pub fn encode(&self, version: u32) -> Result<String, serde_json::Error> {
+        if version >= 2 {
+            return serde_json::to_string(self);
+        }
+
+        let Value::Object(mut value) = serde_json::to_value(self)? else {
+            unreachable!("Tagged enums serialize as objects");
+        };
+
+        let msg = value.remove("type").unwrap_or_default();
+        let data = value.remove("data").unwrap_or_default();
+
+        Ok(format!("{}|{data}", msg.as_str().unwrap_or_default()))
+    }
+}
+
+impl ProtocolSchema {
+    pub fn generate() -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            client: schema_for!(ClientRequest),
+            server: schema_for!(ServerMessage),
+        }
+    }
+}
+
+/// Get the protocol version to use for messages sent to a client requesting a version.
+/// Until a version has been negotiated by joining a room, version 1 is used.
+pub fn negotiate_version(requested: Option<u32>) -> u32 {
+    requested
+        .unwrap_or(LEGACY_PROTOCOL_VERSION)
+        .clamp(LEGACY_PROTOCOL_VERSION, PROTOCOL_VERSION)
+}
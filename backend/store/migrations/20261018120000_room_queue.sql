@@ -0,0 +1,157 @@
+-- Create queue_item table
+CREATE TABLE queue_item
+(
+  id bigserial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  room_id integer NOT NULL,
+  position integer NOT NULL,
+  content json NOT NULL,
+  PRIMARY KEY (id),
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('queue_item'); -- Automatically manage updated_at
+
+-- Create room_id index on queue_item
+CREATE INDEX queue_item_room_id_idx ON queue_item
+  USING btree
+  (room_id ASC NULLS LAST);
+
+-- Create get_queue function
+CREATE FUNCTION get_queue(IN p_room_id integer)
+RETURNS SETOF queue_item
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT q.*
+  FROM queue_item AS q
+  WHERE q.room_id = p_room_id
+  ORDER BY q.position ASC, q.id ASC;
+END;
+$BODY$;
+
+-- Create add_queue_item function
+CREATE FUNCTION add_queue_item(
+  IN p_room_id integer,
+  IN p_content json
+)
+RETURNS SETOF queue_item
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_position integer;
+BEGIN
+  -- Append to the end of the queue
+  SELECT COALESCE(MAX(position) + 1, 0) INTO v_position
+  FROM queue_item
+  WHERE room_id = p_room_id;
+
+  RETURN QUERY
+  INSERT INTO queue_item (
+    room_id,
+    position,
+    content
+  )
+  SELECT
+    p_room_id, -- room_id
+    v_position, -- position
+    p_content -- content
+  RETURNING *;
+END;
+$BODY$;
+
+-- Create delete_queue_item function
+CREATE FUNCTION delete_queue_item(
+  IN p_room_id integer,
+  IN p_item_id bigint
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_position integer;
+BEGIN
+  DELETE FROM queue_item
+  WHERE room_id = p_room_id AND id = p_item_id
+  RETURNING position INTO v_position;
+
+  IF v_position IS NULL THEN
+    RETURN false;
+  END IF;
+
+  -- Close the gap left by the deleted item
+  UPDATE queue_item
+  SET position = position - 1
+  WHERE room_id = p_room_id AND position > v_position;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create move_queue_item function
+CREATE FUNCTION move_queue_item(
+  IN p_room_id integer,
+  IN p_item_id bigint,
+  IN p_position integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_old_position integer;
+  v_max_position integer;
+  v_new_position integer;
+BEGIN
+  SELECT position INTO v_old_position
+  FROM queue_item
+  WHERE room_id = p_room_id AND id = p_item_id;
+
+  IF v_old_position IS NULL THEN
+    RETURN false;
+  END IF;
+
+  SELECT MAX(position) INTO v_max_position
+  FROM queue_item
+  WHERE room_id = p_room_id;
+
+  v_new_position := GREATEST(0, LEAST(p_position, v_max_position));
+
+  IF v_new_position > v_old_position THEN
+    UPDATE queue_item
+    SET position = position - 1
+    WHERE room_id = p_room_id AND position > v_old_position AND position <= v_new_position;
+  ELSIF v_new_position < v_old_position THEN
+    UPDATE queue_item
+    SET position = position + 1
+    WHERE room_id = p_room_id AND position >= v_new_position AND position < v_old_position;
+  END IF;
+
+  UPDATE queue_item
+  SET position = v_new_position
+  WHERE id = p_item_id;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create clear_queue function
+CREATE FUNCTION clear_queue(IN p_room_id integer)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM queue_item
+  WHERE room_id = p_room_id;
+END;
+$BODY$;
@@ -0,0 +1,19 @@
+-- Add end_behavior column to room table
+ALTER TABLE room
+  ADD COLUMN end_behavior text NOT NULL DEFAULT 'stop';
+
+-- Create set_room_end_behavior function
+CREATE FUNCTION set_room_end_behavior(
+  IN p_room_id integer,
+  IN p_end_behavior text
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET end_behavior = p_end_behavior
+  WHERE id = p_room_id;
+END;
+$BODY$;
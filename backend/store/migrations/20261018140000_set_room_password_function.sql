@@ -0,0 +1,15 @@
+-- Create set_room_password function
+CREATE FUNCTION set_room_password(
+  IN p_room_id integer,
+  IN p_password text
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET password = p_password
+  WHERE id = p_room_id;
+END;
+$BODY$;
@@ -0,0 +1,110 @@
+-- Add room_id column to refresh_token table
+ALTER TABLE refresh_token
+  ADD COLUMN room_id integer;
+
+ALTER TABLE refresh_token
+  ADD FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID;
+
+-- Initialize room_id column from claims for existing records
+UPDATE refresh_token AS t
+SET room_id = r.id
+FROM room AS r
+WHERE r.id = (t.claims::json->>'room_id')::integer;
+
+-- Create room_id index on refresh_token
+CREATE INDEX refresh_token_room_id_idx ON refresh_token
+  USING btree
+  (room_id ASC NULLS LAST);
+
+-- Drop old functions
+DROP FUNCTION create_refresh_token;
+
+-- Update create_refresh_token function
+CREATE FUNCTION create_refresh_token(
+  IN p_claims text,
+  IN p_room_id integer
+)
+RETURNS uuid
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_new_token uuid;
+BEGIN
+  -- Generate new refresh token with new family
+  INSERT INTO refresh_token (family, claims, room_id)
+  VALUES (nextval('refresh_token_family_seq'), p_claims, p_room_id)
+  RETURNING token INTO v_new_token;
+
+  -- Return new token
+  RETURN v_new_token;
+END;
+$BODY$;
+
+-- Update refresh_refresh_token function
+CREATE OR REPLACE FUNCTION refresh_refresh_token(
+  IN p_token uuid
+)
+RETURNS refresh_refresh_token_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_refresh_token refresh_token;
+  v_result refresh_refresh_token_result;
+BEGIN
+  SELECT * INTO v_refresh_token
+  FROM refresh_token
+  WHERE token = p_token;
+
+  -- Check if exists
+  IF v_refresh_token IS NULL THEN
+    RETURN NULL;
+  END IF;
+
+  -- Check if already used
+  IF v_refresh_token.used THEN
+    UPDATE refresh_token
+    SET used = true
+    WHERE family = v_refresh_token.family;
+
+    RETURN NULL;
+  END IF;
+
+  -- Check if expired
+  IF v_refresh_token.expires_at < CURRENT_TIMESTAMP THEN
+    RETURN NULL;
+  END IF;
+
+  -- Mark token as used
+  UPDATE refresh_token
+  SET used = true
+  WHERE id = v_refresh_token.id;
+
+  -- Generate new refresh token
+  INSERT INTO refresh_token (family, claims, room_id)
+  VALUES (v_refresh_token.family, v_refresh_token.claims, v_refresh_token.room_id)
+  RETURNING token, claims INTO v_result;
+
+  -- Return result
+  RETURN v_result;
+END;
+$BODY$;
+
+-- Create revoke_room_refresh_tokens function
+CREATE FUNCTION revoke_room_refresh_tokens(
+  IN p_room_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM refresh_token
+  WHERE room_id = p_room_id;
+END;
+$BODY$;
@@ -0,0 +1,124 @@
+-- Update refresh_refresh_token function
+CREATE OR REPLACE FUNCTION refresh_refresh_token(
+  IN p_token uuid
+)
+RETURNS refresh_refresh_token_result
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_refresh_token refresh_token;
+  v_result refresh_refresh_token_result;
+BEGIN
+  SELECT * INTO v_refresh_token
+  FROM refresh_token
+  WHERE token = p_token
+  FOR UPDATE;
+
+  -- Check if exists
+  IF v_refresh_token IS NULL THEN
+    RETURN NULL;
+  END IF;
+
+  -- Check if already used.
+  -- If a token that has already been rotated is presented again,
+  -- it has likely been stolen, so revoke the whole family.
+  IF v_refresh_token.used THEN
+    DELETE FROM refresh_token
+    WHERE family = v_refresh_token.family;
+
+    RETURN NULL;
+  END IF;
+
+  -- Check if expired
+  IF v_refresh_token.expires_at < CURRENT_TIMESTAMP THEN
+    RETURN NULL;
+  END IF;
+
+  -- Mark token as used
+  UPDATE refresh_token
+  SET used = true
+  WHERE id = v_refresh_token.id;
+
+  -- Generate new refresh token
+  INSERT INTO refresh_token (family, claims, room_id)
+  VALUES (v_refresh_token.family, v_refresh_token.claims, v_refresh_token.room_id)
+  RETURNING token, claims INTO v_result;
+
+  -- Return result
+  RETURN v_result;
+END;
+$BODY$;
+
+-- Create revoke_refresh_token_family function
+CREATE FUNCTION revoke_refresh_token_family(
+  IN p_token uuid
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_family bigint;
+BEGIN
+  SELECT family INTO v_family
+  FROM refresh_token
+  WHERE token = p_token;
+
+  IF v_family IS NULL THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM refresh_token
+  WHERE family = v_family;
+
+  RETURN true;
+END;
+$BODY$;
+
+-- Create get_room_sessions function
+CREATE FUNCTION get_room_sessions(IN p_room_id integer)
+RETURNS TABLE (
+  family bigint,
+  created_at timestamp with time zone,
+  refreshed_at timestamp with time zone,
+  expires_at timestamp with time zone,
+  claims text
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    t.family,
+    (SELECT MIN(f.created_at) FROM refresh_token AS f WHERE f.family = t.family), -- created_at
+    t.created_at, -- refreshed_at
+    t.expires_at,
+    t.claims
+  FROM refresh_token AS t
+  WHERE t.room_id = p_room_id AND NOT t.used AND t.expires_at >= CURRENT_TIMESTAMP
+  ORDER BY t.created_at DESC;
+END;
+$BODY$;
+
+-- Create revoke_room_session function
+CREATE FUNCTION revoke_room_session(
+  IN p_room_id integer,
+  IN p_family bigint
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_count integer;
+BEGIN
+  DELETE FROM refresh_token
+  WHERE room_id = p_room_id AND family = p_family;
+
+  GET DIAGNOSTICS v_deleted_count = ROW_COUNT;
+
+  RETURN v_deleted_count > 0;
+END;
+$BODY$;
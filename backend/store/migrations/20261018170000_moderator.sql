@@ -0,0 +1,112 @@
+-- Create moderator table
+CREATE TABLE moderator
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  room_id integer NOT NULL,
+  name text NOT NULL,
+  key text NOT NULL,
+  permissions text[] NOT NULL,
+  PRIMARY KEY (id),
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+  UNIQUE (room_id, name)
+);
+
+SELECT manage_updated_at('moderator'); -- Automatically manage updated_at
+
+-- Create get_moderators function
+CREATE FUNCTION get_moderators(IN p_room_id integer)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT m.*
+  FROM moderator AS m
+  WHERE m.room_id = p_room_id
+  ORDER BY m.id ASC;
+END;
+$BODY$;
+
+-- Create get_moderator_by_name function
+CREATE FUNCTION get_moderator_by_name(
+  IN p_room_id integer,
+  IN p_name text
+)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT m.*
+  FROM moderator AS m
+  WHERE m.room_id = p_room_id AND m.name = p_name;
+END;
+$BODY$;
+
+-- Create create_moderator function
+CREATE FUNCTION create_moderator(
+  IN p_room_id integer,
+  IN p_name text,
+  IN p_key text,
+  IN p_permissions text[]
+)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  INSERT INTO moderator (
+    room_id,
+    name,
+    key,
+    permissions
+  )
+  VALUES (
+    p_room_id,
+    p_name,
+    p_key,
+    p_permissions
+  )
+  ON CONFLICT (room_id, name) DO NOTHING
+  RETURNING *;
+END;
+$BODY$;
+
+-- Create delete_moderator function
+CREATE FUNCTION delete_moderator(
+  IN p_room_id integer,
+  IN p_moderator_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_id integer;
+BEGIN
+  DELETE FROM moderator
+  WHERE room_id = p_room_id AND id = p_moderator_id
+  RETURNING id INTO v_deleted_id;
+
+  IF v_deleted_id IS NULL THEN
+    RETURN false;
+  END IF;
+
+  -- Revoke all sessions belonging to the moderator
+  DELETE FROM refresh_token
+  WHERE room_id = p_room_id
+    AND claims::json->>'level' = 'moderator'
+    AND (claims::json->>'moderator_id')::integer = p_moderator_id;
+
+  RETURN true;
+END;
+$BODY$;
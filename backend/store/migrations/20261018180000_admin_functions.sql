@@ -0,0 +1,83 @@
+-- Create get_rooms function
+CREATE FUNCTION get_rooms()
+RETURNS TABLE (
+  id integer,
+  name text,
+  created_at timestamp with time zone,
+  post_count bigint,
+  last_post_at timestamp with time zone
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    r.id,
+    r.name,
+    r.created_at,
+    COUNT(p.id) AS post_count,
+    MAX(p.created_at) AS last_post_at
+  FROM room AS r
+  LEFT JOIN post AS p ON p.room_id = r.id AND NOT p.is_deleted
+  GROUP BY r.id
+  ORDER BY r.id ASC;
+END;
+$BODY$;
+
+-- Create delete_room function
+CREATE FUNCTION delete_room(
+  IN p_room_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_id integer;
+BEGIN
+  DELETE FROM room
+  WHERE id = p_room_id
+  RETURNING id INTO v_deleted_id;
+
+  RETURN v_deleted_id IS NOT NULL;
+END;
+$BODY$;
+
+-- Create purge_room_posts function
+CREATE FUNCTION purge_room_posts(
+  IN p_room_id integer
+)
+RETURNS SETOF bigint
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  UPDATE post
+  SET is_deleted = true
+  WHERE room_id = p_room_id AND NOT is_deleted
+  RETURNING id;
+END;
+$BODY$;
+
+-- Create get_instance_stats function
+CREATE FUNCTION get_instance_stats()
+RETURNS TABLE (
+  room_count bigint,
+  post_count bigint,
+  image_count bigint,
+  emote_count bigint
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    (SELECT COUNT(*) FROM room) AS room_count,
+    (SELECT COUNT(*) FROM post WHERE NOT is_deleted) AS post_count,
+    (SELECT COUNT(*) FROM image AS i JOIN post AS p ON p.id = i.post_id WHERE NOT p.is_deleted) AS image_count,
+    (SELECT COUNT(*) FROM emote) AS emote_count;
+END;
+$BODY$;
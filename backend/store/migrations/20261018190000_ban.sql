@@ -0,0 +1,119 @@
+-- Create ban table
+CREATE TABLE ban
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  room_id integer NOT NULL,
+  post_id bigint,
+  user_id bigint,
+  ip inet,
+  reason text,
+  expires_at timestamp with time zone,
+  PRIMARY KEY (id),
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+  CHECK (user_id IS NOT NULL OR ip IS NOT NULL)
+);
+
+SELECT manage_updated_at('ban'); -- Automatically manage updated_at
+
+-- Create room_id index on ban
+CREATE INDEX ban_room_id_idx ON ban
+  USING btree
+  (room_id ASC NULLS LAST);
+
+-- Create get_bans function
+CREATE FUNCTION get_bans(IN p_room_id integer)
+RETURNS SETOF ban
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT b.*
+  FROM ban AS b
+  WHERE b.room_id = p_room_id
+    AND (b.expires_at IS NULL OR b.expires_at > CURRENT_TIMESTAMP)
+  ORDER BY b.id DESC;
+END;
+$BODY$;
+
+-- Create get_active_ban function
+CREATE FUNCTION get_active_ban(
+  IN p_room_id integer,
+  IN p_user_id bigint,
+  IN p_ip inet
+)
+RETURNS SETOF ban
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT b.*
+  FROM ban AS b
+  WHERE b.room_id = p_room_id
+    AND (b.user_id = p_user_id OR b.ip = p_ip)
+    AND (b.expires_at IS NULL OR b.expires_at > CURRENT_TIMESTAMP)
+  ORDER BY b.expires_at DESC NULLS FIRST
+  LIMIT 1;
+END;
+$BODY$;
+
+-- Create create_ban_from_post function
+CREATE FUNCTION create_ban_from_post(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_reason text,
+  IN p_expires_at timestamp with time zone
+)
+RETURNS SETOF ban
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  INSERT INTO ban (
+    room_id,
+    post_id,
+    user_id,
+    ip,
+    reason,
+    expires_at
+  )
+  SELECT
+    p.room_id,
+    p.id,
+    p.user_id,
+    p.ip,
+    p_reason,
+    p_expires_at
+  FROM post AS p
+  WHERE p.room_id = p_room_id AND p.id = p_post_id
+  RETURNING *;
+END;
+$BODY$;
+
+-- Create delete_ban function
+CREATE FUNCTION delete_ban(
+  IN p_room_id integer,
+  IN p_ban_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_id integer;
+BEGIN
+  DELETE FROM ban
+  WHERE room_id = p_room_id AND id = p_ban_id
+  RETURNING id INTO v_deleted_id;
+
+  RETURN v_deleted_id IS NOT NULL;
+END;
+$BODY$;
@@ -0,0 +1,85 @@
+-- Create room expires_at index
+CREATE INDEX room_expires_at_idx ON room
+  USING btree
+  (expires_at ASC NULLS LAST);
+
+-- Drop old functions
+DROP FUNCTION create_room;
+
+-- Update get_room_by_name function
+CREATE OR REPLACE FUNCTION get_room_by_name(IN p_room_name text)
+RETURNS SETOF room
+LANGUAGE sql
+
+AS $BODY$
+SELECT * FROM room WHERE name = p_room_name AND expires_at > CURRENT_TIMESTAMP LIMIT 1;
+$BODY$;
+
+-- Create create_room function
+CREATE FUNCTION create_room(
+  IN p_name text,
+  IN p_password text,
+  IN p_expires_at timestamp with time zone
+)
+RETURNS SETOF room
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_room_id integer;
+BEGIN
+  SELECT id INTO v_room_id FROM get_room_by_name(p_name);
+  IF v_room_id IS NOT NULL THEN
+    RAISE EXCEPTION 'Room already exists.';
+  END IF;
+
+  -- Free up the name if it belongs to an expired room
+  DELETE FROM room
+  WHERE name = p_name AND expires_at <= CURRENT_TIMESTAMP;
+
+  -- Insert room
+  RETURN QUERY
+  INSERT INTO room (
+    name,
+    claimed_at,
+    expires_at,
+    password
+  )
+  SELECT
+    p_name, -- name
+    CURRENT_TIMESTAMP, -- claimed_at
+    COALESCE(p_expires_at, to_timestamp('9999-12-31', 'YYYY-MM-DD')), -- expires_at
+    p_password -- password
+  RETURNING *;
+END;
+$BODY$;
+
+-- Create extend_room_expiry function
+CREATE FUNCTION extend_room_expiry(
+  IN p_room_id integer,
+  IN p_expires_at timestamp with time zone
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET expires_at = p_expires_at
+  WHERE id = p_room_id AND expires_at > CURRENT_TIMESTAMP;
+END;
+$BODY$;
+
+-- Create delete_expired_rooms function
+CREATE FUNCTION delete_expired_rooms()
+RETURNS SETOF integer
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  DELETE FROM room
+  WHERE expires_at <= CURRENT_TIMESTAMP
+  RETURNING id;
+END;
+$BODY$;
@@ -0,0 +1,16 @@
+-- Create get_posts function
+CREATE FUNCTION get_posts(IN p_room_id integer, IN p_before_id bigint, IN p_count integer)
+RETURNS TABLE (post post, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT p AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  WHERE p.room_id = p_room_id AND NOT p.is_deleted AND (p_before_id IS NULL OR p.id < p_before_id)
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
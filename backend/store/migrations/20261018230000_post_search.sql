@@ -0,0 +1,88 @@
+-- Create post_search table
+-- Kept separate from post, so that the post composite type returned by functions is unaffected
+CREATE TABLE post_search
+(
+  post_id bigint NOT NULL,
+  search_vector tsvector NOT NULL,
+  PRIMARY KEY (post_id),
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+-- Create search_vector index on post_search
+CREATE INDEX post_search_search_vector_idx ON post_search
+  USING gin
+  (search_vector);
+
+-- Create post_search_vector function
+CREATE FUNCTION post_search_vector(IN p_name text, IN p_comment text)
+RETURNS tsvector
+LANGUAGE sql
+IMMUTABLE
+
+AS $BODY$
+SELECT to_tsvector('simple', COALESCE(p_name, '') || ' ' || COALESCE(p_comment, ''));
+$BODY$;
+
+-- Create update_post_search function
+CREATE FUNCTION update_post_search()
+RETURNS trigger
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  INSERT INTO post_search (post_id, search_vector)
+  VALUES (NEW.id, post_search_vector(NEW.name, NEW.comment))
+  ON CONFLICT (post_id) DO UPDATE SET search_vector = EXCLUDED.search_vector;
+
+  RETURN NEW;
+END;
+$BODY$;
+
+-- Keep post_search up to date
+CREATE TRIGGER update_post_search AFTER INSERT OR UPDATE OF name, comment ON post
+  FOR EACH ROW EXECUTE FUNCTION update_post_search();
+
+-- Index existing posts
+INSERT INTO post_search (post_id, search_vector)
+SELECT id, post_search_vector(name, comment)
+FROM post;
+
+-- Create search_posts function
+CREATE FUNCTION search_posts(
+  IN p_room_id integer,
+  IN p_query text,
+  IN p_from timestamp with time zone,
+  IN p_to timestamp with time zone,
+  IN p_has_image boolean,
+  IN p_admin boolean,
+  IN p_user_id bigint,
+  IN p_before_id bigint,
+  IN p_count integer
+)
+RETURNS TABLE (post post, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT p AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  LEFT JOIN post_search AS s ON s.post_id = p.id
+  WHERE p.room_id = p_room_id
+    AND NOT p.is_deleted
+    AND (p_query IS NULL OR s.search_vector @@ websearch_to_tsquery('simple', p_query))
+    AND (p_from IS NULL OR p.created_at >= p_from)
+    AND (p_to IS NULL OR p.created_at < p_to)
+    AND (p_has_image IS NULL OR (i.id IS NOT NULL) = p_has_image)
+    AND (p_admin IS NULL OR p.admin = p_admin)
+    AND (p_user_id IS NULL OR p.user_id = p_user_id)
+    AND (p_before_id IS NULL OR p.id < p_before_id)
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
@@ -0,0 +1,79 @@
+-- Create post_reply table
+CREATE TABLE post_reply
+(
+  post_id bigint NOT NULL,
+  reply_to_id bigint NOT NULL,
+  PRIMARY KEY (post_id, reply_to_id),
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+  FOREIGN KEY (reply_to_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+-- Create reply_to_id index on post_reply
+CREATE INDEX post_reply_reply_to_id_idx ON post_reply
+  USING btree
+  (reply_to_id);
+
+-- Create replies for quote links in existing posts
+INSERT INTO post_reply (post_id, reply_to_id)
+SELECT DISTINCT p.id, t.id
+FROM post AS p
+CROSS JOIN LATERAL regexp_matches(p.comment, '>>(\d+)', 'g') AS m
+JOIN post AS t ON t.id = m[1]::bigint
+WHERE t.room_id = p.room_id
+  AND t.id <> p.id;
+
+-- Create create_post_replies function
+CREATE FUNCTION create_post_replies(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_reply_to_ids bigint[]
+)
+RETURNS TABLE (post_id bigint, reply_to_id bigint, reply_to_user_id bigint)
+LANGUAGE sql
+
+AS $BODY$
+WITH reply_to AS (
+  SELECT t.id, t.user_id
+  FROM post AS t
+  WHERE t.room_id = p_room_id
+    AND t.id = ANY(p_reply_to_ids)
+    AND t.id <> p_post_id
+    AND NOT t.is_deleted
+    AND EXISTS (SELECT 1 FROM post AS p WHERE p.id = p_post_id AND p.room_id = p_room_id)
+), inserted AS (
+  INSERT INTO post_reply (post_id, reply_to_id)
+  SELECT p_post_id, r.id
+  FROM reply_to AS r
+  ON CONFLICT DO NOTHING
+  RETURNING post_reply.reply_to_id
+)
+SELECT p_post_id, r.id, r.user_id
+FROM reply_to AS r
+JOIN inserted AS i ON i.reply_to_id = r.id
+ORDER BY r.id;
+$BODY$;
+
+-- Create get_post_replies function
+CREATE FUNCTION get_post_replies(IN p_post_ids bigint[])
+RETURNS TABLE (post_id bigint, reply_to_id bigint, reply_to_user_id bigint)
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT r.post_id, r.reply_to_id, t.user_id
+FROM post_reply AS r
+JOIN post AS p ON p.id = r.post_id
+JOIN post AS t ON t.id = r.reply_to_id
+WHERE (r.post_id = ANY(p_post_ids) OR r.reply_to_id = ANY(p_post_ids))
+  AND NOT p.is_deleted
+  AND NOT t.is_deleted
+ORDER BY r.post_id, r.reply_to_id;
+$BODY$;
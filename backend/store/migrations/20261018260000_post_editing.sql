@@ -0,0 +1,96 @@
+-- Add edited_at column to post table
+ALTER TABLE post
+  ADD COLUMN edited_at timestamp with time zone;
+
+-- Create post_revision table
+-- Each revision holds a previous comment of a post, and when it was posted or last edited
+CREATE TABLE post_revision
+(
+  id bigserial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  post_id bigint NOT NULL,
+  comment text,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+-- Create post_id index on post_revision
+CREATE INDEX post_revision_post_id_idx ON post_revision
+  USING btree
+  (post_id ASC NULLS LAST);
+
+-- Create edit_post function
+CREATE FUNCTION edit_post(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_is_admin boolean,
+  IN p_comment text,
+  IN p_editable_since timestamp with time zone
+)
+RETURNS TABLE (post post, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+BEGIN
+  SELECT * INTO v_post
+  FROM post AS p
+  WHERE p.room_id = p_room_id
+    AND p.id = p_post_id
+    AND NOT p.is_deleted
+    AND (p_is_admin OR (p.user_id = p_user_id AND p.created_at >= p_editable_since))
+  FOR UPDATE;
+
+  IF NOT FOUND THEN
+    RETURN;
+  END IF;
+
+  -- Keep the previous comment
+  INSERT INTO post_revision (
+    created_at,
+    post_id,
+    comment
+  )
+  SELECT
+    COALESCE(v_post.edited_at, v_post.created_at), -- created_at
+    v_post.id, -- post_id
+    v_post.comment -- comment
+  ;
+
+  UPDATE post AS p
+  SET comment = p_comment, edited_at = now()
+  WHERE p.id = v_post.id
+  RETURNING * INTO v_post;
+
+  -- Quote links may have changed, so replies will be recreated from the new comment
+  DELETE FROM post_reply AS r
+  WHERE r.post_id = v_post.id;
+
+  RETURN QUERY
+  SELECT v_post AS post, i AS image
+  FROM (SELECT) AS v
+  LEFT JOIN image AS i ON i.post_id = v_post.id;
+END;
+$BODY$;
+
+-- Create get_post_revisions function
+CREATE FUNCTION get_post_revisions(IN p_room_id integer, IN p_post_id bigint)
+RETURNS SETOF post_revision
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT r.*
+FROM post_revision AS r
+INNER JOIN post AS p ON p.id = r.post_id
+WHERE p.room_id = p_room_id AND r.post_id = p_post_id AND NOT p.is_deleted
+ORDER BY r.id;
+$BODY$;
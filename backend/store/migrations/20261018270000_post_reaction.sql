@@ -0,0 +1,77 @@
+-- Create post_reaction table
+-- A reaction is either the name of one of the room's emotes or a Unicode emoji
+CREATE TABLE post_reaction
+(
+  post_id bigint NOT NULL,
+  reaction text NOT NULL,
+  user_id bigint NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT now(),
+  PRIMARY KEY (post_id, reaction, user_id),
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+-- Create add_post_reaction function
+CREATE FUNCTION add_post_reaction(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_reaction text
+)
+RETURNS bigint
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE room_id = p_room_id AND id = p_post_id AND NOT is_deleted) THEN
+    RETURN NULL;
+  END IF;
+
+  INSERT INTO post_reaction (post_id, reaction, user_id)
+  VALUES (p_post_id, p_reaction, p_user_id)
+  ON CONFLICT DO NOTHING;
+
+  RETURN (SELECT count(*) FROM post_reaction WHERE post_id = p_post_id AND reaction = p_reaction);
+END;
+$BODY$;
+
+-- Create remove_post_reaction function
+CREATE FUNCTION remove_post_reaction(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_reaction text
+)
+RETURNS bigint
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE room_id = p_room_id AND id = p_post_id AND NOT is_deleted) THEN
+    RETURN NULL;
+  END IF;
+
+  DELETE FROM post_reaction
+  WHERE post_id = p_post_id AND reaction = p_reaction AND user_id = p_user_id;
+
+  RETURN (SELECT count(*) FROM post_reaction WHERE post_id = p_post_id AND reaction = p_reaction);
+END;
+$BODY$;
+
+-- Create get_post_reactions function
+CREATE FUNCTION get_post_reactions(IN p_post_ids bigint[])
+RETURNS TABLE (post_id bigint, reaction text, user_id bigint)
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT r.post_id, r.reaction, r.user_id
+FROM post_reaction AS r
+JOIN post AS p ON p.id = r.post_id
+WHERE r.post_id = ANY(p_post_ids)
+  AND NOT p.is_deleted
+ORDER BY r.post_id, r.created_at, r.user_id;
+$BODY$;
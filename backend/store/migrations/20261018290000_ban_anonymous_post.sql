@@ -0,0 +1,36 @@
+-- Don't try to ban posts that have neither a user ID nor an IP address
+DROP FUNCTION create_ban_from_post;
+
+-- Create create_ban_from_post function
+CREATE FUNCTION create_ban_from_post(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_reason text,
+  IN p_expires_at timestamp with time zone
+)
+RETURNS SETOF ban
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  INSERT INTO ban (
+    room_id,
+    post_id,
+    user_id,
+    ip,
+    reason,
+    expires_at
+  )
+  SELECT
+    p.room_id,
+    p.id,
+    p.user_id,
+    p.ip,
+    p_reason,
+    p_expires_at
+  FROM post AS p
+  WHERE p.room_id = p_room_id AND p.id = p_post_id AND (p.user_id IS NOT NULL OR p.ip IS NOT NULL)
+  RETURNING *;
+END;
+$BODY$;
@@ -0,0 +1,40 @@
+-- Functions for finding image files that are no longer used after deleting rooms
+
+-- Create get_room_images function
+CREATE FUNCTION get_room_images(IN p_room_id integer)
+RETURNS SETOF image
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT i.*
+FROM image AS i
+INNER JOIN post AS p ON p.id = i.post_id
+WHERE p.room_id = p_room_id;
+$BODY$;
+
+-- Create get_expired_room_images function
+CREATE FUNCTION get_expired_room_images()
+RETURNS SETOF image
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT i.*
+FROM image AS i
+INNER JOIN post AS p ON p.id = i.post_id
+INNER JOIN room AS r ON r.id = p.room_id
+WHERE r.expires_at <= CURRENT_TIMESTAMP;
+$BODY$;
+
+-- Create get_used_image_hashes function
+CREATE FUNCTION get_used_image_hashes(IN p_hashes text[])
+RETURNS SETOF text
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT DISTINCT hash
+FROM image
+WHERE hash = ANY(p_hashes);
+$BODY$;
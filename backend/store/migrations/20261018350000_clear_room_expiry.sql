@@ -0,0 +1,14 @@
+-- Make all rooms never expire again when room expiry is disabled
+
+-- Create clear_room_expiry function
+CREATE FUNCTION clear_room_expiry()
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET expires_at = to_timestamp('9999-12-31', 'YYYY-MM-DD')
+  WHERE expires_at < to_timestamp('9999-12-31', 'YYYY-MM-DD');
+END;
+$BODY$;
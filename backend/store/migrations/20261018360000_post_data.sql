@@ -0,0 +1,285 @@
+-- Return posts without their search vector, which is only needed for searching
+DROP FUNCTION create_post;
+DROP FUNCTION edit_post;
+DROP FUNCTION get_posts;
+DROP FUNCTION get_recent_posts;
+DROP FUNCTION import_post;
+DROP FUNCTION search_posts;
+
+-- Create post_data type
+CREATE TYPE post_data AS (
+  id bigint,
+  created_at timestamp with time zone,
+  updated_at timestamp with time zone,
+  room_id integer,
+  name text,
+  comment text,
+  ip inet,
+  is_deleted boolean,
+  user_id bigint,
+  admin boolean,
+  edited_at timestamp with time zone
+);
+
+-- Create post_data function
+CREATE FUNCTION post_data(IN p_post post)
+RETURNS post_data
+LANGUAGE sql
+IMMUTABLE
+
+AS $BODY$
+SELECT
+  p_post.id,
+  p_post.created_at,
+  p_post.updated_at,
+  p_post.room_id,
+  p_post.name,
+  p_post.comment,
+  p_post.ip,
+  p_post.is_deleted,
+  p_post.user_id,
+  p_post.admin,
+  p_post.edited_at;
+$BODY$;
+
+-- Create create_post function
+CREATE FUNCTION create_post(
+  IN p_room_id integer,
+  IN p_post new_post,
+  IN p_image new_image
+)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+  v_image image;
+BEGIN
+  -- Insert post
+  INSERT INTO post (
+    room_id,
+    name,
+    comment,
+    ip,
+    user_id,
+    admin
+  )
+  SELECT
+    p_room_id, -- room_id
+    p_post.name, -- name
+    p_post.comment, -- comment
+    p_post.ip, -- ip
+    p_post.user_id, -- user_id
+    p_post.admin -- admin
+  RETURNING * INTO v_post;
+
+  -- If an image is provided, insert it
+  IF NOT p_image IS NULL THEN
+    INSERT INTO image (
+      post_id,
+      filename,
+      hash,
+      ext,
+      tn_ext
+    )
+    SELECT
+      v_post.id, -- post_id
+      p_image.filename, -- filename
+      p_image.hash, -- hash
+      p_image.ext, -- ext
+      p_image.tn_ext -- tn_ext
+    RETURNING * INTO v_image;
+  END IF;
+
+  RETURN QUERY SELECT post_data(v_post) AS post, v_image AS image;
+END;
+$BODY$;
+
+-- Create edit_post function
+CREATE FUNCTION edit_post(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_is_admin boolean,
+  IN p_comment text,
+  IN p_editable_since timestamp with time zone
+)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+BEGIN
+  SELECT * INTO v_post
+  FROM post AS p
+  WHERE p.room_id = p_room_id
+    AND p.id = p_post_id
+    AND NOT p.is_deleted
+    AND (p_is_admin OR (p.user_id = p_user_id AND p.created_at >= p_editable_since))
+  FOR UPDATE;
+
+  IF NOT FOUND THEN
+    RETURN;
+  END IF;
+
+  -- Keep the previous comment
+  INSERT INTO post_revision (
+    created_at,
+    post_id,
+    comment
+  )
+  SELECT
+    COALESCE(v_post.edited_at, v_post.created_at), -- created_at
+    v_post.id, -- post_id
+    v_post.comment -- comment
+  ;
+
+  UPDATE post AS p
+  SET comment = p_comment, edited_at = now()
+  WHERE p.id = v_post.id
+  RETURNING * INTO v_post;
+
+  -- Quote links may have changed, so replies will be recreated from the new comment
+  DELETE FROM post_reply AS r
+  WHERE r.post_id = v_post.id;
+
+  RETURN QUERY
+  SELECT post_data(v_post) AS post, i AS image
+  FROM (SELECT) AS v
+  LEFT JOIN image AS i ON i.post_id = v_post.id;
+END;
+$BODY$;
+
+-- Create get_posts function
+CREATE FUNCTION get_posts(IN p_room_id integer, IN p_before_id bigint, IN p_count integer)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT post_data(p) AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  WHERE p.room_id = p_room_id AND NOT p.is_deleted AND (p_before_id IS NULL OR p.id < p_before_id)
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
+
+-- Create get_recent_posts function
+CREATE FUNCTION get_recent_posts(IN p_room_id integer, IN p_count integer)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT post_data(p) AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  WHERE p.room_id = p_room_id AND NOT p.is_deleted
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
+
+-- Create import_post function
+CREATE FUNCTION import_post(
+  IN p_room_id integer,
+  IN p_post new_post,
+  IN p_image new_image,
+  IN p_created_at timestamp with time zone
+)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+  v_image image;
+BEGIN
+  -- Insert post, preserving its original timestamp
+  INSERT INTO post (
+    created_at,
+    updated_at,
+    room_id,
+    name,
+    comment,
+    ip,
+    user_id,
+    admin
+  )
+  SELECT
+    p_created_at, -- created_at
+    p_created_at, -- updated_at
+    p_room_id, -- room_id
+    p_post.name, -- name
+    p_post.comment, -- comment
+    p_post.ip, -- ip
+    p_post.user_id, -- user_id
+    p_post.admin -- admin
+  RETURNING * INTO v_post;
+
+  -- If an image is provided, insert it
+  IF NOT p_image IS NULL THEN
+    INSERT INTO image (
+      post_id,
+      created_at,
+      updated_at,
+      filename,
+      hash,
+      ext,
+      tn_ext
+    )
+    SELECT
+      v_post.id, -- post_id
+      p_created_at, -- created_at
+      p_created_at, -- updated_at
+      p_image.filename, -- filename
+      p_image.hash, -- hash
+      p_image.ext, -- ext
+      p_image.tn_ext -- tn_ext
+    RETURNING * INTO v_image;
+  END IF;
+
+  RETURN QUERY SELECT post_data(v_post) AS post, v_image AS image;
+END;
+$BODY$;
+
+-- Create search_posts function
+CREATE FUNCTION search_posts(
+  IN p_room_id integer,
+  IN p_query text,
+  IN p_from timestamp with time zone,
+  IN p_to timestamp with time zone,
+  IN p_has_image boolean,
+  IN p_admin boolean,
+  IN p_user_id bigint,
+  IN p_before_id bigint,
+  IN p_count integer
+)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT post_data(p) AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  WHERE p.room_id = p_room_id
+    AND NOT p.is_deleted
+    AND (p_query IS NULL OR p.search_vector @@ websearch_to_tsquery('simple', p_query))
+    AND (p_from IS NULL OR p.created_at >= p_from)
+    AND (p_to IS NULL OR p.created_at < p_to)
+    AND (p_has_image IS NULL OR (i.id IS NOT NULL) = p_has_image)
+    AND (p_admin IS NULL OR p.admin = p_admin)
+    AND (p_user_id IS NULL OR p.user_id = p_user_id)
+    AND (p_before_id IS NULL OR p.id < p_before_id)
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
@@ -0,0 +1,22 @@
+CREATE FUNCTION add_post_reaction(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_reaction text
+)
+RETURNS bigint
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE room_id = p_room_id AND id = p_post_id AND NOT is_deleted) THEN
+    RETURN NULL;
+  END IF;
+
+  INSERT INTO post_reaction (post_id, reaction, user_id)
+  VALUES (p_post_id, p_reaction, p_user_id)
+  ON CONFLICT DO NOTHING;
+
+  RETURN (SELECT count(*) FROM post_reaction WHERE post_id = p_post_id AND reaction = p_reaction);
+END;
+$BODY$;
@@ -0,0 +1,29 @@
+CREATE FUNCTION add_queue_item(
+  IN p_room_id integer,
+  IN p_content json
+)
+RETURNS SETOF queue_item
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_position integer;
+BEGIN
+  -- Append to the end of the queue
+  SELECT COALESCE(MAX(position) + 1, 0) INTO v_position
+  FROM queue_item
+  WHERE room_id = p_room_id;
+
+  RETURN QUERY
+  INSERT INTO queue_item (
+    room_id,
+    position,
+    content
+  )
+  SELECT
+    p_room_id, -- room_id
+    v_position, -- position
+    p_content -- content
+  RETURNING *;
+END;
+$BODY$;
@@ -0,0 +1,10 @@
+CREATE FUNCTION clear_queue(IN p_room_id integer)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM queue_item
+  WHERE room_id = p_room_id;
+END;
+$BODY$;
@@ -0,0 +1,28 @@
+CREATE FUNCTION create_moderator(
+  IN p_room_id integer,
+  IN p_name text,
+  IN p_key text,
+  IN p_permissions text[]
+)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  INSERT INTO moderator (
+    room_id,
+    name,
+    key,
+    permissions
+  )
+  VALUES (
+    p_room_id,
+    p_name,
+    p_key,
+    p_permissions
+  )
+  ON CONFLICT (room_id, name) DO NOTHING
+  RETURNING *;
+END;
+$BODY$;
@@ -3,7 +3,7 @@ CREATE FUNCTION create_post(
   IN p_post new_post,
   IN p_image new_image
 )
-RETURNS TABLE (post post, image image)
+RETURNS TABLE (post post_data, image image)
 LANGUAGE plpgsql
 
 AS $BODY$
@@ -47,6 +47,6 @@ BEGIN
     RETURNING * INTO v_image;
   END IF;
 
-  RETURN QUERY SELECT v_post AS post, v_image AS image;
+  RETURN QUERY SELECT post_data(v_post) AS post, v_image AS image;
 END;
 $BODY$;
@@ -0,0 +1,29 @@
+CREATE FUNCTION create_post_replies(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_reply_to_ids bigint[]
+)
+RETURNS TABLE (post_id bigint, reply_to_id bigint, reply_to_user_id bigint)
+LANGUAGE sql
+
+AS $BODY$
+WITH reply_to AS (
+  SELECT t.id, t.user_id
+  FROM post AS t
+  WHERE t.room_id = p_room_id
+    AND t.id = ANY(p_reply_to_ids)
+    AND t.id <> p_post_id
+    AND NOT t.is_deleted
+    AND EXISTS (SELECT 1 FROM post AS p WHERE p.id = p_post_id AND p.room_id = p_room_id)
+), inserted AS (
+  INSERT INTO post_reply (post_id, reply_to_id)
+  SELECT p_post_id, r.id
+  FROM reply_to AS r
+  ON CONFLICT DO NOTHING
+  RETURNING post_reply.reply_to_id
+)
+SELECT p_post_id, r.id, r.user_id
+FROM reply_to AS r
+JOIN inserted AS i ON i.reply_to_id = r.id
+ORDER BY r.id;
+$BODY$;
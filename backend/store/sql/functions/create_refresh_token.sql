@@ -1,5 +1,6 @@
 CREATE FUNCTION create_refresh_token(
-  IN p_claims text
+  IN p_claims text,
+  IN p_room_id integer
 )
 RETURNS uuid
 LANGUAGE plpgsql
@@ -9,8 +10,8 @@ DECLARE
   v_new_token uuid;
 BEGIN
   -- Generate new refresh token with new family
-  INSERT INTO refresh_token (family, claims)
-  VALUES (nextval('refresh_token_family_seq'), p_claims)
+  INSERT INTO refresh_token (family, claims, room_id)
+  VALUES (nextval('refresh_token_family_seq'), p_claims, p_room_id)
   RETURNING token INTO v_new_token;
 
   -- Return new token
@@ -1,6 +1,7 @@
 CREATE FUNCTION create_room(
   IN p_name text,
-  IN p_password text
+  IN p_password text,
+  IN p_expires_at timestamp with time zone
 )
 RETURNS SETOF room
 LANGUAGE plpgsql
@@ -14,18 +15,22 @@ BEGIN
     RAISE EXCEPTION 'Room already exists.';
   END IF;
 
-  -- Insert post
+  -- Free up the name if it belongs to an expired room
+  DELETE FROM room
+  WHERE name = p_name AND expires_at <= CURRENT_TIMESTAMP;
+
+  -- Insert room
   RETURN QUERY
   INSERT INTO room (
     name,
-		claimed_at,
-		expires_at,
+    claimed_at,
+    expires_at,
     password
   )
   SELECT
     p_name, -- name
-		CURRENT_TIMESTAMP, -- claimed_at
-		to_timestamp('9999-12-31', 'YYYY-MM-DD'), -- expires_at
+    CURRENT_TIMESTAMP, -- claimed_at
+    COALESCE(p_expires_at, to_timestamp('9999-12-31', 'YYYY-MM-DD')), -- expires_at
     p_password -- password
   RETURNING *;
 END;
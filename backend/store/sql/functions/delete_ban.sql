@@ -0,0 +1,18 @@
+CREATE FUNCTION delete_ban(
+  IN p_room_id integer,
+  IN p_ban_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_id integer;
+BEGIN
+  DELETE FROM ban
+  WHERE room_id = p_room_id AND id = p_ban_id
+  RETURNING id INTO v_deleted_id;
+
+  RETURN v_deleted_id IS NOT NULL;
+END;
+$BODY$;
@@ -0,0 +1,12 @@
+CREATE FUNCTION delete_expired_rooms()
+RETURNS SETOF integer
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  DELETE FROM room
+  WHERE expires_at <= CURRENT_TIMESTAMP
+  RETURNING id;
+END;
+$BODY$;
@@ -0,0 +1,28 @@
+CREATE FUNCTION delete_moderator(
+  IN p_room_id integer,
+  IN p_moderator_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_id integer;
+BEGIN
+  DELETE FROM moderator
+  WHERE room_id = p_room_id AND id = p_moderator_id
+  RETURNING id INTO v_deleted_id;
+
+  IF v_deleted_id IS NULL THEN
+    RETURN false;
+  END IF;
+
+  -- Revoke all sessions belonging to the moderator
+  DELETE FROM refresh_token
+  WHERE room_id = p_room_id
+    AND claims::json->>'level' = 'moderator'
+    AND (claims::json->>'moderator_id')::integer = p_moderator_id;
+
+  RETURN true;
+END;
+$BODY$;
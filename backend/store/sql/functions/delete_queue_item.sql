@@ -0,0 +1,27 @@
+CREATE FUNCTION delete_queue_item(
+  IN p_room_id integer,
+  IN p_item_id bigint
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_position integer;
+BEGIN
+  DELETE FROM queue_item
+  WHERE room_id = p_room_id AND id = p_item_id
+  RETURNING position INTO v_position;
+
+  IF v_position IS NULL THEN
+    RETURN false;
+  END IF;
+
+  -- Close the gap left by the deleted item
+  UPDATE queue_item
+  SET position = position - 1
+  WHERE room_id = p_room_id AND position > v_position;
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,17 @@
+CREATE FUNCTION delete_room(
+  IN p_room_id integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_id integer;
+BEGIN
+  DELETE FROM room
+  WHERE id = p_room_id
+  RETURNING id INTO v_deleted_id;
+
+  RETURN v_deleted_id IS NOT NULL;
+END;
+$BODY$;
@@ -0,0 +1,54 @@
+CREATE FUNCTION edit_post(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_is_admin boolean,
+  IN p_comment text,
+  IN p_editable_since timestamp with time zone
+)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_post post;
+BEGIN
+  SELECT * INTO v_post
+  FROM post AS p
+  WHERE p.room_id = p_room_id
+    AND p.id = p_post_id
+    AND NOT p.is_deleted
+    AND (p_is_admin OR (p.user_id = p_user_id AND p.created_at >= p_editable_since))
+  FOR UPDATE;
+
+  IF NOT FOUND THEN
+    RETURN;
+  END IF;
+
+  -- Keep the previous comment
+  INSERT INTO post_revision (
+    created_at,
+    post_id,
+    comment
+  )
+  SELECT
+    COALESCE(v_post.edited_at, v_post.created_at), -- created_at
+    v_post.id, -- post_id
+    v_post.comment -- comment
+  ;
+
+  UPDATE post AS p
+  SET comment = p_comment, edited_at = now()
+  WHERE p.id = v_post.id
+  RETURNING * INTO v_post;
+
+  -- Quote links may have changed, so replies will be recreated from the new comment
+  DELETE FROM post_reply AS r
+  WHERE r.post_id = v_post.id;
+
+  RETURN QUERY
+  SELECT post_data(v_post) AS post, i AS image
+  FROM (SELECT) AS v
+  LEFT JOIN image AS i ON i.post_id = v_post.id;
+END;
+$BODY$;
@@ -0,0 +1,14 @@
+CREATE FUNCTION extend_room_expiry(
+  IN p_room_id integer,
+  IN p_expires_at timestamp with time zone
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET expires_at = p_expires_at
+  WHERE id = p_room_id AND expires_at > CURRENT_TIMESTAMP;
+END;
+$BODY$;
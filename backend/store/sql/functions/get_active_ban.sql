@@ -0,0 +1,20 @@
+CREATE FUNCTION get_active_ban(
+  IN p_room_id integer,
+  IN p_user_id bigint,
+  IN p_ip inet
+)
+RETURNS SETOF ban
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT b.*
+  FROM ban AS b
+  WHERE b.room_id = p_room_id
+    AND (b.user_id = p_user_id OR b.ip = p_ip)
+    AND (b.expires_at IS NULL OR b.expires_at > CURRENT_TIMESTAMP)
+  ORDER BY b.expires_at DESC NULLS FIRST
+  LIMIT 1;
+END;
+$BODY$;
@@ -0,0 +1,14 @@
+CREATE FUNCTION get_bans(IN p_room_id integer)
+RETURNS SETOF ban
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT b.*
+  FROM ban AS b
+  WHERE b.room_id = p_room_id
+    AND (b.expires_at IS NULL OR b.expires_at > CURRENT_TIMESTAMP)
+  ORDER BY b.id DESC;
+END;
+$BODY$;
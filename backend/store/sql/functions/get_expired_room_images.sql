@@ -0,0 +1,12 @@
+CREATE FUNCTION get_expired_room_images()
+RETURNS SETOF image
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT i.*
+FROM image AS i
+INNER JOIN post AS p ON p.id = i.post_id
+INNER JOIN room AS r ON r.id = p.room_id
+WHERE r.expires_at <= CURRENT_TIMESTAMP;
+$BODY$;
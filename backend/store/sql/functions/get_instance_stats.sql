@@ -0,0 +1,19 @@
+CREATE FUNCTION get_instance_stats()
+RETURNS TABLE (
+  room_count bigint,
+  post_count bigint,
+  image_count bigint,
+  emote_count bigint
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    (SELECT COUNT(*) FROM room) AS room_count,
+    (SELECT COUNT(*) FROM post WHERE NOT is_deleted) AS post_count,
+    (SELECT COUNT(*) FROM image AS i JOIN post AS p ON p.id = i.post_id WHERE NOT p.is_deleted) AS image_count,
+    (SELECT COUNT(*) FROM emote) AS emote_count;
+END;
+$BODY$;
@@ -0,0 +1,15 @@
+CREATE FUNCTION get_moderator(
+  IN p_room_id integer,
+  IN p_moderator_id integer
+)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT m.*
+  FROM moderator AS m
+  WHERE m.room_id = p_room_id AND m.id = p_moderator_id;
+END;
+$BODY$;
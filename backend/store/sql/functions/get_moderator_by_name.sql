@@ -0,0 +1,15 @@
+CREATE FUNCTION get_moderator_by_name(
+  IN p_room_id integer,
+  IN p_name text
+)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT m.*
+  FROM moderator AS m
+  WHERE m.room_id = p_room_id AND m.name = p_name;
+END;
+$BODY$;
@@ -0,0 +1,13 @@
+CREATE FUNCTION get_moderators(IN p_room_id integer)
+RETURNS SETOF moderator
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT m.*
+  FROM moderator AS m
+  WHERE m.room_id = p_room_id
+  ORDER BY m.id ASC;
+END;
+$BODY$;
@@ -0,0 +1,13 @@
+CREATE FUNCTION get_post_reactions(IN p_post_ids bigint[])
+RETURNS TABLE (post_id bigint, reaction text, user_id bigint)
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT r.post_id, r.reaction, r.user_id
+FROM post_reaction AS r
+JOIN post AS p ON p.id = r.post_id
+WHERE r.post_id = ANY(p_post_ids)
+  AND NOT p.is_deleted
+ORDER BY r.post_id, r.created_at, r.user_id;
+$BODY$;
@@ -0,0 +1,15 @@
+CREATE FUNCTION get_post_replies(IN p_post_ids bigint[])
+RETURNS TABLE (post_id bigint, reply_to_id bigint, reply_to_user_id bigint)
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT r.post_id, r.reply_to_id, t.user_id
+FROM post_reply AS r
+JOIN post AS p ON p.id = r.post_id
+JOIN post AS t ON t.id = r.reply_to_id
+WHERE (r.post_id = ANY(p_post_ids) OR r.reply_to_id = ANY(p_post_ids))
+  AND NOT p.is_deleted
+  AND NOT t.is_deleted
+ORDER BY r.post_id, r.reply_to_id;
+$BODY$;
@@ -0,0 +1,12 @@
+CREATE FUNCTION get_post_revisions(IN p_room_id integer, IN p_post_id bigint, IN p_user_id bigint, IN p_is_admin boolean)
+RETURNS SETOF post_revision
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT r.*
+FROM post_revision AS r
+INNER JOIN post AS p ON p.id = r.post_id
+WHERE p.room_id = p_room_id AND r.post_id = p_post_id AND NOT p.is_deleted AND (p_is_admin OR p.user_id = p_user_id)
+ORDER BY r.id;
+$BODY$;
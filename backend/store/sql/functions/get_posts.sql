@@ -0,0 +1,15 @@
+CREATE FUNCTION get_posts(IN p_room_id integer, IN p_before_id bigint, IN p_count integer)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT post_data(p) AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  WHERE p.room_id = p_room_id AND NOT p.is_deleted AND (p_before_id IS NULL OR p.id < p_before_id)
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
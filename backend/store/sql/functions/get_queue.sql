@@ -0,0 +1,13 @@
+CREATE FUNCTION get_queue(IN p_room_id integer)
+RETURNS SETOF queue_item
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT q.*
+  FROM queue_item AS q
+  WHERE q.room_id = p_room_id
+  ORDER BY q.position ASC, q.id ASC;
+END;
+$BODY$;
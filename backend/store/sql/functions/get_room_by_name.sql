@@ -3,5 +3,5 @@ RETURNS SETOF room
 LANGUAGE sql
 
 AS $BODY$
-SELECT * FROM room WHERE name = p_room_name LIMIT 1;
+SELECT * FROM room WHERE name = p_room_name AND expires_at > CURRENT_TIMESTAMP LIMIT 1;
 $BODY$;
@@ -0,0 +1,11 @@
+CREATE FUNCTION get_room_images(IN p_room_id integer)
+RETURNS SETOF image
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT i.*
+FROM image AS i
+INNER JOIN post AS p ON p.id = i.post_id
+WHERE p.room_id = p_room_id;
+$BODY$;
@@ -0,0 +1,24 @@
+CREATE FUNCTION get_room_sessions(IN p_room_id integer)
+RETURNS TABLE (
+  family bigint,
+  created_at timestamp with time zone,
+  refreshed_at timestamp with time zone,
+  expires_at timestamp with time zone,
+  claims text
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    t.family,
+    (SELECT MIN(f.created_at) FROM refresh_token AS f WHERE f.family = t.family), -- created_at
+    t.created_at, -- refreshed_at
+    t.expires_at,
+    t.claims
+  FROM refresh_token AS t
+  WHERE t.room_id = p_room_id AND NOT t.used AND t.expires_at >= CURRENT_TIMESTAMP
+  ORDER BY t.created_at DESC;
+END;
+$BODY$;
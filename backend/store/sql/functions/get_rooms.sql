@@ -0,0 +1,25 @@
+CREATE FUNCTION get_rooms()
+RETURNS TABLE (
+  id integer,
+  name text,
+  created_at timestamp with time zone,
+  post_count bigint,
+  last_post_at timestamp with time zone
+)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT
+    r.id,
+    r.name,
+    r.created_at,
+    COUNT(p.id) AS post_count,
+    MAX(p.created_at) AS last_post_at
+  FROM room AS r
+  LEFT JOIN post AS p ON p.room_id = r.id AND NOT p.is_deleted
+  GROUP BY r.id
+  ORDER BY r.id ASC;
+END;
+$BODY$;
@@ -0,0 +1,10 @@
+CREATE FUNCTION get_used_image_hashes(IN p_hashes text[])
+RETURNS SETOF text
+LANGUAGE sql
+STABLE
+
+AS $BODY$
+SELECT DISTINCT hash
+FROM image
+WHERE hash = ANY(p_hashes);
+$BODY$;
@@ -0,0 +1,45 @@
+CREATE FUNCTION move_queue_item(
+  IN p_room_id integer,
+  IN p_item_id bigint,
+  IN p_position integer
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_old_position integer;
+  v_max_position integer;
+  v_new_position integer;
+BEGIN
+  SELECT position INTO v_old_position
+  FROM queue_item
+  WHERE room_id = p_room_id AND id = p_item_id;
+
+  IF v_old_position IS NULL THEN
+    RETURN false;
+  END IF;
+
+  SELECT MAX(position) INTO v_max_position
+  FROM queue_item
+  WHERE room_id = p_room_id;
+
+  v_new_position := GREATEST(0, LEAST(p_position, v_max_position));
+
+  IF v_new_position > v_old_position THEN
+    UPDATE queue_item
+    SET position = position - 1
+    WHERE room_id = p_room_id AND position > v_old_position AND position <= v_new_position;
+  ELSIF v_new_position < v_old_position THEN
+    UPDATE queue_item
+    SET position = position + 1
+    WHERE room_id = p_room_id AND position >= v_new_position AND position < v_old_position;
+  END IF;
+
+  UPDATE queue_item
+  SET position = v_new_position
+  WHERE id = p_item_id;
+
+  RETURN true;
+END;
+$BODY$;
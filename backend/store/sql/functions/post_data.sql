@@ -0,0 +1,19 @@
+CREATE FUNCTION post_data(IN p_post post)
+RETURNS post_data
+LANGUAGE sql
+IMMUTABLE
+
+AS $BODY$
+SELECT
+  p_post.id,
+  p_post.created_at,
+  p_post.updated_at,
+  p_post.room_id,
+  p_post.name,
+  p_post.comment,
+  p_post.ip,
+  p_post.is_deleted,
+  p_post.user_id,
+  p_post.admin,
+  p_post.edited_at;
+$BODY$;
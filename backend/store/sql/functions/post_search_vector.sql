@@ -0,0 +1,8 @@
+CREATE FUNCTION post_search_vector(IN p_name text, IN p_comment text)
+RETURNS tsvector
+LANGUAGE sql
+IMMUTABLE
+
+AS $BODY$
+SELECT to_tsvector('simple', COALESCE(p_name, '') || ' ' || COALESCE(p_comment, ''));
+$BODY$;
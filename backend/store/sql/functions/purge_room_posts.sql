@@ -0,0 +1,15 @@
+CREATE FUNCTION purge_room_posts(
+  IN p_room_id integer
+)
+RETURNS SETOF bigint
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  UPDATE post
+  SET is_deleted = true
+  WHERE room_id = p_room_id AND NOT is_deleted
+  RETURNING id;
+END;
+$BODY$;
@@ -11,17 +11,19 @@ DECLARE
 BEGIN
   SELECT * INTO v_refresh_token
   FROM refresh_token
-  WHERE token = p_token;
+  WHERE token = p_token
+  FOR UPDATE;
 
   -- Check if exists
   IF v_refresh_token IS NULL THEN
     RETURN NULL;
   END IF;
 
-  -- Check if already used
+  -- Check if already used.
+  -- If a token that has already been rotated is presented again,
+  -- it has likely been stolen, so revoke the whole family.
   IF v_refresh_token.used THEN
-    UPDATE refresh_token
-    SET used = true
+    DELETE FROM refresh_token
     WHERE family = v_refresh_token.family;
 
     RETURN NULL;
@@ -38,8 +40,8 @@ BEGIN
   WHERE id = v_refresh_token.id;
 
   -- Generate new refresh token
-  INSERT INTO refresh_token (family, claims)
-  VALUES (v_refresh_token.family, v_refresh_token.claims)
+  INSERT INTO refresh_token (family, claims, room_id)
+  VALUES (v_refresh_token.family, v_refresh_token.claims, v_refresh_token.room_id)
   RETURNING token, claims INTO v_result;
 
   -- Return result
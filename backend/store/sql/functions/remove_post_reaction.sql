@@ -0,0 +1,25 @@
+CREATE FUNCTION remove_post_reaction(
+  IN p_room_id integer,
+  IN p_post_id bigint,
+  IN p_user_id bigint,
+  IN p_reaction text
+)
+RETURNS bigint
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  IF NOT EXISTS (SELECT 1 FROM post WHERE room_id = p_room_id AND id = p_post_id AND NOT is_deleted) THEN
+    RETURN NULL;
+  END IF;
+
+  DELETE FROM post_reaction
+  WHERE post_id = p_post_id AND reaction = p_reaction AND user_id = p_user_id;
+
+  IF NOT FOUND THEN
+    RETURN NULL;
+  END IF;
+
+  RETURN (SELECT count(*) FROM post_reaction WHERE post_id = p_post_id AND reaction = p_reaction);
+END;
+$BODY$;
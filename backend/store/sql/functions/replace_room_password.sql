@@ -0,0 +1,17 @@
+CREATE FUNCTION replace_room_password(
+  IN p_room_id integer,
+  IN p_old_password text,
+  IN p_new_password text
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET password = p_new_password
+  WHERE id = p_room_id AND password = p_old_password;
+
+  RETURN FOUND;
+END;
+$BODY$;
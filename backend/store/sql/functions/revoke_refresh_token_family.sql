@@ -0,0 +1,24 @@
+CREATE FUNCTION revoke_refresh_token_family(
+  IN p_token uuid
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_family bigint;
+BEGIN
+  SELECT family INTO v_family
+  FROM refresh_token
+  WHERE token = p_token;
+
+  IF v_family IS NULL THEN
+    RETURN false;
+  END IF;
+
+  DELETE FROM refresh_token
+  WHERE family = v_family;
+
+  RETURN true;
+END;
+$BODY$;
@@ -0,0 +1,12 @@
+CREATE FUNCTION revoke_room_refresh_tokens(
+  IN p_room_id integer
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  DELETE FROM refresh_token
+  WHERE room_id = p_room_id;
+END;
+$BODY$;
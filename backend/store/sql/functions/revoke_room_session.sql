@@ -0,0 +1,19 @@
+CREATE FUNCTION revoke_room_session(
+  IN p_room_id integer,
+  IN p_family bigint
+)
+RETURNS boolean
+LANGUAGE plpgsql
+
+AS $BODY$
+DECLARE
+  v_deleted_count integer;
+BEGIN
+  DELETE FROM refresh_token
+  WHERE room_id = p_room_id AND family = p_family;
+
+  GET DIAGNOSTICS v_deleted_count = ROW_COUNT;
+
+  RETURN v_deleted_count > 0;
+END;
+$BODY$;
@@ -0,0 +1,33 @@
+CREATE FUNCTION search_posts(
+  IN p_room_id integer,
+  IN p_query text,
+  IN p_from timestamp with time zone,
+  IN p_to timestamp with time zone,
+  IN p_has_image boolean,
+  IN p_admin boolean,
+  IN p_user_id bigint,
+  IN p_before_id bigint,
+  IN p_count integer
+)
+RETURNS TABLE (post post_data, image image)
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  RETURN QUERY
+  SELECT post_data(p) AS post, i AS image
+  FROM post AS p
+  LEFT JOIN image AS i ON i.post_id = p.id
+  WHERE p.room_id = p_room_id
+    AND NOT p.is_deleted
+    AND (p_query IS NULL OR p.search_vector @@ websearch_to_tsquery('simple', p_query))
+    AND (p_from IS NULL OR p.created_at >= p_from)
+    AND (p_to IS NULL OR p.created_at < p_to)
+    AND (p_has_image IS NULL OR (i.id IS NOT NULL) = p_has_image)
+    AND (p_admin IS NULL OR p.admin = p_admin)
+    AND (p_user_id IS NULL OR p.user_id = p_user_id)
+    AND (p_before_id IS NULL OR p.id < p_before_id)
+  ORDER BY p.id DESC
+  LIMIT p_count;
+END;
+$BODY$;
@@ -0,0 +1,14 @@
+CREATE FUNCTION set_room_end_behavior(
+  IN p_room_id integer,
+  IN p_end_behavior text
+)
+RETURNS VOID
+LANGUAGE plpgsql
+
+AS $BODY$
+BEGIN
+  UPDATE room
+  SET end_behavior = p_end_behavior
+  WHERE id = p_room_id;
+END;
+$BODY$;
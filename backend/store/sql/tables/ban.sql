@@ -0,0 +1,28 @@
+CREATE TABLE ban
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  room_id integer NOT NULL,
+  post_id bigint,
+  user_id bigint,
+  ip inet,
+  reason text,
+  expires_at timestamp with time zone,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  CHECK (user_id IS NOT NULL OR ip IS NOT NULL)
+);
+
+SELECT manage_updated_at('ban'); -- Automatically manage updated_at
+
+CREATE INDEX ban_room_id_idx ON ban
+  USING btree
+  (room_id ASC NULLS LAST);
@@ -0,0 +1,22 @@
+CREATE TABLE moderator
+(
+  id serial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  room_id integer NOT NULL,
+  name text NOT NULL,
+  key text NOT NULL,
+  permissions text[] NOT NULL,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
+  UNIQUE (room_id, name)
+);
+
+SELECT manage_updated_at('moderator'); -- Automatically manage updated_at
@@ -10,6 +10,8 @@ CREATE TABLE post
   is_deleted boolean NOT NULL DEFAULT false,
   user_id bigint NOT NULL,
   admin boolean NOT NULL DEFAULT false,
+  edited_at timestamp with time zone,
+  search_vector tsvector NOT NULL GENERATED ALWAYS AS (post_search_vector(name, comment)) STORED,
 
   PRIMARY KEY (id),
 
@@ -25,3 +27,7 @@ SELECT manage_updated_at('post'); -- Automatically manage updated_at
 CREATE INDEX post_room_id_idx ON post
   USING btree
   (room_id ASC NULLS LAST);
+
+CREATE INDEX post_search_vector_idx ON post
+  USING gin
+  (search_vector);
@@ -0,0 +1,13 @@
+CREATE TABLE post_reaction
+(
+  post_id bigint NOT NULL,
+  reaction text NOT NULL,
+  user_id bigint NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT now(),
+  PRIMARY KEY (post_id, reaction, user_id),
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
@@ -0,0 +1,20 @@
+CREATE TABLE post_reply
+(
+  post_id bigint NOT NULL,
+  reply_to_id bigint NOT NULL,
+  PRIMARY KEY (post_id, reply_to_id),
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+  FOREIGN KEY (reply_to_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+CREATE INDEX post_reply_reply_to_id_idx ON post_reply
+  USING btree
+  (reply_to_id);
@@ -0,0 +1,19 @@
+CREATE TABLE post_revision
+(
+  id bigserial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  post_id bigint NOT NULL,
+  comment text,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+CREATE INDEX post_revision_post_id_idx ON post_revision
+  USING btree
+  (post_id ASC NULLS LAST);
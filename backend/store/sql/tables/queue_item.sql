@@ -0,0 +1,23 @@
+CREATE TABLE queue_item
+(
+  id bigserial NOT NULL,
+  created_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  updated_at timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  room_id integer NOT NULL,
+  position integer NOT NULL,
+  content json NOT NULL,
+
+  PRIMARY KEY (id),
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID
+);
+
+SELECT manage_updated_at('queue_item'); -- Automatically manage updated_at
+
+CREATE INDEX queue_item_room_id_idx ON queue_item
+  USING btree
+  (room_id ASC NULLS LAST);
@@ -9,9 +9,16 @@ CREATE TABLE refresh_token
   used boolean NOT NULL DEFAULT false,
   expires_at timestamp with time zone NOT NULL DEFAULT (CURRENT_TIMESTAMP + '30 days'::interval),
   claims text NOT NULL,
+  room_id integer,
 
   PRIMARY KEY (id),
 
+  FOREIGN KEY (room_id)
+    REFERENCES room (id) MATCH SIMPLE
+    ON UPDATE NO ACTION
+    ON DELETE CASCADE
+    NOT VALID,
+
   UNIQUE (token)
 );
 
@@ -24,3 +31,7 @@ CREATE INDEX refresh_token_token_idx ON refresh_token
 CREATE INDEX refresh_token_family_idx ON refresh_token
   USING btree
   (family ASC NULLS LAST);
+
+CREATE INDEX refresh_token_room_id_idx ON refresh_token
+  USING btree
+  (room_id ASC NULLS LAST);
@@ -10,6 +10,7 @@ CREATE TABLE room
   password text NOT NULL,
   content json,
   playback_state json,
+  end_behavior text NOT NULL DEFAULT 'stop',
 
   PRIMARY KEY (id),
 
@@ -21,3 +22,7 @@ SELECT manage_updated_at('room'); -- Automatically manage updated_at
 CREATE INDEX room_name_idx ON room
   USING btree
   (name ASC NULLS LAST);
+
+CREATE INDEX room_expires_at_idx ON room
+  USING btree
+  (expires_at ASC NULLS LAST);
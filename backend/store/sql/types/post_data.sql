@@ -0,0 +1,13 @@
+CREATE TYPE post_data AS (
+  id bigint,
+  created_at timestamp with time zone,
+  updated_at timestamp with time zone,
+  room_id integer,
+  name text,
+  comment text,
+  ip inet,
+  is_deleted boolean,
+  user_id bigint,
+  admin boolean,
+  edited_at timestamp with time zone
+);
@@ -0,0 +1,175 @@
+-- SQLite schema, equivalent to the PostgreSQL schema in "migrations".
+-- Timestamps are stored as RFC 3339 text, IP addresses as text,
+-- UUIDs as hyphenated text and arrays as JSON.
+
+-- Create sequence table
+-- Replaces PostgreSQL sequences not tied to a table (user_id_seq, refresh_token_family_seq)
+CREATE TABLE sequence
+(
+  name text NOT NULL,
+  value integer NOT NULL,
+
+  PRIMARY KEY (name)
+);
+
+-- Create room table
+CREATE TABLE room
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+
+  name text NOT NULL,
+  claimed_at text NOT NULL,
+  expires_at text NOT NULL,
+  password text NOT NULL,
+  content text,
+  playback_state text,
+  end_behavior text NOT NULL DEFAULT 'stop',
+
+  UNIQUE (name)
+);
+
+CREATE INDEX room_expires_at_idx ON room (expires_at ASC);
+
+-- Create post table
+CREATE TABLE post
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+  room_id integer NOT NULL,
+  name text,
+  comment text,
+  ip text NOT NULL,
+  is_deleted boolean NOT NULL DEFAULT false,
+  user_id integer NOT NULL,
+  admin boolean NOT NULL DEFAULT false,
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX post_room_id_idx ON post (room_id ASC);
+
+-- Create image table
+CREATE TABLE image
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  post_id integer NOT NULL,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+  filename text NOT NULL,
+  hash text NOT NULL,
+  ext text NOT NULL,
+  tn_ext text NOT NULL,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id)
+    ON DELETE CASCADE,
+
+  UNIQUE (post_id)
+);
+
+CREATE INDEX image_hash_idx ON image (hash ASC);
+
+-- Create emote table
+CREATE TABLE emote
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+  room_id integer NOT NULL,
+  name text,
+  hash text NOT NULL,
+  ext text NOT NULL,
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id)
+    ON DELETE CASCADE,
+
+  UNIQUE (room_id, name)
+);
+
+-- Create queue_item table
+CREATE TABLE queue_item
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+  room_id integer NOT NULL,
+  position integer NOT NULL,
+  content text NOT NULL,
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX queue_item_room_id_idx ON queue_item (room_id ASC);
+
+-- Create moderator table
+CREATE TABLE moderator
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+  room_id integer NOT NULL,
+  name text NOT NULL,
+  key text NOT NULL,
+  permissions text NOT NULL,
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id)
+    ON DELETE CASCADE,
+
+  UNIQUE (room_id, name)
+);
+
+-- Create ban table
+CREATE TABLE ban
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+  room_id integer NOT NULL,
+  post_id integer,
+  user_id integer,
+  ip text,
+  reason text,
+  expires_at text,
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id)
+    ON DELETE CASCADE,
+
+  CHECK (user_id IS NOT NULL OR ip IS NOT NULL)
+);
+
+CREATE INDEX ban_room_id_idx ON ban (room_id ASC);
+
+-- Create refresh_token table
+CREATE TABLE refresh_token
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  updated_at text NOT NULL,
+
+  token text NOT NULL,
+  family integer NOT NULL,
+  used boolean NOT NULL DEFAULT false,
+  expires_at text NOT NULL,
+  claims text NOT NULL,
+  room_id integer,
+
+  FOREIGN KEY (room_id)
+    REFERENCES room (id)
+    ON DELETE CASCADE,
+
+  UNIQUE (token)
+);
+
+CREATE INDEX refresh_token_family_idx ON refresh_token (family ASC);
+
+CREATE INDEX refresh_token_room_id_idx ON refresh_token (room_id ASC);
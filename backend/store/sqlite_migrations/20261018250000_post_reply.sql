@@ -0,0 +1,18 @@
+-- Create post_reply table
+CREATE TABLE post_reply
+(
+  post_id integer NOT NULL,
+  reply_to_id integer NOT NULL,
+
+  PRIMARY KEY (post_id, reply_to_id),
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id)
+    ON DELETE CASCADE,
+
+  FOREIGN KEY (reply_to_id)
+    REFERENCES post (id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX post_reply_reply_to_id_idx ON post_reply (reply_to_id ASC);
@@ -0,0 +1,18 @@
+-- Add edited_at column to post table
+ALTER TABLE post ADD COLUMN edited_at text;
+
+-- Create post_revision table
+-- Each revision holds a previous comment of a post, and when it was posted or last edited
+CREATE TABLE post_revision
+(
+  id integer PRIMARY KEY AUTOINCREMENT,
+  created_at text NOT NULL,
+  post_id integer NOT NULL,
+  comment text,
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id)
+    ON DELETE CASCADE
+);
+
+CREATE INDEX post_revision_post_id_idx ON post_revision (post_id ASC);
@@ -0,0 +1,15 @@
+-- Create post_reaction table
+-- A reaction is either the name of one of the room's emotes or a Unicode emoji
+CREATE TABLE post_reaction
+(
+  post_id integer NOT NULL,
+  reaction text NOT NULL,
+  user_id integer NOT NULL,
+  created_at text NOT NULL,
+
+  PRIMARY KEY (post_id, reaction, user_id),
+
+  FOREIGN KEY (post_id)
+    REFERENCES post (id)
+    ON DELETE CASCADE
+);
@@ -1,4 +1,11 @@
+mod memory;
 pub mod models;
+#[cfg(feature = "sqlite")]
+mod sqlite;
 mod store;
+mod util;
 
+pub use memory::MemoryStore;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
 pub use store::*;
@@ -0,0 +1,1338 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::AriaStore;
+use crate::models::{self as dbm, RefreshRefreshTokenResult};
+use crate::util::{REFRESH_TOKEN_LIFETIME_DAYS, never_expires};
+
+/// Non-persistent store keeping all data in memory.
+/// Mirrors the semantics of the SQL functions used by [`crate::PgStore`],
+/// for use in tests and during development.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Clone, Debug)]
+struct RefreshToken {
+    id: i64,
+    created_at: DateTime<Utc>,
+    token: Uuid,
+    family: i64,
+    used: bool,
+    expires_at: DateTime<Utc>,
+    claims: String,
+    room_id: Option<i32>,
+}
+
+#[derive(Default)]
+struct Sequences {
+    room: i32,
+    post: i64,
+    post_revision: i64,
+    image: i64,
+    emote: i32,
+    queue_item: i64,
+    moderator: i32,
+    ban: i32,
+    user: i64,
+    refresh_token: i64,
+    refresh_token_family: i64,
+}
+
+#[derive(Default)]
+struct MemoryState {
+    seq: Sequences,
+    rooms: BTreeMap<i32, dbm::Room>,
+    posts: BTreeMap<i64, dbm::Post>,
+    images: BTreeMap<i64, dbm::Image>,
+    post_revisions: BTreeMap<i64, dbm::PostRevision>,
+    /// Pairs of (post ID, ID of the post it replies to)
+    post_replies: BTreeSet<(i64, i64)>,
+    /// Time each reaction was added, keyed by (post ID, reaction, user ID)
+    post_reactions: BTreeMap<(i64, String, i64), DateTime<Utc>>,
+    emotes: BTreeMap<i32, dbm::Emote>,
+    queue_items: BTreeMap<i64, dbm::QueueItem>,
+    moderators: BTreeMap<i32, dbm::Moderator>,
+    bans: BTreeMap<i32, dbm::Ban>,
+    refresh_tokens: BTreeMap<i64, RefreshToken>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, MemoryState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+fn next<T: Copy + std::ops::AddAssign + From<u8>>(seq: &mut T) -> T {
+    *seq += T::from(1);
+    *seq
+}
+
+fn is_active(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> bool {
+    expires_at.is_none_or(|v| v > now)
+}
+
+/// Split a search query into lowercase terms, all of which must be found in a post
+fn search_terms(query: Option<&str>) -> Vec<String> {
+    query
+        .map(|q| q.split_whitespace().map(|t| t.to_lowercase()).collect())
+        .unwrap_or_default()
+}
+
+fn matches_terms(terms: &[String], post: &dbm::Post) -> bool {
+    let text = format!(
+        "{} {}",
+        post.name.as_deref().unwrap_or_default(),
+        post.comment.as_deref().unwrap_or_default()
+    )
+    .to_lowercase();
+
+    terms.iter().all(|t| text.contains(t.as_str()))
+}
+
+impl MemoryState {
+    fn get_room(&self, room_id: i32) -> Option<&dbm::Room> {
+        let now = Utc::now();
+
+        self.rooms.get(&room_id).filter(|r| is_active(r.expires_at, now))
+    }
+
+    fn get_room_by_name(&self, name: &str) -> Option<&dbm::Room> {
+        let now = Utc::now();
+
+        self.rooms
+            .values()
+            .find(|r| r.name.as_deref() == Some(name) && is_active(r.expires_at, now))
+    }
+
+    fn update_room(&mut self, room_id: i32, f: impl FnOnce(&mut dbm::Room)) {
+        if let Some(room) = self.rooms.get_mut(&room_id) {
+            f(room);
+            room.updated_at = Some(Utc::now());
+        }
+    }
+
+    /// Delete a room along with everything belonging to it,
+    /// like the ON DELETE CASCADE foreign keys do
+    fn delete_room(&mut self, room_id: i32) -> bool {
+        if self.rooms.remove(&room_id).is_none() {
+            return false;
+        }
+
+        let room = Some(room_id);
+
+        self.posts.retain(|_, p| p.room_id != room);
+        let posts = &self.posts;
+        self.images
+            .retain(|_, i| i.post_id.is_some_and(|id| posts.contains_key(&id)));
+        self.post_revisions
+            .retain(|_, r| r.post_id.is_some_and(|id| posts.contains_key(&id)));
+        self.post_replies
+            .retain(|(post_id, reply_to_id)| posts.contains_key(post_id) && posts.contains_key(reply_to_id));
+        self.post_reactions
+            .retain(|(post_id, _, _), _| posts.contains_key(post_id));
+        self.emotes.retain(|_, e| e.room_id != room);
+        self.queue_items.retain(|_, q| q.room_id != room);
+        self.moderators.retain(|_, m| m.room_id != room);
+        self.bans.retain(|_, b| b.room_id != room);
+        self.refresh_tokens.retain(|_, t| t.room_id != room);
+
+        true
+    }
+
+    fn is_visible_post(&self, room_id: i32, post_id: i64) -> bool {
+        self.posts
+            .get(&post_id)
+            .is_some_and(|p| p.room_id == Some(room_id) && !p.is_deleted)
+    }
+
+    fn post_reaction_count(&self, post_id: i64, reaction: &str) -> i64 {
+        self.post_reactions
+            .keys()
+            .filter(|(id, r, _)| *id == post_id && r == reaction)
+            .count() as i64
+    }
+
+    fn post_image(&self, post_id: i64) -> Option<dbm::Image> {
+        self.images.values().find(|i| i.post_id == Some(post_id)).cloned()
+    }
+
+    fn room_queue_items(&mut self, room_id: i32) -> impl Iterator<Item = &mut dbm::QueueItem> {
+        self.queue_items
+            .values_mut()
+            .filter(move |q| q.room_id == Some(room_id))
+    }
+
+    fn delete_refresh_token_family(&mut self, family: i64) {
+        self.refresh_tokens.retain(|_, t| t.family != family);
+    }
+
+    fn insert_refresh_token(&mut self, family: i64, claims: String, room_id: Option<i32>) -> RefreshToken {
+        let now = Utc::now();
+        let id = next(&mut self.seq.refresh_token);
+
+        let token = RefreshToken {
+            id,
+            created_at: now,
+            token: Uuid::new_v4(),
+            family,
+            used: false,
+            expires_at: now + Duration::days(REFRESH_TOKEN_LIFETIME_DAYS),
+            claims,
+            room_id,
+        };
+
+        self.refresh_tokens.insert(id, token.clone());
+
+        token
+    }
+}
+
+#[async_trait]
+impl AriaStore for MemoryStore {
+    async fn migrate(&self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    async fn get_recent_posts(&self, room_id: i32, count: i32) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        self.get_posts(room_id, None, count.min(50)).await
+    }
+
+    async fn get_posts(
+        &self,
+        room_id: i32,
+        before_id: Option<i64>,
+        count: i32,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        let state = self.state();
+
+        let mut posts: Vec<dbm::PostAndImage> = state
+            .posts
+            .range(..before_id.unwrap_or(i64::MAX))
+            .rev()
+            .map(|(_, p)| p)
+            .filter(|p| p.room_id == Some(room_id) && !p.is_deleted)
+            .take(count.max(0) as usize)
+            .map(|p| dbm::PostAndImage {
+                post: p.clone(),
+                image: p.id.and_then(|id| state.post_image(id)),
+            })
+            .collect();
+
+        // Reverse posts, as they are returned in reverse order
+        posts.reverse();
+
+        Ok(posts)
+    }
+
+    async fn search_posts(
+        &self,
+        room_id: i32,
+        search: &dbm::PostSearch,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        let state = self.state();
+
+        let terms = search_terms(search.query.as_deref());
+
+        let posts = state
+            .posts
+            .range(..search.before_id.unwrap_or(i64::MAX))
+            .rev()
+            .map(|(_, p)| dbm::PostAndImage {
+                post: p.clone(),
+                image: p.id.and_then(|id| state.post_image(id)),
+            })
+            .filter(|pi| {
+                let p = &pi.post;
+
+                p.room_id == Some(room_id)
+                    && !p.is_deleted
+                    && search.from.is_none_or(|v| p.created_at.is_some_and(|c| c >= v))
+                    && search.to.is_none_or(|v| p.created_at.is_some_and(|c| c < v))
+                    && search.has_image.is_none_or(|v| pi.image.is_some() == v)
+                    && search.admin.is_none_or(|v| p.admin == v)
+                    && search.user_id.is_none_or(|v| p.user_id == Some(v))
+                    && matches_terms(&terms, p)
+            })
+            .take(search.count.max(0) as usize)
+            .collect();
+
+        Ok(posts)
+    }
+
+    async fn get_room(&self, room_id: i32) -> Result<Option<dbm::Room>, anyhow::Error> {
+        Ok(self.state().get_room(room_id).cloned())
+    }
+
+    async fn get_room_by_name(&self, name: &str) -> Result<Option<dbm::Room>, anyhow::Error> {
+        Ok(self.state().get_room_by_name(name).cloned())
+    }
+
+    async fn create_room(
+        &self,
+        name: &str,
+        password: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<dbm::Room, anyhow::Error> {
+        let mut state = self.state();
+
+        if state.get_room_by_name(name).is_some() {
+            return Err(anyhow!("Room already exists."));
+        }
+
+        // Free up the name if it belongs to an expired room
+        let expired: Vec<i32> = state
+            .rooms
+            .values()
+            .filter(|r| r.name.as_deref() == Some(name))
+            .filter_map(|r| r.id)
+            .collect();
+
+        for room_id in expired {
+            state.delete_room(room_id);
+        }
+
+        let now = Utc::now();
+        let id = next(&mut state.seq.room);
+
+        let room = dbm::Room {
+            id: Some(id),
+            created_at: Some(now),
+            updated_at: Some(now),
+            name: Some(name.to_owned()),
+            claimed_at: Some(now),
+            expires_at: Some(expires_at.unwrap_or_else(never_expires)),
+            password: Some(password.to_owned()),
+            content: None,
+            playback_state: None,
+            end_behavior: Some("stop".to_owned()),
+        };
+
+        state.rooms.insert(id, room.clone());
+
+        Ok(room)
+    }
+
+    async fn extend_room_expiry(&self, room_id: i32, expires_at: DateTime<Utc>) -> Result<(), anyhow::Error> {
+        let mut state = self.state();
+
+        if state.get_room(room_id).is_some() {
+            state.update_room(room_id, |r| r.expires_at = Some(expires_at));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_expired_rooms(&self) -> Result<Vec<i32>, anyhow::Error> {
+        let mut state = self.state();
+
+        let now = Utc::now();
+
+        let expired: Vec<i32> = state
+            .rooms
+            .values()
+            .filter(|r| !is_active(r.expires_at, now))
+            .filter_map(|r| r.id)
+            .collect();
+
+        for room_id in expired.iter() {
+            state.delete_room(*room_id);
+        }
+
+        Ok(expired)
+    }
+
+    async fn clear_room_expiry(&self) -> Result<(), anyhow::Error> {
+        let mut state = self.state();
+        let never_expires = never_expires();
+
+        let room_ids: Vec<i32> = state
+            .rooms
+            .values()
+            .filter(|r| r.expires_at.is_some_and(|e| e < never_expires))
+            .filter_map(|r| r.id)
+            .collect();
+
+        for room_id in room_ids {
+            state.update_room(room_id, |r| r.expires_at = Some(never_expires));
+        }
+
+        Ok(())
+    }
+
+    async fn set_room_password(&self, room_id: i32, password: &str) -> Result<(), anyhow::Error> {
+        self.state()
+            .update_room(room_id, |r| r.password = Some(password.to_owned()));
+
+        Ok(())
+    }
+
+    async fn replace_room_password(
+        &self,
+        room_id: i32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        let Some(room) = state
+            .rooms
+            .get_mut(&room_id)
+            .filter(|r| r.password.as_deref() == Some(old_password))
+        else {
+            return Ok(false);
+        };
+
+        room.password = Some(new_password.to_owned());
+        room.updated_at = Some(Utc::now());
+
+        Ok(true)
+    }
+
+    async fn get_rooms(&self) -> Result<Vec<dbm::RoomSummary>, anyhow::Error> {
+        let state = self.state();
+
+        let rooms = state
+            .rooms
+            .values()
+            .map(|r| {
+                let posts = state.posts.values().filter(|p| p.room_id == r.id && !p.is_deleted);
+
+                let (post_count, last_post_at) =
+                    posts.fold((0, None), |(count, last), p| (count + 1, last.max(p.created_at)));
+
+                dbm::RoomSummary {
+                    id: r.id,
+                    name: r.name.clone(),
+                    created_at: r.created_at,
+                    post_count: Some(post_count),
+                    last_post_at,
+                }
+            })
+            .collect();
+
+        Ok(rooms)
+    }
+
+    async fn delete_room(&self, room_id: i32) -> Result<bool, anyhow::Error> {
+        Ok(self.state().delete_room(room_id))
+    }
+
+    async fn purge_room_posts(&self, room_id: i32) -> Result<Vec<i64>, anyhow::Error> {
+        let mut state = self.state();
+
+        let now = Utc::now();
+
+        let post_ids = state
+            .posts
+            .values_mut()
+            .filter(|p| p.room_id == Some(room_id) && !p.is_deleted)
+            .filter_map(|p| {
+                p.is_deleted = true;
+                p.updated_at = Some(now);
+                p.id
+            })
+            .collect();
+
+        Ok(post_ids)
+    }
+
+    async fn get_room_images(&self, room_id: i32) -> Result<Vec<dbm::Image>, anyhow::Error> {
+        let state = self.state();
+
+        let images = state
+            .images
+            .values()
+            .filter(|i| {
+                i.post_id
+                    .and_then(|id| state.posts.get(&id))
+                    .is_some_and(|p| p.room_id == Some(room_id))
+            })
+            .cloned()
+            .collect();
+
+        Ok(images)
+    }
+
+    async fn get_expired_room_images(&self) -> Result<Vec<dbm::Image>, anyhow::Error> {
+        let state = self.state();
+
+        let now = Utc::now();
+
+        let images = state
+            .images
+            .values()
+            .filter(|i| {
+                i.post_id
+                    .and_then(|id| state.posts.get(&id))
+                    .and_then(|p| p.room_id)
+                    .and_then(|room_id| state.rooms.get(&room_id))
+                    .is_some_and(|r| !is_active(r.expires_at, now))
+            })
+            .cloned()
+            .collect();
+
+        Ok(images)
+    }
+
+    async fn get_used_image_hashes(&self, hashes: &[String]) -> Result<Vec<String>, anyhow::Error> {
+        let state = self.state();
+
+        let used: BTreeSet<String> = state
+            .images
+            .values()
+            .filter_map(|i| i.hash.as_ref())
+            .filter(|hash| hashes.contains(hash))
+            .cloned()
+            .collect();
+
+        Ok(used.into_iter().collect())
+    }
+
+    async fn get_instance_stats(&self) -> Result<dbm::InstanceStats, anyhow::Error> {
+        let state = self.state();
+
+        let is_live_post = |post_id: Option<i64>| {
+            post_id
+                .and_then(|id| state.posts.get(&id))
+                .is_some_and(|p| !p.is_deleted)
+        };
+
+        Ok(dbm::InstanceStats {
+            room_count: Some(state.rooms.len() as i64),
+            post_count: Some(state.posts.values().filter(|p| !p.is_deleted).count() as i64),
+            image_count: Some(state.images.values().filter(|i| is_live_post(i.post_id)).count() as i64),
+            emote_count: Some(state.emotes.len() as i64),
+        })
+    }
+
+    async fn create_post(
+        &self,
+        room_id: i32,
+        post: &dbm::NewPost,
+        image: Option<&dbm::NewImage>,
+    ) -> Result<dbm::PostAndImage, anyhow::Error> {
+        self.import_post(room_id, post, image, Utc::now()).await
+    }
+
+    async fn import_post(
+        &self,
+        room_id: i32,
+        post: &dbm::NewPost,
+        image: Option<&dbm::NewImage>,
+        created_at: DateTime<Utc>,
+    ) -> Result<dbm::PostAndImage, anyhow::Error> {
+        let mut state = self.state();
+
+        if !state.rooms.contains_key(&room_id) {
+            return Err(anyhow!("Room does not exist."));
+        }
+
+        let now = created_at;
+        let post_id = next(&mut state.seq.post);
+
+        let post = dbm::Post {
+            id: Some(post_id),
+            created_at: Some(now),
+            updated_at: Some(now),
+            room_id: Some(room_id),
+            name: post.name.clone(),
+            comment: post.comment.clone(),
+            ip: post.ip,
+            is_deleted: false,
+            user_id: Some(post.user_id),
+            admin: post.admin,
+            edited_at: None,
+        };
+
+        state.posts.insert(post_id, post.clone());
+
+        // If an image is provided, insert it
+        let image = image.map(|image| {
+            let image_id = next(&mut state.seq.image);
+
+            let image = dbm::Image {
+                id: Some(image_id),
+                post_id: Some(post_id),
+                created_at: Some(now),
+                updated_at: Some(now),
+                filename: image.filename.clone(),
+                hash: image.hash.clone(),
+                ext: image.ext.clone(),
+                tn_ext: image.tn_ext.clone(),
+            };
+
+            state.images.insert(image_id, image.clone());
+
+            image
+        });
+
+        Ok(dbm::PostAndImage { post, image })
+    }
+
+    async fn delete_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        let Some(post) = state
+            .posts
+            .get_mut(&post_id)
+            .filter(|p| p.room_id == Some(room_id) && !p.is_deleted)
+            .filter(|p| is_admin || p.user_id == Some(user_id))
+        else {
+            return Ok(false);
+        };
+
+        post.is_deleted = true;
+        post.updated_at = Some(Utc::now());
+
+        Ok(true)
+    }
+
+    async fn edit_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+        comment: Option<&str>,
+        editable_since: DateTime<Utc>,
+    ) -> Result<Option<dbm::PostAndImage>, anyhow::Error> {
+        let mut state = self.state();
+
+        let Some(post) = state
+            .posts
+            .get(&post_id)
+            .filter(|p| p.room_id == Some(room_id) && !p.is_deleted)
+            .filter(|p| is_admin || (p.user_id == Some(user_id) && p.created_at.is_some_and(|c| c >= editable_since)))
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        // Keep the previous comment
+        let revision_id = next(&mut state.seq.post_revision);
+        state.post_revisions.insert(
+            revision_id,
+            dbm::PostRevision {
+                id: Some(revision_id),
+                created_at: post.edited_at.or(post.created_at),
+                post_id: Some(post_id),
+                comment: post.comment,
+            },
+        );
+
+        let now = Utc::now();
+
+        let post = state.posts.get_mut(&post_id).unwrap();
+        post.comment = comment.map(|v| v.to_owned());
+        post.edited_at = Some(now);
+        post.updated_at = Some(now);
+        let post = post.clone();
+
+        state.post_replies.retain(|(id, _)| *id != post_id);
+
+        Ok(Some(dbm::PostAndImage {
+            post,
+            image: state.post_image(post_id),
+        }))
+    }
+
+    async fn get_post_revisions(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<Vec<dbm::PostRevision>, anyhow::Error> {
+        let state = self.state();
+
+        if state
+            .posts
+            .get(&post_id)
+            .is_none_or(|p| p.room_id != Some(room_id) || p.is_deleted || !(is_admin || p.user_id == Some(user_id)))
+        {
+            return Ok(Vec::new());
+        }
+
+        let revisions = state
+            .post_revisions
+            .values()
+            .filter(|r| r.post_id == Some(post_id))
+            .cloned()
+            .collect();
+
+        Ok(revisions)
+    }
+
+    async fn create_post_replies(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reply_to_ids: &[i64],
+    ) -> Result<Vec<dbm::PostReply>, anyhow::Error> {
+        let mut state = self.state();
+
+        if state.posts.get(&post_id).is_none_or(|p| p.room_id != Some(room_id)) {
+            return Ok(Vec::new());
+        }
+
+        let reply_to: BTreeMap<i64, Option<i64>> = reply_to_ids
+            .iter()
+            .filter(|id| **id != post_id)
+            .filter_map(|id| state.posts.get(id))
+            .filter(|p| p.room_id == Some(room_id) && !p.is_deleted)
+            .filter_map(|p| Some((p.id?, p.user_id)))
+            .collect();
+
+        let mut replies = Vec::new();
+
+        for (reply_to_id, reply_to_user_id) in reply_to {
+            if state.post_replies.insert((post_id, reply_to_id)) {
+                replies.push(dbm::PostReply {
+                    post_id: Some(post_id),
+                    reply_to_id: Some(reply_to_id),
+                    reply_to_user_id,
+                });
+            }
+        }
+
+        Ok(replies)
+    }
+
+    async fn get_post_replies(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReply>, anyhow::Error> {
+        let state = self.state();
+
+        let is_visible = |id: &i64| state.posts.get(id).is_some_and(|p| !p.is_deleted);
+
+        let replies = state
+            .post_replies
+            .iter()
+            .filter(|(post_id, reply_to_id)| post_ids.contains(post_id) || post_ids.contains(reply_to_id))
+            .filter(|(post_id, reply_to_id)| is_visible(post_id) && is_visible(reply_to_id))
+            .map(|(post_id, reply_to_id)| dbm::PostReply {
+                post_id: Some(*post_id),
+                reply_to_id: Some(*reply_to_id),
+                reply_to_user_id: state.posts.get(reply_to_id).and_then(|p| p.user_id),
+            })
+            .collect();
+
+        Ok(replies)
+    }
+
+    async fn add_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let mut state = self.state();
+
+        if !state.is_visible_post(room_id, post_id) {
+            return Ok(None);
+        }
+
+        state
+            .post_reactions
+            .entry((post_id, reaction.to_owned(), user_id))
+            .or_insert_with(Utc::now);
+
+        Ok(Some(state.post_reaction_count(post_id, reaction)))
+    }
+
+    async fn remove_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let mut state = self.state();
+
+        if !state.is_visible_post(room_id, post_id) {
+            return Ok(None);
+        }
+
+        if state
+            .post_reactions
+            .remove(&(post_id, reaction.to_owned(), user_id))
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(state.post_reaction_count(post_id, reaction)))
+    }
+
+    async fn get_post_reactions(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReaction>, anyhow::Error> {
+        let state = self.state();
+
+        let mut reactions: Vec<_> = state
+            .post_reactions
+            .iter()
+            .filter(|((post_id, _, _), _)| post_ids.contains(post_id))
+            .filter(|((post_id, _, _), _)| state.posts.get(post_id).is_some_and(|p| !p.is_deleted))
+            .collect();
+
+        reactions.sort_by_key(|((post_id, _, user_id), created_at)| (*post_id, **created_at, *user_id));
+
+        let reactions = reactions
+            .into_iter()
+            .map(|((post_id, reaction, user_id), _)| dbm::PostReaction {
+                post_id: Some(*post_id),
+                reaction: Some(reaction.clone()),
+                user_id: Some(*user_id),
+            })
+            .collect();
+
+        Ok(reactions)
+    }
+
+    async fn get_emotes(&self, room_id: i32) -> Result<Vec<dbm::Emote>, anyhow::Error> {
+        let emotes = self
+            .state()
+            .emotes
+            .values()
+            .filter(|e| e.room_id == Some(room_id))
+            .cloned()
+            .collect();
+
+        Ok(emotes)
+    }
+
+    async fn create_emote(&self, room_id: i32, emote: &dbm::NewEmote) -> Result<dbm::Emote, anyhow::Error> {
+        let mut state = self.state();
+
+        let now = Utc::now();
+
+        // Replace the image of an existing emote with the same name
+        if let Some(existing) = state
+            .emotes
+            .values_mut()
+            .find(|e| e.room_id == Some(room_id) && e.name == emote.name)
+        {
+            existing.hash = emote.hash.clone();
+            existing.ext = emote.ext.clone();
+            existing.updated_at = Some(now);
+
+            return Ok(existing.clone());
+        }
+
+        let id = next(&mut state.seq.emote);
+
+        let emote = dbm::Emote {
+            id: Some(id),
+            created_at: Some(now),
+            updated_at: Some(now),
+            room_id: Some(room_id),
+            name: emote.name.clone(),
+            hash: emote.hash.clone(),
+            ext: emote.ext.clone(),
+        };
+
+        state.emotes.insert(id, emote.clone());
+
+        Ok(emote)
+    }
+
+    async fn delete_emote(&self, room_id: i32, emote_id: i32) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        if state.emotes.get(&emote_id).is_none_or(|e| e.room_id != Some(room_id)) {
+            return Ok(false);
+        }
+
+        state.emotes.remove(&emote_id);
+
+        Ok(true)
+    }
+
+    async fn set_room_content(&self, room_id: i32, content: &str) -> Result<(), anyhow::Error> {
+        self.state()
+            .update_room(room_id, |r| r.content = Some(content.to_owned()));
+
+        Ok(())
+    }
+
+    async fn set_room_playback_state(&self, room_id: i32, playback_state: &str) -> Result<(), anyhow::Error> {
+        self.state()
+            .update_room(room_id, |r| r.playback_state = Some(playback_state.to_owned()));
+
+        Ok(())
+    }
+
+    async fn set_room_end_behavior(&self, room_id: i32, end_behavior: &str) -> Result<(), anyhow::Error> {
+        self.state()
+            .update_room(room_id, |r| r.end_behavior = Some(end_behavior.to_owned()));
+
+        Ok(())
+    }
+
+    async fn get_queue(&self, room_id: i32) -> Result<Vec<dbm::QueueItem>, anyhow::Error> {
+        let mut queue: Vec<dbm::QueueItem> = self
+            .state()
+            .queue_items
+            .values()
+            .filter(|q| q.room_id == Some(room_id))
+            .cloned()
+            .collect();
+
+        queue.sort_by_key(|q| (q.position, q.id));
+
+        Ok(queue)
+    }
+
+    async fn add_queue_item(&self, room_id: i32, content: &str) -> Result<dbm::QueueItem, anyhow::Error> {
+        let mut state = self.state();
+
+        // Append to the end of the queue
+        let position = state
+            .room_queue_items(room_id)
+            .filter_map(|q| q.position)
+            .max()
+            .map_or(0, |v| v + 1);
+
+        let now = Utc::now();
+        let id = next(&mut state.seq.queue_item);
+
+        let item = dbm::QueueItem {
+            id: Some(id),
+            created_at: Some(now),
+            updated_at: Some(now),
+            room_id: Some(room_id),
+            position: Some(position),
+            content: Some(content.to_owned()),
+        };
+
+        state.queue_items.insert(id, item.clone());
+
+        Ok(item)
+    }
+
+    async fn delete_queue_item(&self, room_id: i32, item_id: i64) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        if state
+            .queue_items
+            .get(&item_id)
+            .is_none_or(|q| q.room_id != Some(room_id))
+        {
+            return Ok(false);
+        }
+
+        let Some(position) = state.queue_items.remove(&item_id).and_then(|q| q.position) else {
+            return Ok(true);
+        };
+
+        // Close the gap left by the deleted item
+        for item in state.room_queue_items(room_id) {
+            if let Some(p) = item.position.as_mut()
+                && *p > position
+            {
+                *p -= 1;
+            }
+        }
+
+        Ok(true)
+    }
+
+    async fn move_queue_item(&self, room_id: i32, item_id: i64, position: i32) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        let Some(old_position) = state
+            .queue_items
+            .get(&item_id)
+            .filter(|q| q.room_id == Some(room_id))
+            .and_then(|q| q.position)
+        else {
+            return Ok(false);
+        };
+
+        let max_position = state
+            .room_queue_items(room_id)
+            .filter_map(|q| q.position)
+            .max()
+            .unwrap_or(0);
+
+        let new_position = position.min(max_position).max(0);
+
+        let now = Utc::now();
+
+        for item in state.room_queue_items(room_id) {
+            let Some(p) = item.position.as_mut() else {
+                continue;
+            };
+
+            if item.id == Some(item_id) {
+                *p = new_position;
+            } else if new_position > old_position && *p > old_position && *p <= new_position {
+                *p -= 1;
+            } else if new_position < old_position && *p >= new_position && *p < old_position {
+                *p += 1;
+            } else {
+                continue;
+            }
+
+            item.updated_at = Some(now);
+        }
+
+        Ok(true)
+    }
+
+    async fn clear_queue(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        self.state().queue_items.retain(|_, q| q.room_id != Some(room_id));
+
+        Ok(())
+    }
+
+    async fn get_bans(&self, room_id: i32) -> Result<Vec<dbm::Ban>, anyhow::Error> {
+        let now = Utc::now();
+
+        let bans = self
+            .state()
+            .bans
+            .values()
+            .rev()
+            .filter(|b| b.room_id == Some(room_id) && is_active(b.expires_at, now))
+            .cloned()
+            .collect();
+
+        Ok(bans)
+    }
+
+    async fn get_active_ban(&self, room_id: i32, user_id: i64, ip: IpAddr) -> Result<Option<dbm::Ban>, anyhow::Error> {
+        let now = Utc::now();
+
+        // Prefer permanent bans, then the one expiring last
+        let ban = self
+            .state()
+            .bans
+            .values()
+            .filter(|b| b.room_id == Some(room_id))
+            .filter(|b| b.user_id == Some(user_id) || b.ip == Some(ip))
+            .filter(|b| is_active(b.expires_at, now))
+            .max_by_key(|b| b.expires_at.map_or(DateTime::<Utc>::MAX_UTC, |v| v))
+            .cloned();
+
+        Ok(ban)
+    }
+
+    async fn create_ban_from_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reason: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<dbm::Ban>, anyhow::Error> {
+        let mut state = self.state();
+
+        let Some(post) = state.posts.get(&post_id).filter(|p| p.room_id == Some(room_id)) else {
+            return Ok(None);
+        };
+
+        let (user_id, ip) = (post.user_id, post.ip);
+
+        // There is nothing to ban if the post has neither a user ID nor an IP address
+        if user_id.is_none() && ip.is_none() {
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let id = next(&mut state.seq.ban);
+
+        let ban = dbm::Ban {
+            id: Some(id),
+            created_at: Some(now),
+            updated_at: Some(now),
+            room_id: Some(room_id),
+            post_id: Some(post_id),
+            user_id,
+            ip,
+            reason: reason.map(|v| v.to_owned()),
+            expires_at,
+        };
+
+        state.bans.insert(id, ban.clone());
+
+        Ok(Some(ban))
+    }
+
+    async fn delete_ban(&self, room_id: i32, ban_id: i32) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        if state.bans.get(&ban_id).is_none_or(|b| b.room_id != Some(room_id)) {
+            return Ok(false);
+        }
+
+        state.bans.remove(&ban_id);
+
+        Ok(true)
+    }
+
+    async fn get_moderators(&self, room_id: i32) -> Result<Vec<dbm::Moderator>, anyhow::Error> {
+        let moderators = self
+            .state()
+            .moderators
+            .values()
+            .filter(|m| m.room_id == Some(room_id))
+            .cloned()
+            .collect();
+
+        Ok(moderators)
+    }
+
+    async fn get_moderator(&self, room_id: i32, moderator_id: i32) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = self
+            .state()
+            .moderators
+            .get(&moderator_id)
+            .filter(|m| m.room_id == Some(room_id))
+            .cloned();
+
+        Ok(moderator)
+    }
+
+    async fn get_moderator_by_name(&self, room_id: i32, name: &str) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = self
+            .state()
+            .moderators
+            .values()
+            .find(|m| m.room_id == Some(room_id) && m.name.as_deref() == Some(name))
+            .cloned();
+
+        Ok(moderator)
+    }
+
+    async fn create_moderator(
+        &self,
+        room_id: i32,
+        name: &str,
+        key: &str,
+        permissions: &[String],
+    ) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let mut state = self.state();
+
+        if state
+            .moderators
+            .values()
+            .any(|m| m.room_id == Some(room_id) && m.name.as_deref() == Some(name))
+        {
+            return Ok(None);
+        }
+
+        let now = Utc::now();
+        let id = next(&mut state.seq.moderator);
+
+        let moderator = dbm::Moderator {
+            id: Some(id),
+            created_at: Some(now),
+            updated_at: Some(now),
+            room_id: Some(room_id),
+            name: Some(name.to_owned()),
+            key: Some(key.to_owned()),
+            permissions: Some(permissions.to_vec()),
+        };
+
+        state.moderators.insert(id, moderator.clone());
+
+        Ok(Some(moderator))
+    }
+
+    async fn delete_moderator(&self, room_id: i32, moderator_id: i32) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        if state
+            .moderators
+            .get(&moderator_id)
+            .is_none_or(|m| m.room_id != Some(room_id))
+        {
+            return Ok(false);
+        }
+
+        state.moderators.remove(&moderator_id);
+
+        // Revoke all sessions belonging to the moderator
+        state.refresh_tokens.retain(|_, t| {
+            if t.room_id != Some(room_id) {
+                return true;
+            }
+
+            let Ok(claims) = serde_json::from_str::<serde_json::Value>(&t.claims) else {
+                return true;
+            };
+
+            !(claims["level"] == "moderator" && claims["moderator_id"] == moderator_id)
+        });
+
+        Ok(true)
+    }
+
+    async fn update_post_images(&self, hash: &str, ext: &str, tn_ext: &str) -> Result<(), anyhow::Error> {
+        let now = Utc::now();
+
+        for image in self
+            .state()
+            .images
+            .values_mut()
+            .filter(|i| i.hash.as_deref() == Some(hash))
+        {
+            image.ext = Some(ext.to_owned());
+            image.tn_ext = Some(tn_ext.to_owned());
+            image.updated_at = Some(now);
+        }
+
+        Ok(())
+    }
+
+    async fn update_emote_images(&self, hash: &str, ext: &str) -> Result<(), anyhow::Error> {
+        let now = Utc::now();
+
+        for emote in self
+            .state()
+            .emotes
+            .values_mut()
+            .filter(|e| e.hash.as_deref() == Some(hash))
+        {
+            emote.ext = Some(ext.to_owned());
+            emote.updated_at = Some(now);
+        }
+
+        Ok(())
+    }
+
+    async fn generate_user_id(&self) -> Result<i64, anyhow::Error> {
+        Ok(next(&mut self.state().seq.user))
+    }
+
+    async fn create_refresh_token(&self, claims: &str, room_id: Option<i32>) -> Result<Uuid, anyhow::Error> {
+        let mut state = self.state();
+
+        // Generate new refresh token with new family
+        let family = next(&mut state.seq.refresh_token_family);
+        let token = state.insert_refresh_token(family, claims.to_owned(), room_id);
+
+        Ok(token.token)
+    }
+
+    async fn refresh_refresh_token(&self, token: Uuid) -> Result<RefreshRefreshTokenResult, anyhow::Error> {
+        let mut state = self.state();
+
+        let none = RefreshRefreshTokenResult {
+            token: None,
+            claims: None,
+        };
+
+        let Some(refresh_token) = state.refresh_tokens.values().find(|t| t.token == token).cloned() else {
+            return Ok(none);
+        };
+
+        // If a token that has already been rotated is presented again,
+        // it has likely been stolen, so revoke the whole family.
+        if refresh_token.used {
+            state.delete_refresh_token_family(refresh_token.family);
+
+            return Ok(none);
+        }
+
+        if refresh_token.expires_at < Utc::now() {
+            return Ok(none);
+        }
+
+        // Mark token as used
+        if let Some(t) = state.refresh_tokens.get_mut(&refresh_token.id) {
+            t.used = true;
+        }
+
+        // Generate new refresh token
+        let new_token = state.insert_refresh_token(refresh_token.family, refresh_token.claims, refresh_token.room_id);
+
+        Ok(RefreshRefreshTokenResult {
+            token: Some(new_token.token),
+            claims: Some(new_token.claims),
+        })
+    }
+
+    async fn revoke_room_refresh_tokens(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        self.state().refresh_tokens.retain(|_, t| t.room_id != Some(room_id));
+
+        Ok(())
+    }
+
+    async fn revoke_refresh_token_family(&self, token: Uuid) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        let Some(family) = state
+            .refresh_tokens
+            .values()
+            .find(|t| t.token == token)
+            .map(|t| t.family)
+        else {
+            return Ok(false);
+        };
+
+        state.delete_refresh_token_family(family);
+
+        Ok(true)
+    }
+
+    async fn get_room_sessions(&self, room_id: i32) -> Result<Vec<dbm::Session>, anyhow::Error> {
+        let state = self.state();
+
+        let now = Utc::now();
+
+        let mut tokens: Vec<&RefreshToken> = state
+            .refresh_tokens
+            .values()
+            .filter(|t| t.room_id == Some(room_id) && !t.used && t.expires_at >= now)
+            .collect();
+
+        tokens.sort_by_key(|t| std::cmp::Reverse(t.created_at));
+
+        let sessions = tokens
+            .into_iter()
+            .map(|t| dbm::Session {
+                family: Some(t.family),
+                created_at: state
+                    .refresh_tokens
+                    .values()
+                    .filter(|f| f.family == t.family)
+                    .map(|f| f.created_at)
+                    .min(),
+                refreshed_at: Some(t.created_at),
+                expires_at: Some(t.expires_at),
+                claims: Some(t.claims.clone()),
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    async fn revoke_room_session(&self, room_id: i32, family: i64) -> Result<bool, anyhow::Error> {
+        let mut state = self.state();
+
+        let count = state.refresh_tokens.len();
+
+        state
+            .refresh_tokens
+            .retain(|_, t| !(t.room_id == Some(room_id) && t.family == family));
+
+        Ok(state.refresh_tokens.len() < count)
+    }
+}
@@ -3,7 +3,7 @@ use std::net::IpAddr;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
-#[derive(Debug, sqlx::Type)]
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "room")]
 pub struct Room {
     pub id: Option<i32>,
@@ -15,10 +15,12 @@ pub struct Room {
     pub password: Option<String>,
     pub content: Option<String>,
     pub playback_state: Option<String>,
+    pub end_behavior: Option<String>,
 }
 
-#[derive(Debug, sqlx::Type)]
-#[sqlx(type_name = "post")]
+/// Post without its search vector
+#[derive(Clone, Debug, sqlx::Type)]
+#[sqlx(type_name = "post_data")]
 pub struct Post {
     pub id: Option<i64>,
     pub created_at: Option<DateTime<Utc>>,
@@ -30,9 +32,10 @@ pub struct Post {
     pub is_deleted: bool,
     pub user_id: Option<i64>,
     pub admin: bool,
+    pub edited_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, sqlx::Type)]
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "image")]
 pub struct Image {
     pub id: Option<i64>,
@@ -45,13 +48,38 @@ pub struct Image {
     pub tn_ext: Option<String>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct PostAndImage {
     pub post: Post,
     pub image: Option<Image>,
 }
 
-#[derive(Debug, sqlx::Type)]
+/// Previous comment of an edited post
+#[derive(Clone, Debug)]
+pub struct PostRevision {
+    pub id: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_id: Option<i64>,
+    pub comment: Option<String>,
+}
+
+/// Reference from a post to a post it replies to
+#[derive(Clone, Debug)]
+pub struct PostReply {
+    pub post_id: Option<i64>,
+    pub reply_to_id: Option<i64>,
+    pub reply_to_user_id: Option<i64>,
+}
+
+/// Reaction to a post by a user
+#[derive(Clone, Debug)]
+pub struct PostReaction {
+    pub post_id: Option<i64>,
+    pub reaction: Option<String>,
+    pub user_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "new_post")]
 pub struct NewPost {
     pub name: Option<String>,
@@ -61,7 +89,7 @@ pub struct NewPost {
     pub admin: bool,
 }
 
-#[derive(Debug, sqlx::Type)]
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "new_image")]
 pub struct NewImage {
     pub filename: Option<String>,
@@ -70,7 +98,7 @@ pub struct NewImage {
     pub tn_ext: Option<String>,
 }
 
-#[derive(Debug, sqlx::Type)]
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "emote")]
 pub struct Emote {
     pub id: Option<i32>,
@@ -82,7 +110,7 @@ pub struct Emote {
     pub ext: Option<String>,
 }
 
-#[derive(Debug, sqlx::Type)]
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "new_emote")]
 pub struct NewEmote {
     pub name: Option<String>,
@@ -90,9 +118,84 @@ pub struct NewEmote {
     pub ext: Option<String>,
 }
 
-#[derive(Debug, sqlx::Type)]
+#[derive(Clone, Debug, sqlx::Type)]
+#[sqlx(type_name = "queue_item")]
+pub struct QueueItem {
+    pub id: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub room_id: Option<i32>,
+    pub position: Option<i32>,
+    pub content: Option<String>,
+}
+
+#[derive(Clone, Debug, sqlx::Type)]
 #[sqlx(type_name = "refresh_refresh_token_result")]
 pub struct RefreshRefreshTokenResult {
     pub token: Option<Uuid>,
     pub claims: Option<String>,
 }
+
+#[derive(Clone, Debug, sqlx::Type)]
+#[sqlx(type_name = "moderator")]
+pub struct Moderator {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub room_id: Option<i32>,
+    pub name: Option<String>,
+    pub key: Option<String>,
+    pub permissions: Option<Vec<String>>,
+}
+
+#[derive(Clone, Debug, sqlx::Type)]
+#[sqlx(type_name = "ban")]
+pub struct Ban {
+    pub id: Option<i32>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+    pub room_id: Option<i32>,
+    pub post_id: Option<i64>,
+    pub user_id: Option<i64>,
+    pub ip: Option<IpAddr>,
+    pub reason: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RoomSummary {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub post_count: Option<i64>,
+    pub last_post_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct InstanceStats {
+    pub room_count: Option<i64>,
+    pub post_count: Option<i64>,
+    pub image_count: Option<i64>,
+    pub emote_count: Option<i64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Session {
+    pub family: Option<i64>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub refreshed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub claims: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PostSearch {
+    pub query: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub has_image: Option<bool>,
+    pub admin: Option<bool>,
+    pub user_id: Option<i64>,
+    pub before_id: Option<i64>,
+    pub count: i32,
+}
@@ -0,0 +1,1477 @@
+use std::net::IpAddr;
+use std::str::FromStr;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePool, SqliteRow};
+use sqlx::{QueryBuilder, Row};
+use uuid::Uuid;
+
+use crate::AriaStore;
+use crate::models::{self as dbm, RefreshRefreshTokenResult};
+use crate::util::{REFRESH_TOKEN_LIFETIME_DAYS, never_expires};
+
+/// Post columns followed by the columns of its image, if any, prefixed with "image_"
+const POST_AND_IMAGE_COLUMNS: &str = r#"
+    p.*,
+    i.id AS image_id,
+    i.post_id AS image_post_id,
+    i.created_at AS image_created_at,
+    i.updated_at AS image_updated_at,
+    i.filename AS image_filename,
+    i.hash AS image_hash,
+    i.ext AS image_ext,
+    i.tn_ext AS image_tn_ext
+"#;
+
+/// Store backed by an SQLite database.
+/// The PostgreSQL functions are re-expressed as plain queries,
+/// with multi-statement ones running in a transaction.
+pub struct SqliteStore {
+    pool: SqlitePool,
+}
+
+impl SqliteStore {
+    pub fn new(uri: &str) -> Result<Self, anyhow::Error> {
+        let options = SqliteConnectOptions::from_str(uri)
+            .context("Invalid SQLite database URI")?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal);
+
+        let pool = SqlitePool::connect_lazy_with(options);
+
+        Ok(Self { pool })
+    }
+
+    /// Begin a transaction that takes the write lock immediately,
+    /// so concurrent read-then-write transactions wait instead of failing
+    async fn begin(&self) -> Result<sqlx::Transaction<'static, sqlx::Sqlite>, anyhow::Error> {
+        Ok(self.pool.begin_with("BEGIN IMMEDIATE").await?)
+    }
+}
+
+async fn next_sequence_value<'e, E>(executor: E, name: &str) -> Result<i64, anyhow::Error>
+where
+    E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+{
+    let value = sqlx::query_scalar(
+        r#"INSERT INTO sequence (name, value) VALUES ($1, 1)
+        ON CONFLICT (name) DO UPDATE SET value = value + 1
+        RETURNING value;"#,
+    )
+    .bind(name)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(value)
+}
+
+fn decode_error(column: &str, err: impl std::error::Error + Send + Sync + 'static) -> sqlx::Error {
+    sqlx::Error::ColumnDecode {
+        index: column.to_owned(),
+        source: Box::new(err),
+    }
+}
+
+fn get_ip(row: &SqliteRow, column: &str) -> Result<Option<IpAddr>, sqlx::Error> {
+    row.try_get::<Option<String>, _>(column)?
+        .map(|v| v.parse().map_err(|err| decode_error(column, err)))
+        .transpose()
+}
+
+fn room_from_row(row: &SqliteRow) -> Result<dbm::Room, sqlx::Error> {
+    Ok(dbm::Room {
+        id: row.try_get("id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        name: row.try_get("name")?,
+        claimed_at: row.try_get("claimed_at")?,
+        expires_at: row.try_get("expires_at")?,
+        password: row.try_get("password")?,
+        content: row.try_get("content")?,
+        playback_state: row.try_get("playback_state")?,
+        end_behavior: row.try_get("end_behavior")?,
+    })
+}
+
+fn post_from_row(row: &SqliteRow) -> Result<dbm::Post, sqlx::Error> {
+    Ok(dbm::Post {
+        id: row.try_get("id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        room_id: row.try_get("room_id")?,
+        name: row.try_get("name")?,
+        comment: row.try_get("comment")?,
+        ip: get_ip(row, "ip")?,
+        is_deleted: row.try_get("is_deleted")?,
+        user_id: row.try_get("user_id")?,
+        admin: row.try_get("admin")?,
+        edited_at: row.try_get("edited_at")?,
+    })
+}
+
+fn image_from_row(row: &SqliteRow, prefix: &str) -> Result<Option<dbm::Image>, sqlx::Error> {
+    let column = |name: &str| format!("{prefix}{name}");
+
+    let Some(id) = row.try_get(column("id").as_str())? else {
+        return Ok(None);
+    };
+
+    Ok(Some(dbm::Image {
+        id: Some(id),
+        post_id: row.try_get(column("post_id").as_str())?,
+        created_at: row.try_get(column("created_at").as_str())?,
+        updated_at: row.try_get(column("updated_at").as_str())?,
+        filename: row.try_get(column("filename").as_str())?,
+        hash: row.try_get(column("hash").as_str())?,
+        ext: row.try_get(column("ext").as_str())?,
+        tn_ext: row.try_get(column("tn_ext").as_str())?,
+    }))
+}
+
+fn post_and_image_from_row(row: &SqliteRow) -> Result<dbm::PostAndImage, sqlx::Error> {
+    Ok(dbm::PostAndImage {
+        post: post_from_row(row)?,
+        image: image_from_row(row, "image_")?,
+    })
+}
+
+fn emote_from_row(row: &SqliteRow) -> Result<dbm::Emote, sqlx::Error> {
+    Ok(dbm::Emote {
+        id: row.try_get("id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        room_id: row.try_get("room_id")?,
+        name: row.try_get("name")?,
+        hash: row.try_get("hash")?,
+        ext: row.try_get("ext")?,
+    })
+}
+
+fn queue_item_from_row(row: &SqliteRow) -> Result<dbm::QueueItem, sqlx::Error> {
+    Ok(dbm::QueueItem {
+        id: row.try_get("id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        room_id: row.try_get("room_id")?,
+        position: row.try_get("position")?,
+        content: row.try_get("content")?,
+    })
+}
+
+fn moderator_from_row(row: &SqliteRow) -> Result<dbm::Moderator, sqlx::Error> {
+    let permissions = row
+        .try_get::<Option<String>, _>("permissions")?
+        .map(|v| serde_json::from_str(&v).map_err(|err| decode_error("permissions", err)))
+        .transpose()?;
+
+    Ok(dbm::Moderator {
+        id: row.try_get("id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        room_id: row.try_get("room_id")?,
+        name: row.try_get("name")?,
+        key: row.try_get("key")?,
+        permissions,
+    })
+}
+
+fn ban_from_row(row: &SqliteRow) -> Result<dbm::Ban, sqlx::Error> {
+    Ok(dbm::Ban {
+        id: row.try_get("id")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+        room_id: row.try_get("room_id")?,
+        post_id: row.try_get("post_id")?,
+        user_id: row.try_get("user_id")?,
+        ip: get_ip(row, "ip")?,
+        reason: row.try_get("reason")?,
+        expires_at: row.try_get("expires_at")?,
+    })
+}
+
+fn session_from_row(row: &SqliteRow) -> Result<dbm::Session, sqlx::Error> {
+    Ok(dbm::Session {
+        family: row.try_get("family")?,
+        created_at: row.try_get("created_at")?,
+        refreshed_at: row.try_get("refreshed_at")?,
+        expires_at: row.try_get("expires_at")?,
+        claims: row.try_get("claims")?,
+    })
+}
+
+#[async_trait]
+impl AriaStore for SqliteStore {
+    async fn migrate(&self) -> Result<(), anyhow::Error> {
+        sqlx::migrate!("./sqlite_migrations").run(&self.pool).await?;
+
+        Ok(())
+    }
+
+    async fn get_recent_posts(&self, room_id: i32, count: i32) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        self.get_posts(room_id, None, count.min(50)).await
+    }
+
+    async fn get_posts(
+        &self,
+        room_id: i32,
+        before_id: Option<i64>,
+        count: i32,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        let mut posts = sqlx::query(&format!(
+            r#"SELECT {POST_AND_IMAGE_COLUMNS}
+            FROM post AS p
+            LEFT JOIN image AS i ON i.post_id = p.id
+            WHERE p.room_id = $1 AND NOT p.is_deleted AND ($2 IS NULL OR p.id < $2)
+            ORDER BY p.id DESC
+            LIMIT $3;"#
+        ))
+        .bind(room_id)
+        .bind(before_id)
+        .bind(count)
+        .try_map(|row| post_and_image_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting posts")?;
+
+        // Reverse posts, as they are returned in reverse order
+        posts.reverse();
+
+        Ok(posts)
+    }
+
+    async fn search_posts(
+        &self,
+        room_id: i32,
+        search: &dbm::PostSearch,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        let mut query = QueryBuilder::new(format!(
+            r#"SELECT {POST_AND_IMAGE_COLUMNS}
+            FROM post AS p
+            LEFT JOIN image AS i ON i.post_id = p.id
+            WHERE NOT p.is_deleted AND p.room_id = "#
+        ));
+
+        query.push_bind(room_id);
+
+        // Every term must occur in either the name or the comment
+        for term in search.query.iter().flat_map(|q| q.split_whitespace()) {
+            let pattern = format!(
+                "%{}%",
+                term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+            );
+
+            query
+                .push(" AND (COALESCE(p.name, '') || ' ' || COALESCE(p.comment, '')) LIKE ")
+                .push_bind(pattern)
+                .push(" ESCAPE '\\'");
+        }
+
+        if let Some(from) = search.from {
+            query.push(" AND p.created_at >= ").push_bind(from);
+        }
+
+        if let Some(to) = search.to {
+            query.push(" AND p.created_at < ").push_bind(to);
+        }
+
+        if let Some(has_image) = search.has_image {
+            query.push(" AND (i.id IS NOT NULL) = ").push_bind(has_image);
+        }
+
+        if let Some(admin) = search.admin {
+            query.push(" AND p.admin = ").push_bind(admin);
+        }
+
+        if let Some(user_id) = search.user_id {
+            query.push(" AND p.user_id = ").push_bind(user_id);
+        }
+
+        if let Some(before_id) = search.before_id {
+            query.push(" AND p.id < ").push_bind(before_id);
+        }
+
+        query.push(" ORDER BY p.id DESC LIMIT ").push_bind(search.count);
+
+        let posts = query
+            .build()
+            .try_map(|row| post_and_image_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+            .context("Error searching posts")?;
+
+        Ok(posts)
+    }
+
+    async fn get_room(&self, room_id: i32) -> Result<Option<dbm::Room>, anyhow::Error> {
+        let room = sqlx::query(r#"SELECT * FROM room WHERE id = $1 AND expires_at > $2;"#)
+            .bind(room_id)
+            .bind(Utc::now())
+            .try_map(|row| room_from_row(&row))
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error getting room")?;
+
+        Ok(room)
+    }
+
+    async fn get_room_by_name(&self, name: &str) -> Result<Option<dbm::Room>, anyhow::Error> {
+        let room = sqlx::query(r#"SELECT * FROM room WHERE name = $1 AND expires_at > $2;"#)
+            .bind(name)
+            .bind(Utc::now())
+            .try_map(|row| room_from_row(&row))
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error getting room")?;
+
+        Ok(room)
+    }
+
+    async fn create_room(
+        &self,
+        name: &str,
+        password: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<dbm::Room, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let now = Utc::now();
+
+        let existing: Option<i32> = sqlx::query_scalar(r#"SELECT id FROM room WHERE name = $1 AND expires_at > $2;"#)
+            .bind(name)
+            .bind(now)
+            .fetch_optional(&mut *tx)
+            .await?;
+
+        if existing.is_some() {
+            anyhow::bail!("Room already exists.");
+        }
+
+        // Free up the name if it belongs to an expired room
+        sqlx::query(r#"DELETE FROM room WHERE name = $1 AND expires_at <= $2;"#)
+            .bind(name)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+
+        let room = sqlx::query(
+            r#"INSERT INTO room (created_at, updated_at, name, claimed_at, expires_at, password)
+            VALUES ($1, $1, $2, $1, $3, $4)
+            RETURNING *;"#,
+        )
+        .bind(now)
+        .bind(name)
+        .bind(expires_at.unwrap_or_else(never_expires))
+        .bind(password)
+        .try_map(|row| room_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(room)
+    }
+
+    async fn extend_room_expiry(&self, room_id: i32, expires_at: DateTime<Utc>) -> Result<(), anyhow::Error> {
+        let now = Utc::now();
+
+        sqlx::query(r#"UPDATE room SET expires_at = $1, updated_at = $2 WHERE id = $3 AND expires_at > $2;"#)
+            .bind(expires_at)
+            .bind(now)
+            .bind(room_id)
+            .execute(&self.pool)
+            .await
+            .context("Error extending room expiry")?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_rooms(&self) -> Result<Vec<i32>, anyhow::Error> {
+        let room_ids = sqlx::query_scalar(r#"DELETE FROM room WHERE expires_at <= $1 RETURNING id;"#)
+            .bind(Utc::now())
+            .fetch_all(&self.pool)
+            .await
+            .context("Error deleting expired rooms")?;
+
+        Ok(room_ids)
+    }
+
+    async fn clear_room_expiry(&self) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE room SET expires_at = $1, updated_at = $2 WHERE expires_at < $1;"#)
+            .bind(never_expires())
+            .bind(Utc::now())
+            .execute(&self.pool)
+            .await
+            .context("Error clearing room expiry")?;
+
+        Ok(())
+    }
+
+    async fn set_room_password(&self, room_id: i32, password: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE room SET password = $1, updated_at = $2 WHERE id = $3;"#)
+            .bind(password)
+            .bind(Utc::now())
+            .bind(room_id)
+            .execute(&self.pool)
+            .await
+            .context("Error setting room password")?;
+
+        Ok(())
+    }
+
+    async fn replace_room_password(
+        &self,
+        room_id: i32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"UPDATE room SET password = $1, updated_at = $2 WHERE id = $3 AND password = $4;"#)
+            .bind(new_password)
+            .bind(Utc::now())
+            .bind(room_id)
+            .bind(old_password)
+            .execute(&self.pool)
+            .await
+            .context("Error replacing room password")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_rooms(&self) -> Result<Vec<dbm::RoomSummary>, anyhow::Error> {
+        let rooms = sqlx::query(
+            r#"SELECT
+              r.id,
+              r.name,
+              r.created_at,
+              COUNT(p.id) AS post_count,
+              MAX(p.created_at) AS last_post_at
+            FROM room AS r
+            LEFT JOIN post AS p ON p.room_id = r.id AND NOT p.is_deleted
+            GROUP BY r.id
+            ORDER BY r.id ASC;"#,
+        )
+        .try_map(|row: SqliteRow| {
+            Ok(dbm::RoomSummary {
+                id: row.try_get("id")?,
+                name: row.try_get("name")?,
+                created_at: row.try_get("created_at")?,
+                post_count: row.try_get("post_count")?,
+                last_post_at: row.try_get("last_post_at")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting rooms")?;
+
+        Ok(rooms)
+    }
+
+    async fn delete_room(&self, room_id: i32) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM room WHERE id = $1;"#)
+            .bind(room_id)
+            .execute(&self.pool)
+            .await
+            .context("Error deleting room")?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn purge_room_posts(&self, room_id: i32) -> Result<Vec<i64>, anyhow::Error> {
+        let post_ids = sqlx::query_scalar(
+            r#"UPDATE post SET is_deleted = true, updated_at = $1
+            WHERE room_id = $2 AND NOT is_deleted
+            RETURNING id;"#,
+        )
+        .bind(Utc::now())
+        .bind(room_id)
+        .fetch_all(&self.pool)
+        .await
+        .context("Error purging room posts")?;
+
+        Ok(post_ids)
+    }
+
+    async fn get_room_images(&self, room_id: i32) -> Result<Vec<dbm::Image>, anyhow::Error> {
+        let images = sqlx::query(
+            r#"SELECT i.*
+            FROM image AS i
+            INNER JOIN post AS p ON p.id = i.post_id
+            WHERE p.room_id = $1;"#,
+        )
+        .bind(room_id)
+        .try_map(|row| image_from_row(&row, ""))
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting room images")?;
+
+        Ok(images.into_iter().flatten().collect())
+    }
+
+    async fn get_expired_room_images(&self) -> Result<Vec<dbm::Image>, anyhow::Error> {
+        let images = sqlx::query(
+            r#"SELECT i.*
+            FROM image AS i
+            INNER JOIN post AS p ON p.id = i.post_id
+            INNER JOIN room AS r ON r.id = p.room_id
+            WHERE r.expires_at <= $1;"#,
+        )
+        .bind(Utc::now())
+        .try_map(|row| image_from_row(&row, ""))
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting expired room images")?;
+
+        Ok(images.into_iter().flatten().collect())
+    }
+
+    async fn get_used_image_hashes(&self, hashes: &[String]) -> Result<Vec<String>, anyhow::Error> {
+        let hashes = sqlx::query_scalar(
+            r#"SELECT DISTINCT hash
+            FROM image
+            WHERE hash IN (SELECT value FROM json_each($1));"#,
+        )
+        .bind(serde_json::to_string(hashes)?)
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting used image hashes")?;
+
+        Ok(hashes)
+    }
+
+    async fn get_instance_stats(&self) -> Result<dbm::InstanceStats, anyhow::Error> {
+        let stats = sqlx::query(
+            r#"SELECT
+              (SELECT COUNT(*) FROM room) AS room_count,
+              (SELECT COUNT(*) FROM post WHERE NOT is_deleted) AS post_count,
+              (SELECT COUNT(*) FROM image AS i JOIN post AS p ON p.id = i.post_id WHERE NOT p.is_deleted) AS image_count,
+              (SELECT COUNT(*) FROM emote) AS emote_count;"#,
+        )
+        .try_map(|row: SqliteRow| {
+            Ok(dbm::InstanceStats {
+                room_count: row.try_get("room_count")?,
+                post_count: row.try_get("post_count")?,
+                image_count: row.try_get("image_count")?,
+                emote_count: row.try_get("emote_count")?,
+            })
+        })
+        .fetch_one(&self.pool)
+        .await
+        .context("Error getting instance stats")?;
+
+        Ok(stats)
+    }
+
+    async fn create_post(
+        &self,
+        room_id: i32,
+        post: &dbm::NewPost,
+        image: Option<&dbm::NewImage>,
+    ) -> Result<dbm::PostAndImage, anyhow::Error> {
+        self.import_post(room_id, post, image, Utc::now()).await
+    }
+
+    async fn import_post(
+        &self,
+        room_id: i32,
+        post: &dbm::NewPost,
+        image: Option<&dbm::NewImage>,
+        created_at: DateTime<Utc>,
+    ) -> Result<dbm::PostAndImage, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let post = sqlx::query(
+            r#"INSERT INTO post (created_at, updated_at, room_id, name, comment, ip, user_id, admin)
+            VALUES ($1, $1, $2, $3, $4, $5, $6, $7)
+            RETURNING *;"#,
+        )
+        .bind(created_at)
+        .bind(room_id)
+        .bind(&post.name)
+        .bind(&post.comment)
+        .bind(post.ip.map(|v| v.to_string()))
+        .bind(post.user_id)
+        .bind(post.admin)
+        .try_map(|row| post_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // If an image is provided, insert it
+        let image = if let Some(image) = image {
+            sqlx::query(
+                r#"INSERT INTO image (post_id, created_at, updated_at, filename, hash, ext, tn_ext)
+                VALUES ($1, $2, $2, $3, $4, $5, $6)
+                RETURNING *;"#,
+            )
+            .bind(post.id)
+            .bind(created_at)
+            .bind(&image.filename)
+            .bind(&image.hash)
+            .bind(&image.ext)
+            .bind(&image.tn_ext)
+            .try_map(|row| image_from_row(&row, ""))
+            .fetch_one(&mut *tx)
+            .await?
+        } else {
+            None
+        };
+
+        tx.commit().await?;
+
+        Ok(dbm::PostAndImage { post, image })
+    }
+
+    async fn delete_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"UPDATE post SET is_deleted = true, updated_at = $1
+            WHERE room_id = $2 AND id = $3 AND NOT is_deleted AND ($4 OR user_id = $5);"#,
+        )
+        .bind(Utc::now())
+        .bind(room_id)
+        .bind(post_id)
+        .bind(is_admin)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn edit_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+        comment: Option<&str>,
+        editable_since: DateTime<Utc>,
+    ) -> Result<Option<dbm::PostAndImage>, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let post = sqlx::query(r#"SELECT * FROM post WHERE room_id = $1 AND id = $2 AND NOT is_deleted;"#)
+            .bind(room_id)
+            .bind(post_id)
+            .try_map(|row| post_from_row(&row))
+            .fetch_optional(&mut *tx)
+            .await?
+            .filter(|p| is_admin || (p.user_id == Some(user_id) && p.created_at.is_some_and(|c| c >= editable_since)));
+
+        let Some(post) = post else {
+            return Ok(None);
+        };
+
+        // Keep the previous comment
+        sqlx::query(r#"INSERT INTO post_revision (created_at, post_id, comment) VALUES ($1, $2, $3);"#)
+            .bind(post.edited_at.or(post.created_at))
+            .bind(post_id)
+            .bind(&post.comment)
+            .execute(&mut *tx)
+            .await?;
+
+        let now = Utc::now();
+
+        sqlx::query(r#"UPDATE post SET comment = $1, edited_at = $2, updated_at = $2 WHERE id = $3;"#)
+            .bind(comment)
+            .bind(now)
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+
+        sqlx::query(r#"DELETE FROM post_reply WHERE post_id = $1;"#)
+            .bind(post_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let post = sqlx::query(&format!(
+            r#"SELECT {POST_AND_IMAGE_COLUMNS}
+            FROM post AS p
+            LEFT JOIN image AS i ON i.post_id = p.id
+            WHERE p.id = $1;"#
+        ))
+        .bind(post_id)
+        .try_map(|row| post_and_image_from_row(&row))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(post))
+    }
+
+    async fn get_post_revisions(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<Vec<dbm::PostRevision>, anyhow::Error> {
+        let revisions = sqlx::query(
+            r#"SELECT r.*
+            FROM post_revision AS r
+            INNER JOIN post AS p ON p.id = r.post_id
+            WHERE p.room_id = $1 AND r.post_id = $2 AND NOT p.is_deleted AND ($4 OR p.user_id = $3)
+            ORDER BY r.id;"#,
+        )
+        .bind(room_id)
+        .bind(post_id)
+        .bind(user_id)
+        .bind(is_admin)
+        .try_map(|row: SqliteRow| {
+            Ok(dbm::PostRevision {
+                id: row.try_get("id")?,
+                created_at: row.try_get("created_at")?,
+                post_id: row.try_get("post_id")?,
+                comment: row.try_get("comment")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting post revisions")?;
+
+        Ok(revisions)
+    }
+
+    async fn create_post_replies(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reply_to_ids: &[i64],
+    ) -> Result<Vec<dbm::PostReply>, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let reply_to = sqlx::query(
+            r#"SELECT t.id, t.user_id
+            FROM post AS t
+            WHERE t.room_id = $1
+              AND t.id IN (SELECT value FROM json_each($2))
+              AND t.id <> $3
+              AND NOT t.is_deleted
+              AND EXISTS (SELECT 1 FROM post AS p WHERE p.id = $3 AND p.room_id = $1)
+            ORDER BY t.id;"#,
+        )
+        .bind(room_id)
+        .bind(serde_json::to_string(reply_to_ids)?)
+        .bind(post_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut replies = Vec::new();
+
+        for row in reply_to {
+            let reply_to_id: i64 = row.try_get("id")?;
+
+            let result = sqlx::query(r#"INSERT OR IGNORE INTO post_reply (post_id, reply_to_id) VALUES ($1, $2);"#)
+                .bind(post_id)
+                .bind(reply_to_id)
+                .execute(&mut *tx)
+                .await?;
+
+            if result.rows_affected() > 0 {
+                replies.push(dbm::PostReply {
+                    post_id: Some(post_id),
+                    reply_to_id: Some(reply_to_id),
+                    reply_to_user_id: row.try_get("user_id")?,
+                });
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(replies)
+    }
+
+    async fn get_post_replies(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReply>, anyhow::Error> {
+        let replies = sqlx::query(
+            r#"SELECT r.post_id, r.reply_to_id, t.user_id AS reply_to_user_id
+            FROM post_reply AS r
+            JOIN post AS p ON p.id = r.post_id
+            JOIN post AS t ON t.id = r.reply_to_id
+            WHERE (r.post_id IN (SELECT value FROM json_each($1)) OR r.reply_to_id IN (SELECT value FROM json_each($1)))
+              AND NOT p.is_deleted
+              AND NOT t.is_deleted
+            ORDER BY r.post_id, r.reply_to_id;"#,
+        )
+        .bind(serde_json::to_string(post_ids)?)
+        .try_map(|row: SqliteRow| {
+            Ok(dbm::PostReply {
+                post_id: row.try_get("post_id")?,
+                reply_to_id: row.try_get("reply_to_id")?,
+                reply_to_user_id: row.try_get("reply_to_user_id")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting post replies")?;
+
+        Ok(replies)
+    }
+
+    async fn add_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        if !is_visible_post(&mut tx, room_id, post_id).await? {
+            return Ok(None);
+        }
+
+        sqlx::query(
+            r#"INSERT OR IGNORE INTO post_reaction (post_id, reaction, user_id, created_at) VALUES ($1, $2, $3, $4);"#,
+        )
+        .bind(post_id)
+        .bind(reaction)
+        .bind(user_id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
+        let count = post_reaction_count(&mut tx, post_id, reaction).await?;
+
+        tx.commit().await?;
+
+        Ok(Some(count))
+    }
+
+    async fn remove_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        if !is_visible_post(&mut tx, room_id, post_id).await? {
+            return Ok(None);
+        }
+
+        let result = sqlx::query(r#"DELETE FROM post_reaction WHERE post_id = $1 AND reaction = $2 AND user_id = $3;"#)
+            .bind(post_id)
+            .bind(reaction)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        let count = post_reaction_count(&mut tx, post_id, reaction).await?;
+
+        tx.commit().await?;
+
+        Ok(Some(count))
+    }
+
+    async fn get_post_reactions(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReaction>, anyhow::Error> {
+        let reactions = sqlx::query(
+            r#"SELECT r.post_id, r.reaction, r.user_id
+            FROM post_reaction AS r
+            JOIN post AS p ON p.id = r.post_id
+            WHERE r.post_id IN (SELECT value FROM json_each($1))
+              AND NOT p.is_deleted
+            ORDER BY r.post_id, r.created_at, r.user_id;"#,
+        )
+        .bind(serde_json::to_string(post_ids)?)
+        .try_map(|row: SqliteRow| {
+            Ok(dbm::PostReaction {
+                post_id: row.try_get("post_id")?,
+                reaction: row.try_get("reaction")?,
+                user_id: row.try_get("user_id")?,
+            })
+        })
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting post reactions")?;
+
+        Ok(reactions)
+    }
+
+    async fn get_emotes(&self, room_id: i32) -> Result<Vec<dbm::Emote>, anyhow::Error> {
+        let emotes = sqlx::query(r#"SELECT * FROM emote WHERE room_id = $1;"#)
+            .bind(room_id)
+            .try_map(|row| emote_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting emotes")?;
+
+        Ok(emotes)
+    }
+
+    async fn create_emote(&self, room_id: i32, emote: &dbm::NewEmote) -> Result<dbm::Emote, anyhow::Error> {
+        let emote = sqlx::query(
+            r#"INSERT INTO emote (created_at, updated_at, room_id, name, hash, ext)
+            VALUES ($1, $1, $2, $3, $4, $5)
+            ON CONFLICT (room_id, name) DO UPDATE SET hash = excluded.hash, ext = excluded.ext, updated_at = excluded.updated_at
+            RETURNING *;"#,
+        )
+        .bind(Utc::now())
+        .bind(room_id)
+        .bind(&emote.name)
+        .bind(&emote.hash)
+        .bind(&emote.ext)
+        .try_map(|row| emote_from_row(&row))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(emote)
+    }
+
+    async fn delete_emote(&self, room_id: i32, emote_id: i32) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM emote WHERE room_id = $1 AND id = $2;"#)
+            .bind(room_id)
+            .bind(emote_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn set_room_content(&self, room_id: i32, content: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE room SET content = $1, updated_at = $2 WHERE id = $3;"#)
+            .bind(content)
+            .bind(Utc::now())
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_room_playback_state(&self, room_id: i32, playback_state: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE room SET playback_state = $1, updated_at = $2 WHERE id = $3;"#)
+            .bind(playback_state)
+            .bind(Utc::now())
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn set_room_end_behavior(&self, room_id: i32, end_behavior: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE room SET end_behavior = $1, updated_at = $2 WHERE id = $3;"#)
+            .bind(end_behavior)
+            .bind(Utc::now())
+            .bind(room_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_queue(&self, room_id: i32) -> Result<Vec<dbm::QueueItem>, anyhow::Error> {
+        let queue = sqlx::query(r#"SELECT * FROM queue_item WHERE room_id = $1 ORDER BY position ASC, id ASC;"#)
+            .bind(room_id)
+            .try_map(|row| queue_item_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting queue")?;
+
+        Ok(queue)
+    }
+
+    async fn add_queue_item(&self, room_id: i32, content: &str) -> Result<dbm::QueueItem, anyhow::Error> {
+        // Append to the end of the queue
+        let item = sqlx::query(
+            r#"INSERT INTO queue_item (created_at, updated_at, room_id, position, content)
+            SELECT $1, $1, $2, COALESCE(MAX(position) + 1, 0), $3
+            FROM queue_item
+            WHERE room_id = $2
+            RETURNING *;"#,
+        )
+        .bind(Utc::now())
+        .bind(room_id)
+        .bind(content)
+        .try_map(|row| queue_item_from_row(&row))
+        .fetch_one(&self.pool)
+        .await
+        .context("Error adding queue item")?;
+
+        Ok(item)
+    }
+
+    async fn delete_queue_item(&self, room_id: i32, item_id: i64) -> Result<bool, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let position: Option<i32> =
+            sqlx::query_scalar(r#"DELETE FROM queue_item WHERE room_id = $1 AND id = $2 RETURNING position;"#)
+                .bind(room_id)
+                .bind(item_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        let Some(position) = position else {
+            return Ok(false);
+        };
+
+        // Close the gap left by the deleted item
+        sqlx::query(r#"UPDATE queue_item SET position = position - 1 WHERE room_id = $1 AND position > $2;"#)
+            .bind(room_id)
+            .bind(position)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn move_queue_item(&self, room_id: i32, item_id: i64, position: i32) -> Result<bool, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let old_position: Option<i32> =
+            sqlx::query_scalar(r#"SELECT position FROM queue_item WHERE room_id = $1 AND id = $2;"#)
+                .bind(room_id)
+                .bind(item_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+        let Some(old_position) = old_position else {
+            return Ok(false);
+        };
+
+        let max_position: i32 = sqlx::query_scalar(r#"SELECT MAX(position) FROM queue_item WHERE room_id = $1;"#)
+            .bind(room_id)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        let new_position = position.min(max_position).max(0);
+
+        if new_position > old_position {
+            sqlx::query(
+                r#"UPDATE queue_item SET position = position - 1
+                WHERE room_id = $1 AND position > $2 AND position <= $3;"#,
+            )
+            .bind(room_id)
+            .bind(old_position)
+            .bind(new_position)
+            .execute(&mut *tx)
+            .await?;
+        } else if new_position < old_position {
+            sqlx::query(
+                r#"UPDATE queue_item SET position = position + 1
+                WHERE room_id = $1 AND position >= $2 AND position < $3;"#,
+            )
+            .bind(room_id)
+            .bind(new_position)
+            .bind(old_position)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        sqlx::query(r#"UPDATE queue_item SET position = $1, updated_at = $2 WHERE id = $3;"#)
+            .bind(new_position)
+            .bind(Utc::now())
+            .bind(item_id)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn clear_queue(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM queue_item WHERE room_id = $1;"#)
+            .bind(room_id)
+            .execute(&self.pool)
+            .await
+            .context("Error clearing queue")?;
+
+        Ok(())
+    }
+
+    async fn get_bans(&self, room_id: i32) -> Result<Vec<dbm::Ban>, anyhow::Error> {
+        let bans = sqlx::query(
+            r#"SELECT * FROM ban
+            WHERE room_id = $1 AND (expires_at IS NULL OR expires_at > $2)
+            ORDER BY id DESC;"#,
+        )
+        .bind(room_id)
+        .bind(Utc::now())
+        .try_map(|row| ban_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting bans")?;
+
+        Ok(bans)
+    }
+
+    async fn get_active_ban(&self, room_id: i32, user_id: i64, ip: IpAddr) -> Result<Option<dbm::Ban>, anyhow::Error> {
+        let ban = sqlx::query(
+            r#"SELECT * FROM ban
+            WHERE room_id = $1
+              AND (user_id = $2 OR ip = $3)
+              AND (expires_at IS NULL OR expires_at > $4)
+            ORDER BY expires_at IS NOT NULL, expires_at DESC
+            LIMIT 1;"#,
+        )
+        .bind(room_id)
+        .bind(user_id)
+        .bind(ip.to_string())
+        .bind(Utc::now())
+        .try_map(|row| ban_from_row(&row))
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting active ban")?;
+
+        Ok(ban)
+    }
+
+    async fn create_ban_from_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reason: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<dbm::Ban>, anyhow::Error> {
+        let ban = sqlx::query(
+            r#"INSERT INTO ban (created_at, updated_at, room_id, post_id, user_id, ip, reason, expires_at)
+            SELECT $1, $1, p.room_id, p.id, p.user_id, p.ip, $2, $3
+            FROM post AS p
+            WHERE p.room_id = $4 AND p.id = $5 AND (p.user_id IS NOT NULL OR p.ip IS NOT NULL)
+            RETURNING *;"#,
+        )
+        .bind(Utc::now())
+        .bind(reason)
+        .bind(expires_at)
+        .bind(room_id)
+        .bind(post_id)
+        .try_map(|row| ban_from_row(&row))
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error creating ban")?;
+
+        Ok(ban)
+    }
+
+    async fn delete_ban(&self, room_id: i32, ban_id: i32) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM ban WHERE room_id = $1 AND id = $2;"#)
+            .bind(room_id)
+            .bind(ban_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_moderators(&self, room_id: i32) -> Result<Vec<dbm::Moderator>, anyhow::Error> {
+        let moderators = sqlx::query(r#"SELECT * FROM moderator WHERE room_id = $1 ORDER BY id ASC;"#)
+            .bind(room_id)
+            .try_map(|row| moderator_from_row(&row))
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting moderators")?;
+
+        Ok(moderators)
+    }
+
+    async fn get_moderator(&self, room_id: i32, moderator_id: i32) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = sqlx::query(r#"SELECT * FROM moderator WHERE room_id = $1 AND id = $2;"#)
+            .bind(room_id)
+            .bind(moderator_id)
+            .try_map(|row| moderator_from_row(&row))
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error getting moderator")?;
+
+        Ok(moderator)
+    }
+
+    async fn get_moderator_by_name(&self, room_id: i32, name: &str) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = sqlx::query(r#"SELECT * FROM moderator WHERE room_id = $1 AND name = $2;"#)
+            .bind(room_id)
+            .bind(name)
+            .try_map(|row| moderator_from_row(&row))
+            .fetch_optional(&self.pool)
+            .await
+            .context("Error getting moderator")?;
+
+        Ok(moderator)
+    }
+
+    async fn create_moderator(
+        &self,
+        room_id: i32,
+        name: &str,
+        key: &str,
+        permissions: &[String],
+    ) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = sqlx::query(
+            r#"INSERT INTO moderator (created_at, updated_at, room_id, name, key, permissions)
+            VALUES ($1, $1, $2, $3, $4, $5)
+            ON CONFLICT (room_id, name) DO NOTHING
+            RETURNING *;"#,
+        )
+        .bind(Utc::now())
+        .bind(room_id)
+        .bind(name)
+        .bind(key)
+        .bind(serde_json::to_string(permissions)?)
+        .try_map(|row| moderator_from_row(&row))
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error creating moderator")?;
+
+        Ok(moderator)
+    }
+
+    async fn delete_moderator(&self, room_id: i32, moderator_id: i32) -> Result<bool, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let result = sqlx::query(r#"DELETE FROM moderator WHERE room_id = $1 AND id = $2;"#)
+            .bind(room_id)
+            .bind(moderator_id)
+            .execute(&mut *tx)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // Revoke all sessions belonging to the moderator
+        sqlx::query(
+            r#"DELETE FROM refresh_token
+            WHERE room_id = $1
+              AND json_extract(claims, '$.level') = 'moderator'
+              AND json_extract(claims, '$.moderator_id') = $2;"#,
+        )
+        .bind(room_id)
+        .bind(moderator_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn update_post_images(&self, hash: &str, ext: &str, tn_ext: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE image SET ext = $1, tn_ext = $2, updated_at = $3 WHERE hash = $4;"#)
+            .bind(ext)
+            .bind(tn_ext)
+            .bind(Utc::now())
+            .bind(hash)
+            .execute(&self.pool)
+            .await
+            .context("Error updating post images")?;
+
+        Ok(())
+    }
+
+    async fn update_emote_images(&self, hash: &str, ext: &str) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"UPDATE emote SET ext = $1, updated_at = $2 WHERE hash = $3;"#)
+            .bind(ext)
+            .bind(Utc::now())
+            .bind(hash)
+            .execute(&self.pool)
+            .await
+            .context("Error updating emote images")?;
+
+        Ok(())
+    }
+
+    async fn generate_user_id(&self) -> Result<i64, anyhow::Error> {
+        next_sequence_value(&self.pool, "user_id")
+            .await
+            .context("Error generating user id")
+    }
+
+    async fn create_refresh_token(&self, claims: &str, room_id: Option<i32>) -> Result<Uuid, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        // Generate new refresh token with new family
+        let family = next_sequence_value(&mut *tx, "refresh_token_family").await?;
+        let token = insert_refresh_token(&mut tx, family, claims, room_id).await?;
+
+        tx.commit().await?;
+
+        Ok(token)
+    }
+
+    async fn refresh_refresh_token(&self, token: Uuid) -> Result<RefreshRefreshTokenResult, anyhow::Error> {
+        let mut tx = self.begin().await?;
+
+        let none = RefreshRefreshTokenResult {
+            token: None,
+            claims: None,
+        };
+
+        let refresh_token =
+            sqlx::query(r#"SELECT id, family, used, expires_at, claims, room_id FROM refresh_token WHERE token = $1;"#)
+                .bind(token.to_string())
+                .try_map(|row: SqliteRow| {
+                    Ok((
+                        row.try_get::<i64, _>("id")?,
+                        row.try_get::<i64, _>("family")?,
+                        row.try_get::<bool, _>("used")?,
+                        row.try_get::<DateTime<Utc>, _>("expires_at")?,
+                        row.try_get::<String, _>("claims")?,
+                        row.try_get::<Option<i32>, _>("room_id")?,
+                    ))
+                })
+                .fetch_optional(&mut *tx)
+                .await
+                .context("Error refreshing refresh token")?;
+
+        // Check if exists
+        let Some((id, family, used, expires_at, claims, room_id)) = refresh_token else {
+            return Ok(none);
+        };
+
+        // Check if already used.
+        // If a token that has already been rotated is presented again,
+        // it has likely been stolen, so revoke the whole family.
+        if used {
+            sqlx::query(r#"DELETE FROM refresh_token WHERE family = $1;"#)
+                .bind(family)
+                .execute(&mut *tx)
+                .await?;
+
+            tx.commit().await?;
+
+            return Ok(none);
+        }
+
+        // Check if expired
+        if expires_at < Utc::now() {
+            return Ok(none);
+        }
+
+        // Mark token as used
+        sqlx::query(r#"UPDATE refresh_token SET used = true, updated_at = $1 WHERE id = $2;"#)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+
+        // Generate new refresh token
+        let new_token = insert_refresh_token(&mut tx, family, &claims, room_id).await?;
+
+        tx.commit().await?;
+
+        Ok(RefreshRefreshTokenResult {
+            token: Some(new_token),
+            claims: Some(claims),
+        })
+    }
+
+    async fn revoke_room_refresh_tokens(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query(r#"DELETE FROM refresh_token WHERE room_id = $1;"#)
+            .bind(room_id)
+            .execute(&self.pool)
+            .await
+            .context("Error revoking refresh tokens")?;
+
+        Ok(())
+    }
+
+    async fn revoke_refresh_token_family(&self, token: Uuid) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(
+            r#"DELETE FROM refresh_token
+            WHERE family = (SELECT family FROM refresh_token WHERE token = $1);"#,
+        )
+        .bind(token.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_room_sessions(&self, room_id: i32) -> Result<Vec<dbm::Session>, anyhow::Error> {
+        let sessions = sqlx::query(
+            r#"SELECT
+              t.family,
+              (SELECT MIN(f.created_at) FROM refresh_token AS f WHERE f.family = t.family) AS created_at,
+              t.created_at AS refreshed_at,
+              t.expires_at,
+              t.claims
+            FROM refresh_token AS t
+            WHERE t.room_id = $1 AND NOT t.used AND t.expires_at >= $2
+            ORDER BY t.created_at DESC;"#,
+        )
+        .bind(room_id)
+        .bind(Utc::now())
+        .try_map(|row| session_from_row(&row))
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting sessions")?;
+
+        Ok(sessions)
+    }
+
+    async fn revoke_room_session(&self, room_id: i32, family: i64) -> Result<bool, anyhow::Error> {
+        let result = sqlx::query(r#"DELETE FROM refresh_token WHERE room_id = $1 AND family = $2;"#)
+            .bind(room_id)
+            .bind(family)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+async fn insert_refresh_token(
+    tx: &mut sqlx::Transaction<'static, sqlx::Sqlite>,
+    family: i64,
+    claims: &str,
+    room_id: Option<i32>,
+) -> Result<Uuid, anyhow::Error> {
+    let now = Utc::now();
+    let token = Uuid::new_v4();
+
+    sqlx::query(
+        r#"INSERT INTO refresh_token (created_at, updated_at, token, family, expires_at, claims, room_id)
+        VALUES ($1, $1, $2, $3, $4, $5, $6);"#,
+    )
+    .bind(now)
+    .bind(token.to_string())
+    .bind(family)
+    .bind(now + Duration::days(REFRESH_TOKEN_LIFETIME_DAYS))
+    .bind(claims)
+    .bind(room_id)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(token)
+}
+
+async fn is_visible_post(
+    tx: &mut sqlx::Transaction<'static, sqlx::Sqlite>,
+    room_id: i32,
+    post_id: i64,
+) -> Result<bool, anyhow::Error> {
+    let exists =
+        sqlx::query_scalar(r#"SELECT EXISTS (SELECT 1 FROM post WHERE room_id = $1 AND id = $2 AND NOT is_deleted);"#)
+            .bind(room_id)
+            .bind(post_id)
+            .fetch_one(&mut **tx)
+            .await?;
+
+    Ok(exists)
+}
+
+async fn post_reaction_count(
+    tx: &mut sqlx::Transaction<'static, sqlx::Sqlite>,
+    post_id: i64,
+    reaction: &str,
+) -> Result<i64, anyhow::Error> {
+    let count = sqlx::query_scalar(r#"SELECT count(*) FROM post_reaction WHERE post_id = $1 AND reaction = $2;"#)
+        .bind(post_id)
+        .bind(reaction)
+        .fetch_one(&mut **tx)
+        .await?;
+
+    Ok(count)
+}
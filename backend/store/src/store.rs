@@ -1,5 +1,8 @@
+use std::net::IpAddr;
+
 use anyhow::Context;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::models::{self as dbm, RefreshRefreshTokenResult};
@@ -10,11 +13,63 @@ pub trait AriaStore: Send + Sync {
 
     async fn get_recent_posts(&self, room_id: i32, count: i32) -> Result<Vec<dbm::PostAndImage>, anyhow::Error>;
 
+    async fn get_posts(
+        &self,
+        room_id: i32,
+        before_id: Option<i64>,
+        count: i32,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error>;
+
+    async fn search_posts(
+        &self,
+        room_id: i32,
+        search: &dbm::PostSearch,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error>;
+
     async fn get_room(&self, room_id: i32) -> Result<Option<dbm::Room>, anyhow::Error>;
 
     async fn get_room_by_name(&self, name: &str) -> Result<Option<dbm::Room>, anyhow::Error>;
 
-    async fn create_room(&self, name: &str, password: &str) -> Result<dbm::Room, anyhow::Error>;
+    async fn create_room(
+        &self,
+        name: &str,
+        password: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<dbm::Room, anyhow::Error>;
+
+    async fn extend_room_expiry(&self, room_id: i32, expires_at: DateTime<Utc>) -> Result<(), anyhow::Error>;
+
+    async fn delete_expired_rooms(&self) -> Result<Vec<i32>, anyhow::Error>;
+
+    /// Make all rooms never expire
+    async fn clear_room_expiry(&self) -> Result<(), anyhow::Error>;
+
+    async fn set_room_password(&self, room_id: i32, password: &str) -> Result<(), anyhow::Error>;
+
+    /// Set the room password only if it is still `old_password`
+    async fn replace_room_password(
+        &self,
+        room_id: i32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, anyhow::Error>;
+
+    async fn get_rooms(&self) -> Result<Vec<dbm::RoomSummary>, anyhow::Error>;
+
+    async fn delete_room(&self, room_id: i32) -> Result<bool, anyhow::Error>;
+
+    async fn purge_room_posts(&self, room_id: i32) -> Result<Vec<i64>, anyhow::Error>;
+
+    /// Get the images of all posts in a room
+    async fn get_room_images(&self, room_id: i32) -> Result<Vec<dbm::Image>, anyhow::Error>;
+
+    /// Get the images of all posts in rooms that have expired
+    async fn get_expired_room_images(&self) -> Result<Vec<dbm::Image>, anyhow::Error>;
+
+    /// Get which of the specified image hashes are still used by any post
+    async fn get_used_image_hashes(&self, hashes: &[String]) -> Result<Vec<String>, anyhow::Error>;
+
+    async fn get_instance_stats(&self) -> Result<dbm::InstanceStats, anyhow::Error>;
 
     async fn create_post(
         &self,
@@ -23,6 +78,15 @@ pub trait AriaStore: Send + Sync {
         image: Option<&dbm::NewImage>,
     ) -> Result<dbm::PostAndImage, anyhow::Error>;
 
+    /// Create a post with the specified timestamp, such as when importing it from another instance
+    async fn import_post(
+        &self,
+        room_id: i32,
+        post: &dbm::NewPost,
+        image: Option<&dbm::NewImage>,
+        created_at: DateTime<Utc>,
+    ) -> Result<dbm::PostAndImage, anyhow::Error>;
+
     async fn delete_post(
         &self,
         room_id: i32,
@@ -31,6 +95,65 @@ pub trait AriaStore: Send + Sync {
         is_admin: bool,
     ) -> Result<bool, anyhow::Error>;
 
+    /// Replace the comment of a post, keeping the previous comment as a revision.
+    /// Unless `is_admin` is set, only the poster can edit a post, and only if it was posted after `editable_since`.
+    /// Any replies recorded for the post are removed, as its quote links may have changed.
+    async fn edit_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+        comment: Option<&str>,
+        editable_since: DateTime<Utc>,
+    ) -> Result<Option<dbm::PostAndImage>, anyhow::Error>;
+
+    /// Get the previous comments of an edited post.
+    /// Unless `is_admin` is set, only the poster can view them.
+    async fn get_post_revisions(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<Vec<dbm::PostRevision>, anyhow::Error>;
+
+    /// Record a post as replying to other posts.
+    /// Posts that do not exist in the same room are ignored, and only the replies created are returned.
+    async fn create_post_replies(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reply_to_ids: &[i64],
+    ) -> Result<Vec<dbm::PostReply>, anyhow::Error>;
+
+    /// Get all replies to or from any of the specified posts
+    async fn get_post_replies(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReply>, anyhow::Error>;
+
+    /// Add a user's reaction to a post, unless it has already been added.
+    /// Returns the number of users who have reacted with the reaction, or `None` if the post was not found.
+    async fn add_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error>;
+
+    /// Remove a user's reaction to a post.
+    /// Returns the number of users who have reacted with the reaction,
+    /// or `None` if the post was not found or the user had not reacted with the reaction.
+    async fn remove_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error>;
+
+    /// Get all reactions to any of the specified posts, in the order they were added
+    async fn get_post_reactions(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReaction>, anyhow::Error>;
+
     async fn get_emotes(&self, room_id: i32) -> Result<Vec<dbm::Emote>, anyhow::Error>;
 
     async fn create_emote(&self, room_id: i32, emote: &dbm::NewEmote) -> Result<dbm::Emote, anyhow::Error>;
@@ -41,15 +164,65 @@ pub trait AriaStore: Send + Sync {
 
     async fn set_room_playback_state(&self, room_id: i32, playback_state: &str) -> Result<(), anyhow::Error>;
 
+    async fn set_room_end_behavior(&self, room_id: i32, end_behavior: &str) -> Result<(), anyhow::Error>;
+
+    async fn get_queue(&self, room_id: i32) -> Result<Vec<dbm::QueueItem>, anyhow::Error>;
+
+    async fn add_queue_item(&self, room_id: i32, content: &str) -> Result<dbm::QueueItem, anyhow::Error>;
+
+    async fn delete_queue_item(&self, room_id: i32, item_id: i64) -> Result<bool, anyhow::Error>;
+
+    async fn move_queue_item(&self, room_id: i32, item_id: i64, position: i32) -> Result<bool, anyhow::Error>;
+
+    async fn clear_queue(&self, room_id: i32) -> Result<(), anyhow::Error>;
+
+    async fn get_bans(&self, room_id: i32) -> Result<Vec<dbm::Ban>, anyhow::Error>;
+
+    async fn get_active_ban(&self, room_id: i32, user_id: i64, ip: IpAddr) -> Result<Option<dbm::Ban>, anyhow::Error>;
+
+    async fn create_ban_from_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reason: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<dbm::Ban>, anyhow::Error>;
+
+    async fn delete_ban(&self, room_id: i32, ban_id: i32) -> Result<bool, anyhow::Error>;
+
+    async fn get_moderators(&self, room_id: i32) -> Result<Vec<dbm::Moderator>, anyhow::Error>;
+
+    async fn get_moderator(&self, room_id: i32, moderator_id: i32) -> Result<Option<dbm::Moderator>, anyhow::Error>;
+
+    async fn get_moderator_by_name(&self, room_id: i32, name: &str) -> Result<Option<dbm::Moderator>, anyhow::Error>;
+
+    async fn create_moderator(
+        &self,
+        room_id: i32,
+        name: &str,
+        key: &str,
+        permissions: &[String],
+    ) -> Result<Option<dbm::Moderator>, anyhow::Error>;
+
+    async fn delete_moderator(&self, room_id: i32, moderator_id: i32) -> Result<bool, anyhow::Error>;
+
     async fn update_post_images(&self, hash: &str, ext: &str, tn_ext: &str) -> Result<(), anyhow::Error>;
 
     async fn update_emote_images(&self, hash: &str, ext: &str) -> Result<(), anyhow::Error>;
 
     async fn generate_user_id(&self) -> Result<i64, anyhow::Error>;
 
-    async fn create_refresh_token(&self, claims: &str) -> Result<Uuid, anyhow::Error>;
+    async fn create_refresh_token(&self, claims: &str, room_id: Option<i32>) -> Result<Uuid, anyhow::Error>;
 
     async fn refresh_refresh_token(&self, token: Uuid) -> Result<RefreshRefreshTokenResult, anyhow::Error>;
+
+    async fn revoke_room_refresh_tokens(&self, room_id: i32) -> Result<(), anyhow::Error>;
+
+    async fn revoke_refresh_token_family(&self, token: Uuid) -> Result<bool, anyhow::Error>;
+
+    async fn get_room_sessions(&self, room_id: i32) -> Result<Vec<dbm::Session>, anyhow::Error>;
+
+    async fn revoke_room_session(&self, room_id: i32, family: i64) -> Result<bool, anyhow::Error>;
 }
 
 pub struct PgStore {
@@ -89,11 +262,63 @@ impl AriaStore for PgStore {
         Ok(posts)
     }
 
+    async fn get_posts(
+        &self,
+        room_id: i32,
+        before_id: Option<i64>,
+        count: i32,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        let mut posts = sqlx::query_as_unchecked!(
+            dbm::PostAndImage,
+            r#"SELECT post, image FROM get_posts($1, $2, $3);"#,
+            room_id,
+            before_id,
+            count,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error getting posts")?;
+
+        // Reverse posts, as they are returned in reverse order
+        posts.reverse();
+
+        Ok(posts)
+    }
+
+    async fn search_posts(
+        &self,
+        room_id: i32,
+        search: &dbm::PostSearch,
+    ) -> Result<Vec<dbm::PostAndImage>, anyhow::Error> {
+        let posts = sqlx::query_as_unchecked!(
+            dbm::PostAndImage,
+            r#"SELECT post, image FROM search_posts($1, $2, $3, $4, $5, $6, $7, $8, $9);"#,
+            room_id,
+            search.query,
+            search.from,
+            search.to,
+            search.has_image,
+            search.admin,
+            search.user_id,
+            search.before_id,
+            search.count,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Error searching posts")?;
+
+        Ok(posts)
+    }
+
     async fn get_room(&self, room_id: i32) -> Result<Option<dbm::Room>, anyhow::Error> {
-        let room = sqlx::query_as_unchecked!(dbm::Room, r#"SELECT * FROM room WHERE id = $1;"#, room_id)
-            .fetch_optional(&self.pool)
-            .await
-            .context("Error getting room")?;
+        let room = sqlx::query_as_unchecked!(
+            dbm::Room,
+            r#"SELECT * FROM room WHERE id = $1 AND expires_at > CURRENT_TIMESTAMP;"#,
+            room_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting room")?;
 
         Ok(room)
     }
@@ -107,13 +332,141 @@ impl AriaStore for PgStore {
         Ok(room)
     }
 
-    async fn create_room(&self, name: &str, password: &str) -> Result<dbm::Room, anyhow::Error> {
-        let room = sqlx::query_as_unchecked!(dbm::Room, r#"SELECT * FROM create_room($1, $2);"#, name, password)
+    async fn create_room(
+        &self,
+        name: &str,
+        password: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<dbm::Room, anyhow::Error> {
+        let room = sqlx::query_as_unchecked!(
+            dbm::Room,
+            r#"SELECT * FROM create_room($1, $2, $3);"#,
+            name,
+            password,
+            expires_at
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error creating room")?;
+
+        Ok(room)
+    }
+
+    async fn extend_room_expiry(&self, room_id: i32, expires_at: DateTime<Utc>) -> Result<(), anyhow::Error> {
+        sqlx::query_unchecked!(r#"SELECT extend_room_expiry($1, $2);"#, room_id, expires_at)
+            .execute(&self.pool)
+            .await
+            .context("Error extending room expiry")?;
+
+        Ok(())
+    }
+
+    async fn delete_expired_rooms(&self) -> Result<Vec<i32>, anyhow::Error> {
+        let room_ids = sqlx::query_scalar!(r#"SELECT * FROM delete_expired_rooms();"#)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error deleting expired rooms")?;
+
+        Ok(room_ids.into_iter().flatten().collect())
+    }
+
+    async fn clear_room_expiry(&self) -> Result<(), anyhow::Error> {
+        sqlx::query_unchecked!(r#"SELECT clear_room_expiry();"#)
+            .execute(&self.pool)
+            .await
+            .context("Error clearing room expiry")?;
+
+        Ok(())
+    }
+
+    async fn set_room_password(&self, room_id: i32, password: &str) -> Result<(), anyhow::Error> {
+        sqlx::query_unchecked!(r#"SELECT set_room_password($1, $2);"#, room_id, password)
+            .execute(&self.pool)
+            .await
+            .context("Error setting room password")?;
+
+        Ok(())
+    }
+
+    async fn replace_room_password(
+        &self,
+        room_id: i32,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(
+            r#"SELECT replace_room_password($1, $2, $3);"#,
+            room_id,
+            old_password,
+            new_password
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error replacing room password")?;
+
+        Ok(success.unwrap())
+    }
+
+    async fn get_rooms(&self) -> Result<Vec<dbm::RoomSummary>, anyhow::Error> {
+        let rooms = sqlx::query_as_unchecked!(dbm::RoomSummary, r#"SELECT * FROM get_rooms();"#)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting rooms")?;
+
+        Ok(rooms)
+    }
+
+    async fn delete_room(&self, room_id: i32) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT delete_room($1);"#, room_id)
             .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
+
+    async fn purge_room_posts(&self, room_id: i32) -> Result<Vec<i64>, anyhow::Error> {
+        let post_ids = sqlx::query_scalar!(r#"SELECT * FROM purge_room_posts($1);"#, room_id)
+            .fetch_all(&self.pool)
             .await
-            .context("Error creating room")?;
+            .context("Error purging posts")?;
 
-        Ok(room)
+        Ok(post_ids.into_iter().flatten().collect())
+    }
+
+    async fn get_room_images(&self, room_id: i32) -> Result<Vec<dbm::Image>, anyhow::Error> {
+        let images = sqlx::query_as_unchecked!(dbm::Image, r#"SELECT * FROM get_room_images($1);"#, room_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting room images")?;
+
+        Ok(images)
+    }
+
+    async fn get_expired_room_images(&self) -> Result<Vec<dbm::Image>, anyhow::Error> {
+        let images = sqlx::query_as_unchecked!(dbm::Image, r#"SELECT * FROM get_expired_room_images();"#)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting expired room images")?;
+
+        Ok(images)
+    }
+
+    async fn get_used_image_hashes(&self, hashes: &[String]) -> Result<Vec<String>, anyhow::Error> {
+        let hashes = sqlx::query_scalar!(r#"SELECT * FROM get_used_image_hashes($1);"#, hashes)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting used image hashes")?;
+
+        Ok(hashes.into_iter().flatten().collect())
+    }
+
+    async fn get_instance_stats(&self) -> Result<dbm::InstanceStats, anyhow::Error> {
+        let stats = sqlx::query_as_unchecked!(dbm::InstanceStats, r#"SELECT * FROM get_instance_stats();"#)
+            .fetch_one(&self.pool)
+            .await
+            .context("Error getting instance stats")?;
+
+        Ok(stats)
     }
 
     async fn create_post(
@@ -135,6 +488,27 @@ impl AriaStore for PgStore {
         Ok(post)
     }
 
+    async fn import_post(
+        &self,
+        room_id: i32,
+        post: &dbm::NewPost,
+        image: Option<&dbm::NewImage>,
+        created_at: DateTime<Utc>,
+    ) -> Result<dbm::PostAndImage, anyhow::Error> {
+        let post = sqlx::query_as_unchecked!(
+            dbm::PostAndImage,
+            r#"SELECT * FROM import_post($1, $2, $3, $4);"#,
+            room_id,
+            post,
+            image,
+            created_at,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
     async fn delete_post(
         &self,
         room_id: i32,
@@ -155,6 +529,128 @@ impl AriaStore for PgStore {
         Ok(success.unwrap())
     }
 
+    async fn edit_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+        comment: Option<&str>,
+        editable_since: DateTime<Utc>,
+    ) -> Result<Option<dbm::PostAndImage>, anyhow::Error> {
+        let post = sqlx::query_as_unchecked!(
+            dbm::PostAndImage,
+            r#"SELECT * FROM edit_post($1, $2, $3, $4, $5, $6);"#,
+            room_id,
+            post_id,
+            user_id,
+            is_admin,
+            comment,
+            editable_since,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(post)
+    }
+
+    async fn get_post_revisions(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        is_admin: bool,
+    ) -> Result<Vec<dbm::PostRevision>, anyhow::Error> {
+        let revisions = sqlx::query_as_unchecked!(
+            dbm::PostRevision,
+            r#"SELECT * FROM get_post_revisions($1, $2, $3, $4);"#,
+            room_id,
+            post_id,
+            user_id,
+            is_admin,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(revisions)
+    }
+
+    async fn create_post_replies(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reply_to_ids: &[i64],
+    ) -> Result<Vec<dbm::PostReply>, anyhow::Error> {
+        let replies = sqlx::query_as_unchecked!(
+            dbm::PostReply,
+            r#"SELECT * FROM create_post_replies($1, $2, $3);"#,
+            room_id,
+            post_id,
+            reply_to_ids,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(replies)
+    }
+
+    async fn get_post_replies(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReply>, anyhow::Error> {
+        let replies = sqlx::query_as_unchecked!(dbm::PostReply, r#"SELECT * FROM get_post_replies($1);"#, post_ids)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(replies)
+    }
+
+    async fn add_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT add_post_reaction($1, $2, $3, $4);"#,
+            room_id,
+            post_id,
+            user_id,
+            reaction,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn remove_post_reaction(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        user_id: i64,
+        reaction: &str,
+    ) -> Result<Option<i64>, anyhow::Error> {
+        let count = sqlx::query_scalar!(
+            r#"SELECT remove_post_reaction($1, $2, $3, $4);"#,
+            room_id,
+            post_id,
+            user_id,
+            reaction,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn get_post_reactions(&self, post_ids: &[i64]) -> Result<Vec<dbm::PostReaction>, anyhow::Error> {
+        let reactions =
+            sqlx::query_as_unchecked!(dbm::PostReaction, r#"SELECT * FROM get_post_reactions($1);"#, post_ids)
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(reactions)
+    }
+
     async fn get_emotes(&self, room_id: i32) -> Result<Vec<dbm::Emote>, anyhow::Error> {
         let emotes = sqlx::query_as_unchecked!(dbm::Emote, r#"SELECT * FROM get_emotes($1);"#, room_id)
             .fetch_all(&self.pool)
@@ -200,6 +696,182 @@ impl AriaStore for PgStore {
         Ok(())
     }
 
+    async fn set_room_end_behavior(&self, room_id: i32, end_behavior: &str) -> Result<(), anyhow::Error> {
+        sqlx::query_unchecked!(r#"SELECT set_room_end_behavior($1, $2);"#, room_id, end_behavior)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_queue(&self, room_id: i32) -> Result<Vec<dbm::QueueItem>, anyhow::Error> {
+        let queue = sqlx::query_as_unchecked!(dbm::QueueItem, r#"SELECT * FROM get_queue($1);"#, room_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting queue")?;
+
+        Ok(queue)
+    }
+
+    async fn add_queue_item(&self, room_id: i32, content: &str) -> Result<dbm::QueueItem, anyhow::Error> {
+        let item = sqlx::query_as_unchecked!(
+            dbm::QueueItem,
+            r#"SELECT * FROM add_queue_item($1, $2::json);"#,
+            room_id,
+            content
+        )
+        .fetch_one(&self.pool)
+        .await
+        .context("Error adding queue item")?;
+
+        Ok(item)
+    }
+
+    async fn delete_queue_item(&self, room_id: i32, item_id: i64) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT delete_queue_item($1, $2);"#, room_id, item_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
+
+    async fn move_queue_item(&self, room_id: i32, item_id: i64, position: i32) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT move_queue_item($1, $2, $3);"#, room_id, item_id, position)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
+
+    async fn clear_queue(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query_unchecked!(r#"SELECT clear_queue($1);"#, room_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_bans(&self, room_id: i32) -> Result<Vec<dbm::Ban>, anyhow::Error> {
+        let bans = sqlx::query_as_unchecked!(dbm::Ban, r#"SELECT * FROM get_bans($1);"#, room_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting bans")?;
+
+        Ok(bans)
+    }
+
+    async fn get_active_ban(&self, room_id: i32, user_id: i64, ip: IpAddr) -> Result<Option<dbm::Ban>, anyhow::Error> {
+        let ban = sqlx::query_as_unchecked!(
+            dbm::Ban,
+            r#"SELECT * FROM get_active_ban($1, $2, $3);"#,
+            room_id,
+            user_id,
+            ip
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting ban")?;
+
+        Ok(ban)
+    }
+
+    async fn create_ban_from_post(
+        &self,
+        room_id: i32,
+        post_id: i64,
+        reason: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<Option<dbm::Ban>, anyhow::Error> {
+        let ban = sqlx::query_as_unchecked!(
+            dbm::Ban,
+            r#"SELECT * FROM create_ban_from_post($1, $2, $3, $4);"#,
+            room_id,
+            post_id,
+            reason,
+            expires_at
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error creating ban")?;
+
+        Ok(ban)
+    }
+
+    async fn delete_ban(&self, room_id: i32, ban_id: i32) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT delete_ban($1, $2);"#, room_id, ban_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
+
+    async fn get_moderators(&self, room_id: i32) -> Result<Vec<dbm::Moderator>, anyhow::Error> {
+        let moderators = sqlx::query_as_unchecked!(dbm::Moderator, r#"SELECT * FROM get_moderators($1);"#, room_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting moderators")?;
+
+        Ok(moderators)
+    }
+
+    async fn get_moderator(&self, room_id: i32, moderator_id: i32) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = sqlx::query_as_unchecked!(
+            dbm::Moderator,
+            r#"SELECT * FROM get_moderator($1, $2);"#,
+            room_id,
+            moderator_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting moderator")?;
+
+        Ok(moderator)
+    }
+
+    async fn get_moderator_by_name(&self, room_id: i32, name: &str) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = sqlx::query_as_unchecked!(
+            dbm::Moderator,
+            r#"SELECT * FROM get_moderator_by_name($1, $2);"#,
+            room_id,
+            name
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error getting moderator")?;
+
+        Ok(moderator)
+    }
+
+    async fn create_moderator(
+        &self,
+        room_id: i32,
+        name: &str,
+        key: &str,
+        permissions: &[String],
+    ) -> Result<Option<dbm::Moderator>, anyhow::Error> {
+        let moderator = sqlx::query_as_unchecked!(
+            dbm::Moderator,
+            r#"SELECT * FROM create_moderator($1, $2, $3, $4);"#,
+            room_id,
+            name,
+            key,
+            permissions
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .context("Error creating moderator")?;
+
+        Ok(moderator)
+    }
+
+    async fn delete_moderator(&self, room_id: i32, moderator_id: i32) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT delete_moderator($1, $2);"#, room_id, moderator_id)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
+
     async fn update_post_images(&self, hash: &str, ext: &str, tn_ext: &str) -> Result<(), anyhow::Error> {
         sqlx::query_unchecked!(r#"SELECT update_post_images($1, $2, $3);"#, hash, ext, tn_ext)
             .execute(&self.pool)
@@ -225,8 +897,8 @@ impl AriaStore for PgStore {
         Ok(new_user_id)
     }
 
-    async fn create_refresh_token(&self, claims: &str) -> Result<Uuid, anyhow::Error> {
-        let token = sqlx::query_scalar!(r#"SELECT create_refresh_token($1);"#, claims)
+    async fn create_refresh_token(&self, claims: &str, room_id: Option<i32>) -> Result<Uuid, anyhow::Error> {
+        let token = sqlx::query_scalar!(r#"SELECT create_refresh_token($1, $2);"#, claims, room_id)
             .fetch_one(&self.pool)
             .await?
             .context("Error creating refresh token")?;
@@ -246,4 +918,38 @@ impl AriaStore for PgStore {
 
         Ok(result)
     }
+
+    async fn revoke_room_refresh_tokens(&self, room_id: i32) -> Result<(), anyhow::Error> {
+        sqlx::query_unchecked!(r#"SELECT revoke_room_refresh_tokens($1);"#, room_id)
+            .execute(&self.pool)
+            .await
+            .context("Error revoking refresh tokens")?;
+
+        Ok(())
+    }
+
+    async fn revoke_refresh_token_family(&self, token: Uuid) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT revoke_refresh_token_family($1);"#, token)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
+
+    async fn get_room_sessions(&self, room_id: i32) -> Result<Vec<dbm::Session>, anyhow::Error> {
+        let sessions = sqlx::query_as_unchecked!(dbm::Session, r#"SELECT * FROM get_room_sessions($1);"#, room_id)
+            .fetch_all(&self.pool)
+            .await
+            .context("Error getting sessions")?;
+
+        Ok(sessions)
+    }
+
+    async fn revoke_room_session(&self, room_id: i32, family: i64) -> Result<bool, anyhow::Error> {
+        let success = sqlx::query_scalar!(r#"SELECT revoke_room_session($1, $2);"#, room_id, family)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(success.unwrap())
+    }
 }
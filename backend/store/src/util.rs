@@ -0,0 +1,12 @@
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// How long a refresh token stays valid after being issued
+pub(crate) const REFRESH_TOKEN_LIFETIME_DAYS: i64 = 30;
+
+/// Expiry timestamp used for rooms that never expire
+pub(crate) fn never_expires() -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(9999, 12, 31)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap()
+        .and_utc()
+}
@@ -25,7 +25,7 @@ export default defineConfig({
         target: "http://localhost:3000",
       },
       "/aria-ws": {
-        target: "ws://localhost:3001",
+        target: "ws://localhost:3000",
         ws: true,
         xfwd: true,
       },
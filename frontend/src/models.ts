@@ -21,18 +21,64 @@ export interface Image {
   tn_url: string;
 }
 
+export type CommentToken =
+  | { type: "text"; text: string }
+  | { type: "newline" }
+  | { type: "link"; url: string }
+  | { type: "emote"; name: string }
+  | { type: "quote_link"; post_id: number }
+  | { type: "greentext"; text: string }
+  | { type: "spoiler"; children: CommentToken[] }
+  | { type: "code"; text: string; block: boolean };
+
 export interface Post {
   id: number;
   name: string;
   comment: string;
+  tokens?: CommentToken[];
   image?: Image;
   posted: string;
+  edited?: string;
   you: boolean;
   admin: boolean;
+  replies_to?: number[];
+  replied_by?: number[];
+  replies_to_you?: boolean;
+  reactions?: Reaction[];
 
   isDeleted: boolean;
 }
 
+export interface Reaction {
+  reaction: string;
+  count: number;
+  you_reacted?: boolean;
+}
+
+export interface ReactionUpdate {
+  post_id: number;
+  reaction: string;
+  count: number;
+  you_reacted?: boolean;
+}
+
+export interface Presence {
+  viewers: number;
+  members: PresenceMember[];
+}
+
+export interface PresenceMember {
+  user_id: number;
+  name?: string;
+  admin?: boolean;
+  master?: boolean;
+}
+
+export interface Typist {
+  user_id: number;
+  name?: string;
+}
+
 export interface Emote {
   id: number;
   name: string;
@@ -1,3 +1,6 @@
+// WebSocket protocol version requested when joining a room
+export const PROTOCOL_VERSION = 2;
+
 export interface Message<T> {
   msg: string;
   data: T;
@@ -18,8 +21,7 @@ export class AriaWebSocket {
   ) {}
 
   public send<T>(msg: string, data?: T) {
-    const _data = data ? JSON.stringify(data) : "";
-    this.ws?.send(`${msg}|${_data}`);
+    this.ws?.send(JSON.stringify({ type: msg, data }));
   }
 
   public create_listener(): AriaWsListener {
@@ -129,10 +131,17 @@ export class AriaWsListener {
 }
 
 function parseMsg<T>(msg: string): Message<T> {
-  const parts = msg.split("|", 2);
-  const data = JSON.parse(parts[1]);
+  // Messages use version 1 framing until a protocol version has been negotiated by joining a room
+  if (!msg.startsWith("{")) {
+    const ix = msg.indexOf("|");
+    const data = JSON.parse(msg.substring(ix + 1));
+
+    return { msg: msg.substring(0, ix), data };
+  }
+
+  const { type, data } = JSON.parse(msg);
 
-  return { msg: parts[0], data };
+  return { msg: type, data };
 }
 
 function getTimestamp(): number {
@@ -1,11 +1,11 @@
 import axios from "axios";
 import { defineStore } from "pinia";
-import { computed, ref } from "vue";
+import { computed, ref, watch } from "vue";
 
 import { useMainStore } from "./main";
 import { useRoomStore } from "./room";
 
-import type { Post } from "@/models";
+import type { Post, Typist } from "@/models";
 import { delay } from "@/utils/delay";
 
 export interface NewPost {
@@ -16,6 +16,10 @@ export interface NewPost {
 
 const MAX_POSTS = 200;
 
+// Interval at which typing is reported while typing.
+// Must be shorter than the time after which the server considers typing to have stopped.
+const TYPING_INTERVAL = 3000;
+
 export const useChatStore = defineStore("chat", () => {
   const mainStore = useMainStore();
   const roomStore = useRoomStore();
@@ -34,6 +38,9 @@ export const useChatStore = defineStore("chat", () => {
 
   const submitOnCooldown = ref(false);
 
+  const typists = ref<Typist[]>([]);
+  let lastTypingAt = 0;
+
   const canSubmitPost = computed(() => {
     // Prevent duplicate submits
     if (posting.value) {
@@ -154,7 +161,31 @@ export const useChatStore = defineStore("chat", () => {
     const ws = roomStore.ws;
     const ws_listener = roomStore.createWebsocketListener();
 
+    watch(
+      () => newPost.value.comment,
+      (comment) => {
+        const now = Date.now();
+
+        if (comment) {
+          if (now - lastTypingAt >= TYPING_INTERVAL) {
+            lastTypingAt = now;
+            ws.send("typing-start");
+          }
+        } else if (lastTypingAt > 0) {
+          lastTypingAt = 0;
+          ws.send("typing-stop");
+        }
+      },
+    );
+
+    ws_listener.on("typing", (_typists: Typist[]) => {
+      typists.value = _typists;
+    });
+
     ws_listener.on("joined", async () => {
+      typists.value = [];
+      lastTypingAt = 0;
+
       const last_post_id = posts.value[posts.value.length - 1]?.id || 0;
 
       ws.send("get-recent-posts", { since: last_post_id });
@@ -184,6 +215,16 @@ export const useChatStore = defineStore("chat", () => {
       post.isDeleted = true;
     });
 
+    ws_listener.on("delete-posts", (postIds: number[]) => {
+      const deletedIds = new Set(postIds);
+
+      for (const post of posts.value) {
+        if (deletedIds.has(post.id)) {
+          post.isDeleted = true;
+        }
+      }
+    });
+
     ws_listener.on("oldposts", (__posts: Post[]) => {
       const _posts = posts.value;
       let newPosts: Post[];
@@ -214,6 +255,7 @@ export const useChatStore = defineStore("chat", () => {
     postingError,
     postingCooldown,
     submitOnCooldown,
+    typists,
     clearNewPost,
     submitPost,
     isInitialized,
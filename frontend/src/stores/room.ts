@@ -4,8 +4,8 @@ import { computed, ref, watch } from "vue";
 
 import { useMainStore } from "./main";
 
-import type { Content, Emote, Room } from "@/models";
-import { AriaWebSocket } from "@/services/websocket";
+import type { Content, Emote, Presence, Room } from "@/models";
+import { AriaWebSocket, PROTOCOL_VERSION } from "@/services/websocket";
 import { DEFAULT_ROOM_SETTINGS, type RoomSettings } from "@/settings";
 import { tryParseJson } from "@/utils/json";
 import { getTimestamp } from "@/utils/timestamp";
@@ -52,6 +52,7 @@ export const useRoomStore = defineStore("room", () => {
   const content = ref<Content>();
   const isConnected = ref(false);
   const isMaster = ref(false);
+  const presence = ref<Presence>({ viewers: 0, members: [] });
 
   const serverPlaybackStateTimestamp = ref(0);
   const serverPlaybackState = ref<PlaybackState>({
@@ -315,11 +316,17 @@ export const useRoomStore = defineStore("room", () => {
       async () => {
         isConnected.value = true;
 
-        ws.send("join", { room: name.value, user: await mainStore.getUser() });
+        ws.send("join", {
+          room: name.value,
+          user: await mainStore.getUser(),
+          name: settings.value.chatName || undefined,
+          protocol: PROTOCOL_VERSION,
+        });
       },
       async () => {
         isConnected.value = false;
         isMaster.value = false;
+        presence.value = { viewers: 0, members: [] };
       },
     );
 
@@ -368,6 +375,10 @@ export const useRoomStore = defineStore("room", () => {
       isMaster.value = false;
     });
 
+    ws_listener.on("presence", async (_presence: Presence) => {
+      presence.value = _presence;
+    });
+
     ws_listener.on("playbackstate", async (ps: PlaybackState) => {
       serverPlaybackStateTimestamp.value = getTimestamp();
 
@@ -396,6 +407,7 @@ export const useRoomStore = defineStore("room", () => {
     serverPlaybackStateTimestamp,
     isConnected,
     isMaster,
+    presence,
     ws,
     claimRoom,
     loadRoom,